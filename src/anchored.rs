@@ -0,0 +1,89 @@
+use serde::de;
+use serde::ser;
+
+/// Name of the newtype struct through which `Anchored` is recognised by this crate's
+/// `Serializer` and `Deserializer`.
+pub(crate) const ANCHORED_TOKEN: &str = "$serde_xml_rs::Anchored";
+
+/// An item of a `$unknown`, `$comment` or `$pi` field, along with the field of the known child
+/// element it was found before, so that it is written back at the same position.
+///
+/// Plain strings collected into these fields are all written where the field is declared.
+/// `Anchored` items are written before the field named by `before` instead, or after all fields
+/// if `before` is `None`. Since fields are written in declaration order, this only works if the
+/// collecting field is declared before the fields its items are anchored to; it is best declared
+/// first.
+///
+/// ```rust
+/// # use serde::{Deserialize, Serialize};
+/// # use serde_xml_rs::{from_str, to_string, Anchored};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct Channel {
+///     #[serde(rename = "$unknown", default)]
+///     unknown: Vec<Anchored<String>>,
+///     name: String,
+///     enabled: bool,
+/// }
+///
+/// # fn main() {
+/// let src = r#"<?xml version="1.0" encoding="UTF-8"?><Channel><name>Front door</name><codec>h264</codec><enabled>true</enabled></Channel>"#;
+/// let channel: Channel = from_str(src).unwrap();
+/// assert_eq!(channel.unknown[0].value, "<codec>h264</codec>");
+/// assert_eq!(channel.unknown[0].before.as_deref(), Some("enabled"));
+/// assert_eq!(to_string(&channel).unwrap(), src);
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Anchored<T> {
+    pub value: T,
+    /// Name of the field the item is written before, or `None` to write it after all fields.
+    pub before: Option<String>,
+}
+
+impl<T> Anchored<T> {
+    pub fn new(value: T, before: Option<String>) -> Self {
+        Anchored { value, before }
+    }
+}
+
+impl<T> From<T> for Anchored<T> {
+    fn from(value: T) -> Self {
+        Anchored::new(value, None)
+    }
+}
+
+impl<T: ser::Serialize> ser::Serialize for Anchored<T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // An empty name stands for `None`, since it can't be the name of a field
+        let before = self.before.as_deref().unwrap_or_default();
+        serializer.serialize_newtype_struct(ANCHORED_TOKEN, &(before, &self.value))
+    }
+}
+
+impl<'de, T: de::Deserialize<'de>> de::Deserialize<'de> for Anchored<T> {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AnchoredVisitor<T>(std::marker::PhantomData<T>);
+
+        impl<'de, T: de::Deserialize<'de>> de::Visitor<'de> for AnchoredVisitor<T> {
+            type Value = Anchored<T>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a value along with the name of the field it precedes")
+            }
+
+            fn visit_newtype_struct<D: de::Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Anchored<T>, D::Error> {
+                let (before, value): (String, T) = de::Deserialize::deserialize(deserializer)?;
+                Ok(Anchored::new(
+                    value,
+                    Some(before).filter(|name| !name.is_empty()),
+                ))
+            }
+        }
+
+        deserializer
+            .deserialize_newtype_struct(ANCHORED_TOKEN, AnchoredVisitor(std::marker::PhantomData))
+    }
+}
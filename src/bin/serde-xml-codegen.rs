@@ -0,0 +1,49 @@
+//! Generates Rust types for `serde-xml-rs` from an XSD or from sample documents.
+//!
+//! ```text
+//! serde-xml-codegen --xsd schema.xsd > types.rs
+//! serde-xml-codegen sample1.xml sample2.xml > types.rs
+//! ```
+//!
+//! Samples are read from the standard input if no file is given.
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::process;
+
+use serde_xml_rs::{codegen, XsdSchema};
+
+const USAGE: &str = "usage: serde-xml-codegen [--xsd SCHEMA | SAMPLE...]";
+
+fn run(args: &[String]) -> Result<String, Box<dyn std::error::Error>> {
+    match args {
+        [flag, path] if flag == "--xsd" => {
+            let schema = XsdSchema::from_reader(BufReader::new(File::open(path)?))?;
+            Ok(codegen::from_xsd(&schema)?)
+        }
+        [] => Ok(codegen::from_samples(vec![io::stdin()])?),
+        paths if paths.iter().any(|path| path.starts_with('-')) => Err(USAGE.into()),
+        paths => {
+            let samples = paths
+                .iter()
+                .map(|path| Ok(BufReader::new(File::open(path)?)))
+                .collect::<io::Result<Vec<_>>>()?;
+            Ok(codegen::from_samples(samples)?)
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    match run(&args) {
+        Ok(code) => print!("{}", code),
+        Err(error) => {
+            eprintln!("serde-xml-codegen: {}", error);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Canonical XML (without comments): no XML declaration, empty elements written as a start and
+//! end tag, namespace declarations and then attributes in sorted order, and a fixed set of
+//! escapes. Two documents with the same canonical form carry the same information.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use xml::namespace::{Namespace, NS_XMLNS_PREFIX, NS_XML_PREFIX};
+
+use crate::tree::{Document, Element, Node};
+
+pub fn canonicalize(document: &Document) -> String {
+    let mut out = String::new();
+    // Comments are left out along with the line break separating them from the root element
+    let is_written = |node: &&Node| !matches!(node, Node::Comment(_));
+    for node in document.before.iter().filter(is_written) {
+        write_node(&mut out, node, &Namespace::empty());
+        out.push('\n');
+    }
+    write_element(&mut out, &document.root, &Namespace::empty());
+    for node in document.after.iter().filter(is_written) {
+        out.push('\n');
+        write_node(&mut out, node, &Namespace::empty());
+    }
+    out
+}
+
+fn write_node(out: &mut String, node: &Node, parent: &Namespace) {
+    match node {
+        Node::Element(element) => write_element(out, element, parent),
+        Node::Text(text) => escape(out, text, false),
+        Node::Comment(_) => {}
+        Node::ProcessingInstruction { name, data } => match data {
+            Some(data) => write!(out, "<?{} {}?>", name, data).unwrap(),
+            None => write!(out, "<?{}?>", name).unwrap(),
+        },
+    }
+}
+
+fn write_element(out: &mut String, element: &Element, parent: &Namespace) {
+    let name = qualified_name(element.name.prefix.as_deref(), &element.name.local_name);
+    write!(out, "<{}", name).unwrap();
+
+    // Only the declarations that change the namespaces in scope
+    let declared: BTreeMap<&str, &str> = element
+        .namespace
+        .0
+        .iter()
+        .filter(|(prefix, _)| *prefix != NS_XML_PREFIX && *prefix != NS_XMLNS_PREFIX)
+        .filter(|(prefix, uri)| match parent.get(prefix.as_str()) {
+            Some(parent_uri) => parent_uri != uri.as_str(),
+            None => !(prefix.is_empty() && uri.is_empty()),
+        })
+        .map(|(prefix, uri)| (prefix.as_str(), uri.as_str()))
+        .collect();
+    for (prefix, uri) in declared {
+        match prefix {
+            "" => out.push_str(" xmlns=\""),
+            prefix => write!(out, " xmlns:{}=\"", prefix).unwrap(),
+        }
+        escape(out, uri, true);
+        out.push('"');
+    }
+
+    // Attributes are sorted by namespace URI, then local name
+    let mut attributes: Vec<_> = element.attributes.iter().collect();
+    attributes.sort_by(|a, b| {
+        (
+            a.name.namespace.as_deref().unwrap_or(""),
+            &a.name.local_name,
+        )
+            .cmp(&(
+                b.name.namespace.as_deref().unwrap_or(""),
+                &b.name.local_name,
+            ))
+    });
+    for attribute in attributes {
+        let name = qualified_name(attribute.name.prefix.as_deref(), &attribute.name.local_name);
+        write!(out, " {}=\"", name).unwrap();
+        escape(out, &attribute.value, true);
+        out.push('"');
+    }
+    out.push('>');
+
+    for child in &element.children {
+        write_node(out, child, &element.namespace);
+    }
+    write!(out, "</{}>", name).unwrap();
+}
+
+fn qualified_name(prefix: Option<&str>, local_name: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{}:{}", prefix, local_name),
+        None => local_name.to_string(),
+    }
+}
+
+fn escape(out: &mut String, text: &str, attribute: bool) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' if !attribute => out.push_str("&gt;"),
+            '"' if attribute => out.push_str("&quot;"),
+            '\t' if attribute => out.push_str("&#x9;"),
+            '\n' if attribute => out.push_str("&#xA;"),
+            '\r' => out.push_str("&#xD;"),
+            c => out.push(c),
+        }
+    }
+}
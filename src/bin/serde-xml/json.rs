@@ -0,0 +1,123 @@
+//! Conversion between XML and JSON, with the keys this crate uses for serde: `@name` for
+//! attributes, `$value` for text next to attributes or child elements, and arrays for repeated
+//! elements. JSON objects are unordered, so child elements come out grouped and sorted by name.
+
+use serde_json::{Map, Value};
+use xml::attribute::OwnedAttribute;
+use xml::name::OwnedName;
+
+use crate::tree::{Element, Node};
+
+/// The JSON for a document, as an object with the root element as its only key.
+pub fn to_json(root: &Element) -> Value {
+    let mut object = Map::new();
+    object.insert(qualified_name(&root.name), element_value(root));
+    Value::Object(object)
+}
+
+fn qualified_name(name: &OwnedName) -> String {
+    match &name.prefix {
+        Some(prefix) => format!("{}:{}", prefix, name.local_name),
+        None => name.local_name.clone(),
+    }
+}
+
+fn element_value(element: &Element) -> Value {
+    let text = element.text();
+    if element.attributes.is_empty() && element.child_elements().next().is_none() {
+        return Value::String(text);
+    }
+
+    let mut object = Map::new();
+    for attribute in &element.attributes {
+        object.insert(
+            format!("@{}", qualified_name(&attribute.name)),
+            Value::String(attribute.value.clone()),
+        );
+    }
+    for child in element.child_elements() {
+        let value = element_value(child);
+        match object.get_mut(&qualified_name(&child.name)) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+            None => {
+                object.insert(qualified_name(&child.name), value);
+            }
+        }
+    }
+    if !text.is_empty() {
+        object.insert("$value".to_string(), Value::String(text));
+    }
+    Value::Object(object)
+}
+
+/// The root element for a JSON document, which must be an object with a single key.
+pub fn from_json(value: &Value) -> Result<Element, String> {
+    match value {
+        Value::Object(object) if object.len() == 1 => {
+            let (name, value) = object.iter().next().unwrap();
+            if value.is_array() {
+                return Err(format!("the root element '{}' must not be an array", name));
+            }
+            element(name, value)
+        }
+        _ => Err("expected an object with the root element as its only key".to_string()),
+    }
+}
+
+/// Elements for a key of a JSON object: several for an array, one otherwise.
+fn elements(name: &str, value: &Value) -> Result<Vec<Element>, String> {
+    if let Value::Array(values) = value {
+        let mut elements = Vec::new();
+        for value in values {
+            if value.is_array() {
+                return Err(format!("nested arrays for element '{}'", name));
+            }
+            elements.push(element(name, value)?);
+        }
+        return Ok(elements);
+    }
+    Ok(vec![element(name, value)?])
+}
+
+fn element(name: &str, value: &Value) -> Result<Element, String> {
+    let mut element = Element::new(name);
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                if let Some(attribute) = key.strip_prefix('@') {
+                    let value = scalar(value)
+                        .ok_or_else(|| format!("attribute '{}' must be a scalar", key))?;
+                    element.attributes.push(OwnedAttribute {
+                        name: OwnedName::local(attribute),
+                        value,
+                    });
+                } else if key == "$value" {
+                    let text = scalar(value).ok_or_else(|| {
+                        format!("'$value' of element '{}' must be a scalar", name)
+                    })?;
+                    element.children.push(Node::Text(text));
+                } else {
+                    for child in elements(key, value)? {
+                        element.children.push(Node::Element(child));
+                    }
+                }
+            }
+        }
+        value => {
+            if let Some(text) = scalar(value).filter(|text| !text.is_empty()) {
+                element.children.push(Node::Text(text));
+            }
+        }
+    }
+    Ok(element)
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => Some(String::new()),
+        Value::String(text) => Some(text.clone()),
+        Value::Bool(_) | Value::Number(_) => Some(value.to_string()),
+        Value::Array(_) | Value::Object(_) => None,
+    }
+}
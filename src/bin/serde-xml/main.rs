@@ -0,0 +1,161 @@
+//! Command-line tool for XML documents, following the conventions of `serde-xml-rs`.
+//!
+//! ```text
+//! serde-xml to-json order.xml > order.json
+//! serde-xml from-json order.json > order.xml
+//! serde-xml pretty < order.xml
+//! serde-xml extract /order/line[2]/@sku order.xml
+//! serde-xml check order.xsd order.xml
+//! ```
+//!
+//! Documents are read from the standard input if no file is given, or if the file is `-`.
+//! Errors are reported on the standard error, with their position when the input is malformed,
+//! and the exit code is 1, or 2 for invalid arguments.
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::process;
+
+use serde::Deserialize;
+use serde_xml_rs::{DecodingReader, Deserializer, RawXml, XsdSchema};
+use xml::reader::{EventReader, ParserConfig};
+use xml::writer::EmitterConfig;
+
+mod canonical;
+mod json;
+mod path;
+mod tree;
+
+const USAGE: &str = "\
+usage: serde-xml <command> [ARGS] [FILE]
+
+commands:
+  to-json [FILE]         convert a document to JSON
+  from-json [FILE]       convert JSON to a document
+  pretty [FILE]          indent a document
+  minify [FILE]          remove the whitespace between elements
+  canonicalize [FILE]    write the canonical form of a document
+  extract PATH [FILE]    print the elements, attribute values or text at a path,
+                         such as /order/line[2]/@sku or /order/note/text()
+  check SCHEMA [FILE]    validate a document against an XSD";
+
+enum Failure {
+    Usage,
+    Error(Box<dyn Error>),
+}
+
+impl<E: Into<Box<dyn Error>>> From<E> for Failure {
+    fn from(error: E) -> Self {
+        Failure::Error(error.into())
+    }
+}
+
+fn open(path: Option<&String>) -> io::Result<Box<dyn Read>> {
+    match path.map(String::as_str) {
+        None | Some("-") => Ok(Box::new(io::stdin())),
+        Some(path) => Ok(Box::new(BufReader::new(File::open(path)?))),
+    }
+}
+
+/// Opens a document, decoding it from the encoding in its declaration.
+fn open_xml(path: Option<&String>) -> io::Result<DecodingReader<Box<dyn Read>>> {
+    Ok(DecodingReader::new(open(path)?))
+}
+
+/// Copies a document event by event, dropping the whitespace between elements.
+fn reformat<W: Write>(input: Option<&String>, out: W, indent: bool) -> Result<(), Failure> {
+    let config = ParserConfig::new()
+        .trim_whitespace(true)
+        .ignore_comments(false);
+    let reader = EventReader::new_with_config(open_xml(input)?, config);
+    let mut writer = EmitterConfig::new()
+        .perform_indent(indent)
+        .create_writer(out);
+    for event in reader {
+        let event = event.map_err(serde_xml_rs::Error::from)?;
+        if let Some(event) = event.as_writer_event() {
+            writer.write(event).map_err(serde_xml_rs::Error::from)?;
+        }
+    }
+    writeln!(writer.inner_mut())?;
+    Ok(())
+}
+
+fn run<W: Write>(args: &[String], mut out: W) -> Result<(), Failure> {
+    match args {
+        [command, input @ ..] if command == "to-json" && input.len() <= 1 => {
+            let document = tree::parse(open_xml(input.first())?, true)?;
+            serde_json::to_writer_pretty(&mut out, &json::to_json(&document.root))?;
+            writeln!(out)?;
+        }
+        [command, input @ ..] if command == "from-json" && input.len() <= 1 => {
+            let value: serde_json::Value = serde_json::from_reader(open(input.first())?)?;
+            let root = json::from_json(&value)?;
+            let config = EmitterConfig::new().perform_indent(true);
+            let mut writer = config.create_writer(&mut out);
+            root.emit(&mut writer).map_err(serde_xml_rs::Error::from)?;
+            writeln!(out)?;
+        }
+        [command, input @ ..] if command == "pretty" && input.len() <= 1 => {
+            reformat(input.first(), out, true)?;
+        }
+        [command, input @ ..] if command == "minify" && input.len() <= 1 => {
+            reformat(input.first(), out, false)?;
+        }
+        [command, input @ ..] if command == "canonicalize" && input.len() <= 1 => {
+            let document = tree::parse(open_xml(input.first())?, false)?;
+            out.write_all(canonical::canonicalize(&document).as_bytes())?;
+        }
+        [command, path, input @ ..] if command == "extract" && input.len() <= 1 => {
+            let path: path::Path = path.parse()?;
+            let document = tree::parse(open_xml(input.first())?, true)?;
+            let matches = path.select(&document.root);
+            if matches.is_empty() {
+                return Err(format!("nothing matches the path {}", path).into());
+            }
+            for found in matches {
+                match found {
+                    path::Match::Element(element) => element.write(&mut out, false)?,
+                    path::Match::Text(text) => out.write_all(text.as_bytes())?,
+                }
+                writeln!(out)?;
+            }
+        }
+        [command, schema, input @ ..] if command == "check" && input.len() <= 1 => {
+            let schema = XsdSchema::from_reader(BufReader::new(File::open(schema)?))?;
+            let mut deserializer =
+                Deserializer::new_from_reader(open_xml(input.first())?).validate(schema);
+            RawXml::deserialize(&mut deserializer)?;
+            writeln!(out, "valid")?;
+        }
+        _ => return Err(Failure::Usage),
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    // Nothing is printed for a document that turns out to be malformed halfway through
+    let mut out = Vec::new();
+    match run(&args, &mut out) {
+        Ok(()) => {
+            if let Err(error) = io::stdout().write_all(&out) {
+                eprintln!("serde-xml: {}", error);
+                process::exit(1);
+            }
+        }
+        Err(Failure::Usage) => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+        Err(Failure::Error(error)) => {
+            eprintln!("serde-xml: {}", error);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Paths selecting parts of a document, such as `/order/line[2]/@sku`: element names or `*`,
+//! each with an optional position among the matching siblings counting from 1, and optionally
+//! ending with an attribute or `text()`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::tree::Element;
+
+pub struct Path {
+    source: String,
+    steps: Vec<Step>,
+    target: Target,
+}
+
+struct Step {
+    /// `None` for `*`.
+    name: Option<String>,
+    position: Option<usize>,
+}
+
+enum Target {
+    Element,
+    Attribute(String),
+    Text,
+}
+
+pub enum Match<'a> {
+    Element(&'a Element),
+    Text(String),
+}
+
+impl FromStr for Path {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("invalid path '{}': {}", source, reason);
+        let mut parts: Vec<&str> = source
+            .strip_prefix('/')
+            .unwrap_or(source)
+            .split('/')
+            .collect();
+
+        let target = match parts.last() {
+            Some(&"text()") => Target::Text,
+            Some(last) if last.starts_with('@') => Target::Attribute(last[1..].to_string()),
+            _ => Target::Element,
+        };
+        if !matches!(target, Target::Element) {
+            parts.pop();
+        }
+
+        let mut steps = Vec::new();
+        for part in parts {
+            let (name, position) = match part.strip_suffix(']').and_then(|p| p.split_once('[')) {
+                Some((name, position)) => match position.parse::<usize>() {
+                    Ok(position) if position > 0 => (name, Some(position)),
+                    _ => return Err(invalid("positions are numbers from 1")),
+                },
+                None => (part, None),
+            };
+            if name.is_empty() || name.contains(|c| "[]@()".contains(c)) {
+                return Err(invalid("expected an element name or '*'"));
+            }
+            steps.push(Step {
+                name: Some(name.to_string()).filter(|name| name != "*"),
+                position,
+            });
+        }
+        if steps.is_empty() {
+            return Err(invalid("expected the root element"));
+        }
+
+        Ok(Path {
+            source: source.to_string(),
+            steps,
+            target,
+        })
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Step {
+    /// The elements matching the step among some siblings.
+    fn select<'a>(&self, siblings: impl Iterator<Item = &'a Element>) -> Vec<&'a Element> {
+        let matching = siblings.filter(|element| match &self.name {
+            Some(name) => element_name(element) == *name,
+            None => true,
+        });
+        match self.position {
+            Some(position) => matching.skip(position - 1).take(1).collect(),
+            None => matching.collect(),
+        }
+    }
+}
+
+fn element_name(element: &Element) -> String {
+    match &element.name.prefix {
+        Some(prefix) => format!("{}:{}", prefix, element.name.local_name),
+        None => element.name.local_name.clone(),
+    }
+}
+
+impl Path {
+    pub fn select<'a>(&self, root: &'a Element) -> Vec<Match<'a>> {
+        let mut elements = self.steps[0].select(std::iter::once(root));
+        for step in &self.steps[1..] {
+            elements = elements
+                .into_iter()
+                .flat_map(|parent| step.select(parent.child_elements()))
+                .collect();
+        }
+
+        match &self.target {
+            Target::Element => elements.into_iter().map(Match::Element).collect(),
+            Target::Text => elements
+                .into_iter()
+                .map(|element| Match::Text(element.text()))
+                .collect(),
+            Target::Attribute(name) => elements
+                .into_iter()
+                .filter_map(|element| {
+                    element
+                        .attributes
+                        .iter()
+                        .find(|attribute| attribute.name.local_name == *name)
+                        .map(|attribute| Match::Text(attribute.value.clone()))
+                })
+                .collect(),
+        }
+    }
+}
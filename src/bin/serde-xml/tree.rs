@@ -0,0 +1,149 @@
+//! A document read into memory, for the commands that need more than one pass over it.
+
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+use serde_xml_rs::Error;
+use xml::attribute::OwnedAttribute;
+use xml::name::OwnedName;
+use xml::namespace::Namespace;
+use xml::reader::{EventReader, ParserConfig, XmlEvent};
+use xml::writer::{self, EmitterConfig, EventWriter};
+
+pub struct Element {
+    pub name: OwnedName,
+    pub attributes: Vec<OwnedAttribute>,
+    /// All namespaces in scope, including those declared by ancestors.
+    pub namespace: Namespace,
+    pub children: Vec<Node>,
+}
+
+pub enum Node {
+    Element(Element),
+    Text(String),
+    Comment(String),
+    ProcessingInstruction { name: String, data: Option<String> },
+}
+
+/// A document reduced to its root element, along with the processing instructions around it.
+pub struct Document {
+    pub before: Vec<Node>,
+    pub root: Element,
+    pub after: Vec<Node>,
+}
+
+/// Reads a document. Whitespace is kept as is unless `trim` is set.
+pub fn parse<R: Read>(reader: R, trim: bool) -> Result<Document, Error> {
+    let config = ParserConfig::new()
+        .trim_whitespace(trim)
+        .whitespace_to_characters(true)
+        .cdata_to_characters(true)
+        .coalesce_characters(true)
+        .ignore_comments(false);
+    let mut before = Vec::new();
+    let mut after = Vec::new();
+    let mut root = None;
+    let mut stack: Vec<Element> = Vec::new();
+
+    for event in EventReader::new_with_config(reader, config) {
+        let node = match event? {
+            XmlEvent::StartElement {
+                name,
+                attributes,
+                namespace,
+            } => {
+                stack.push(Element {
+                    name,
+                    attributes,
+                    namespace,
+                    children: Vec::new(),
+                });
+                continue;
+            }
+            XmlEvent::EndElement { .. } => match stack.pop() {
+                Some(element) => Node::Element(element),
+                None => continue,
+            },
+            XmlEvent::Characters(text) => Node::Text(text),
+            XmlEvent::Comment(text) => Node::Comment(text),
+            XmlEvent::ProcessingInstruction { name, data } => {
+                Node::ProcessingInstruction { name, data }
+            }
+            _ => continue,
+        };
+        match (stack.last_mut(), node) {
+            (Some(parent), node) => parent.children.push(node),
+            (None, Node::Element(element)) => root = Some(element),
+            (None, Node::Text(_)) => {}
+            (None, node) if root.is_none() => before.push(node),
+            (None, node) => after.push(node),
+        }
+    }
+
+    Ok(Document {
+        before,
+        root: root.expect("the parser rejects documents without a root element"),
+        after,
+    })
+}
+
+impl Element {
+    pub fn new(name: &str) -> Self {
+        Element {
+            name: OwnedName::local(name),
+            attributes: Vec::new(),
+            namespace: Namespace::empty(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn child_elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    /// The text directly inside the element.
+    pub fn text(&self) -> String {
+        self.children
+            .iter()
+            .filter_map(|node| match node {
+                Node::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Writes the element and its content, without an XML declaration.
+    pub fn write<W: Write>(&self, out: W, indent: bool) -> Result<(), Error> {
+        let config = EmitterConfig::new()
+            .perform_indent(indent)
+            .write_document_declaration(false);
+        let mut writer = EventWriter::new_with_config(out, config);
+        self.emit(&mut writer)?;
+        Ok(())
+    }
+
+    pub fn emit<W: Write>(&self, writer: &mut EventWriter<W>) -> writer::Result<()> {
+        writer.write(writer::XmlEvent::StartElement {
+            name: self.name.borrow(),
+            attributes: self.attributes.iter().map(|a| a.borrow()).collect(),
+            namespace: Cow::Borrowed(&self.namespace),
+        })?;
+        for child in &self.children {
+            match child {
+                Node::Element(element) => element.emit(writer)?,
+                Node::Text(text) => writer.write(writer::XmlEvent::Characters(text))?,
+                Node::Comment(text) => writer.write(writer::XmlEvent::Comment(text))?,
+                Node::ProcessingInstruction { name, data } => {
+                    writer.write(writer::XmlEvent::ProcessingInstruction {
+                        name,
+                        data: data.as_deref(),
+                    })?
+                }
+            }
+        }
+        writer.write(writer::XmlEvent::end_element())
+    }
+}
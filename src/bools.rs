@@ -0,0 +1,86 @@
+//! Formats for a single boolean field, whatever the `BoolFormat` of the serializer, for use with
+//! `#[serde(with = "...")]`. The values of all formats are read back, as with `BoolFormat`.
+//!
+//! ```rust
+//! # use serde::{Deserialize, Serialize};
+//! # use serde_xml_rs::{from_str, to_string};
+//! #[derive(Debug, Serialize, Deserialize, PartialEq)]
+//! struct Port {
+//!     #[serde(rename = "@enabled", alias = "enabled", with = "serde_xml_rs::bools::one_zero")]
+//!     enabled: bool,
+//!     #[serde(with = "serde_xml_rs::bools::upper_case")]
+//!     secure: bool,
+//! }
+//!
+//! # fn main() {
+//! let port = Port { enabled: true, secure: false };
+//! let xml = to_string(&port).unwrap();
+//! assert_eq!(
+//!     xml,
+//!     r#"<?xml version="1.0" encoding="UTF-8"?><Port enabled="1"><secure>FALSE</secure></Port>"#
+//! );
+//! assert_eq!(from_str::<Port>(&xml).unwrap(), port);
+//! # }
+//! ```
+
+use serde::de::{self, Deserialize, Deserializer, Unexpected};
+use serde::ser::Serializer;
+
+use crate::layout::BoolFormat;
+
+fn serialize_as<S: Serializer>(
+    format: BoolFormat,
+    v: bool,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(format.format(v))
+}
+
+fn deserialize_as<'de, D: Deserializer<'de>>(
+    format: BoolFormat,
+    deserializer: D,
+) -> Result<bool, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    format
+        .parse(&s)
+        .ok_or_else(|| de::Error::invalid_value(Unexpected::Str(&s), &"a boolean"))
+}
+
+/// `true` and `false`.
+pub mod true_false {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_as(BoolFormat::TrueFalse, *v, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        deserialize_as(BoolFormat::TrueFalse, deserializer)
+    }
+}
+
+/// `1` and `0`.
+pub mod one_zero {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_as(BoolFormat::OneZero, *v, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        deserialize_as(BoolFormat::OneZero, deserializer)
+    }
+}
+
+/// `TRUE` and `FALSE`.
+pub mod upper_case {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_as(BoolFormat::UpperCase, *v, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        deserialize_as(BoolFormat::UpperCase, deserializer)
+    }
+}
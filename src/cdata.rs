@@ -0,0 +1,145 @@
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess};
+use serde::ser::{Serialize, Serializer};
+
+/// Name of the newtype struct through which CDATA sections are recognised by this crate's
+/// `Serializer` and `Deserializer`.
+pub(crate) const CDATA_TOKEN: &str = "$serde_xml_rs::CData";
+
+/// Text written as a CDATA section. A `]]>` in the text ends the section and starts another, so
+/// any text can be written. When deserialized, text is read whether or not it is in a CDATA
+/// section; use `Text` to know which.
+///
+/// ```rust
+/// # use serde::{Deserialize, Serialize};
+/// # use serde_xml_rs::{from_str, to_string, CData};
+/// #[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// struct Widget {
+///     script: CData<String>,
+/// }
+///
+/// # fn main() {
+/// let widget = Widget {
+///     script: CData("if (a < b && c) { x[y[0]]> 1 }".to_string()),
+/// };
+/// let xml = to_string(&widget).unwrap();
+/// assert_eq!(
+///     xml,
+///     r#"<?xml version="1.0" encoding="UTF-8"?><Widget><script><![CDATA[if (a < b && c) { x[y[0]]]]><![CDATA[> 1 }]]></script></Widget>"#
+/// );
+/// assert_eq!(from_str::<Widget>(&xml).unwrap(), widget);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CData<T>(pub T);
+
+impl<T: Serialize> Serialize for CData<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(CDATA_TOKEN, &self.0)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for CData<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(CData)
+    }
+}
+
+/// Text content along with whether it was, or is to be, written as a CDATA section. Text is read
+/// as `CData` only if all of it is in CDATA sections.
+///
+/// ```rust
+/// # use serde::Deserialize;
+/// # use serde_xml_rs::{from_str, Text};
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Snippet {
+///     html: Text,
+/// }
+///
+/// # fn main() {
+/// let snippet: Snippet = from_str("<Snippet><html><![CDATA[<b>bold</b>]]></html></Snippet>").unwrap();
+/// assert_eq!(snippet.html, Text::CData("<b>bold</b>".to_string()));
+///
+/// let snippet: Snippet = from_str("<Snippet><html>&lt;b>bold&lt;/b></html></Snippet>").unwrap();
+/// assert_eq!(snippet.html, Text::Plain("<b>bold</b>".to_string()));
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Text {
+    Plain(String),
+    CData(String),
+}
+
+impl Text {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Text::Plain(text) | Text::CData(text) => text,
+        }
+    }
+
+    pub fn is_cdata(&self) -> bool {
+        matches!(self, Text::CData(_))
+    }
+}
+
+impl Default for Text {
+    fn default() -> Self {
+        Text::Plain(String::new())
+    }
+}
+
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Text {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Text::Plain(text) => serializer.serialize_str(text),
+            Text::CData(text) => serializer.serialize_newtype_struct(CDATA_TOKEN, text),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Text {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TextVisitor;
+
+        impl<'de> de::Visitor<'de> for TextVisitor {
+            type Value = Text;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("text content")
+            }
+
+            fn visit_str<E: de::Error>(self, text: &str) -> Result<Text, E> {
+                Ok(Text::Plain(text.to_string()))
+            }
+
+            fn visit_string<E: de::Error>(self, text: String) -> Result<Text, E> {
+                Ok(Text::Plain(text))
+            }
+
+            fn visit_newtype_struct<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Text, D::Error> {
+                String::deserialize(deserializer).map(Text::Plain)
+            }
+
+            /// This crate's `Deserializer` gives the text as a single entry, keyed by its kind.
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Text, A::Error> {
+                match map.next_entry::<String, String>()? {
+                    Some((kind, text)) if kind == "cdata" => Ok(Text::CData(text)),
+                    Some((_, text)) => Ok(Text::Plain(text)),
+                    None => Ok(Text::default()),
+                }
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(CDATA_TOKEN, TextVisitor)
+    }
+}
@@ -0,0 +1,396 @@
+//! Generates Rust types for the documents described by an XSD or by sample documents, with the
+//! serde attributes this crate expects: `@` for attributes, `$value` for text content or a
+//! choice of elements, `Vec` for repeated elements and `Option` for optional ones. Elements that
+//! may come interleaved, such as those of a repeated choice, are read as a `$value` sequence of an
+//! enum. Names that aren't valid identifiers, even raw ones, such as `self`, get a trailing
+//! underscore.
+//!
+//! ```rust
+//! let code = serde_xml_rs::codegen::from_samples(vec![
+//!     r#"<order id="7"><line>apple</line><line>pear</line></order>"#.as_bytes(),
+//!     r#"<order id="8"><note>urgent</note></order>"#.as_bytes(),
+//! ])
+//! .unwrap();
+//! assert!(code.contains("pub struct Order {"));
+//! assert!(code.contains(r#"#[serde(rename = "@id", alias = "id")]"#));
+//! assert!(code.contains("pub line: Vec<String>,"));
+//! assert!(code.contains("pub note: Option<String>,"));
+//! ```
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::io::Read;
+
+use crate::error::Result;
+use crate::schema::XsdSchema;
+
+mod sample;
+mod xsd;
+
+/// Generates types for the global elements of an XSD.
+pub fn from_xsd(schema: &XsdSchema) -> Result<String> {
+    let mut module = Module::default();
+    xsd::generate(schema, &mut module)?;
+    Ok(module.render())
+}
+
+/// Generates types inferred from one or more sample documents. Elements with the same name are
+/// assumed to have the same type wherever they appear.
+///
+/// A field is an `Option` if its element is missing or empty in some samples. Empty elements are
+/// read as `None` by a `Deserializer` set to `none_values(NoneValues::EmptyElement)`; otherwise
+/// only strings can be read from them.
+pub fn from_samples<I>(samples: I) -> Result<String>
+where
+    I: IntoIterator,
+    I::Item: Read,
+{
+    let mut module = Module::default();
+    sample::generate(samples, &mut module)?;
+    Ok(module.render())
+}
+
+/// The generated items, in the order they are emitted.
+#[derive(Default)]
+struct Module {
+    items: Vec<Item>,
+    names: HashSet<String>,
+}
+
+struct Item {
+    name: String,
+    /// Name of the element for a root type, or `name` otherwise.
+    xml_name: String,
+    kind: ItemKind,
+}
+
+enum ItemKind {
+    Struct(Vec<Field>),
+    Enum(Vec<Variant>),
+}
+
+struct Field {
+    /// Key of the field for this crate, such as `@id` or `$value`.
+    xml_name: String,
+    ty: FieldType,
+}
+
+struct Variant {
+    xml_name: String,
+    /// `None` for unit variants.
+    ty: Option<FieldType>,
+}
+
+#[derive(Clone)]
+struct FieldType {
+    base: String,
+    /// Whether `base` is one of the generated items.
+    generated: bool,
+    occurs: Occurs,
+    boxed: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Occurs {
+    One,
+    Optional,
+    /// Repeated, along with whether the element may be missing.
+    Many {
+        optional: bool,
+    },
+}
+
+impl FieldType {
+    fn primitive(base: &str) -> Self {
+        FieldType {
+            base: base.to_string(),
+            generated: false,
+            occurs: Occurs::One,
+            boxed: false,
+        }
+    }
+
+    fn generated(name: String) -> Self {
+        FieldType {
+            base: name,
+            generated: true,
+            occurs: Occurs::One,
+            boxed: false,
+        }
+    }
+
+    fn with_occurs(mut self, occurs: Occurs) -> Self {
+        self.occurs = occurs;
+        self
+    }
+
+    fn render(&self) -> String {
+        let base = if self.boxed {
+            format!("Box<{}>", self.base)
+        } else {
+            self.base.clone()
+        };
+        match self.occurs {
+            Occurs::One => base,
+            Occurs::Optional => format!("Option<{}>", base),
+            Occurs::Many { .. } => format!("Vec<{}>", self.base),
+        }
+    }
+}
+
+impl Module {
+    /// Reserves a type name derived from an element or type name, unique within the module.
+    fn reserve_name(&mut self, xml_name: &str) -> String {
+        let base = escape_reserved(pascal_case(xml_name));
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while !self.names.insert(name.clone()) {
+            name = format!("{}{}", base, suffix);
+            suffix += 1;
+        }
+        name
+    }
+
+    fn push(&mut self, name: String, xml_name: &str, kind: ItemKind) {
+        self.items.push(Item {
+            name,
+            xml_name: xml_name.to_string(),
+            kind,
+        });
+    }
+
+    fn item(&self, name: &str) -> Option<&Item> {
+        self.items.iter().find(|item| item.name == name)
+    }
+
+    /// Names of the generated items directly contained in an item, which make it recursive
+    /// unless boxed or in a `Vec`.
+    fn contained(&self, item: &Item) -> Vec<String> {
+        let types: Vec<&FieldType> = match &item.kind {
+            ItemKind::Struct(fields) => fields.iter().map(|field| &field.ty).collect(),
+            ItemKind::Enum(variants) => variants.iter().filter_map(|v| v.ty.as_ref()).collect(),
+        };
+        types
+            .into_iter()
+            .filter(|ty| ty.generated && !ty.boxed && !matches!(ty.occurs, Occurs::Many { .. }))
+            .map(|ty| ty.base.clone())
+            .collect()
+    }
+
+    fn reaches(&self, from: &str, to: &str, visited: &mut HashSet<String>) -> bool {
+        if from == to {
+            return true;
+        }
+        if !visited.insert(from.to_string()) {
+            return false;
+        }
+        match self.item(from) {
+            Some(item) => self
+                .contained(item)
+                .iter()
+                .any(|next| self.reaches(next, to, visited)),
+            None => false,
+        }
+    }
+
+    /// Boxes the fields through which a type contains itself.
+    fn box_recursive_fields(&mut self) {
+        for index in 0..self.items.len() {
+            let name = self.items[index].name.clone();
+            let contained = self.contained(&self.items[index]);
+            for target in contained {
+                if self.reaches(&target, &name, &mut HashSet::new()) {
+                    let types: Vec<&mut FieldType> = match &mut self.items[index].kind {
+                        ItemKind::Struct(fields) => {
+                            fields.iter_mut().map(|field| &mut field.ty).collect()
+                        }
+                        ItemKind::Enum(variants) => {
+                            variants.iter_mut().filter_map(|v| v.ty.as_mut()).collect()
+                        }
+                    };
+                    for ty in types.into_iter().filter(|ty| ty.base == target) {
+                        ty.boxed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    fn render(mut self) -> String {
+        self.box_recursive_fields();
+
+        let mut out = String::from("use serde::{Deserialize, Serialize};\n");
+        for item in &self.items {
+            out.push_str("\n#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n");
+            if item.name != item.xml_name {
+                writeln!(out, "#[serde(rename = {:?})]", item.xml_name).unwrap();
+            }
+            match &item.kind {
+                ItemKind::Struct(fields) if fields.is_empty() => {
+                    writeln!(out, "pub struct {} {{}}", item.name).unwrap();
+                    continue;
+                }
+                ItemKind::Struct(fields) => {
+                    writeln!(out, "pub struct {} {{", item.name).unwrap();
+                    let mut names = HashSet::new();
+                    for field in fields {
+                        render_field(&mut out, field, &mut names);
+                    }
+                }
+                ItemKind::Enum(variants) => {
+                    writeln!(out, "pub enum {} {{", item.name).unwrap();
+                    let mut names = HashSet::new();
+                    for variant in variants {
+                        let name =
+                            unique(escape_reserved(pascal_case(&variant.xml_name)), &mut names);
+                        if name != variant.xml_name {
+                            writeln!(out, "    #[serde(rename = {:?})]", variant.xml_name).unwrap();
+                        }
+                        match &variant.ty {
+                            Some(ty) => writeln!(out, "    {}({}),", name, ty.render()).unwrap(),
+                            None => writeln!(out, "    {},", name).unwrap(),
+                        }
+                    }
+                }
+            }
+            out.push_str("}\n");
+        }
+        out
+    }
+}
+
+fn render_field(out: &mut String, field: &Field, names: &mut HashSet<String>) {
+    let base_name = match field.xml_name.as_str() {
+        "$value" => "value".to_string(),
+        "$unknown" => "unknown".to_string(),
+        "@*" => "extra_attributes".to_string(),
+        name => snake_case(name.trim_start_matches('@')),
+    };
+    let name = unique(escape_reserved(base_name), names);
+
+    let mut attributes = Vec::new();
+    if name != field.xml_name {
+        attributes.push(format!("rename = {:?}", field.xml_name));
+    }
+    // Attributes are read by their name, without the `@`
+    if let Some(attribute) = field.xml_name.strip_prefix('@').filter(|a| *a != "*") {
+        attributes.push(format!("alias = {:?}", attribute));
+    }
+    let optional = matches!(field.ty.occurs, Occurs::Many { optional: true });
+    if optional || field.xml_name == "@*" || field.xml_name == "$unknown" {
+        attributes.push("default".to_string());
+    }
+    if !attributes.is_empty() {
+        writeln!(out, "    #[serde({})]", attributes.join(", ")).unwrap();
+    }
+    writeln!(
+        out,
+        "    pub {}: {},",
+        raw_identifier(&name),
+        field.ty.render()
+    )
+    .unwrap();
+}
+
+fn unique(base: String, names: &mut HashSet<String>) -> String {
+    let mut name = base.clone();
+    let mut suffix = 2;
+    while !names.insert(name.clone()) {
+        name = format!("{}_{}", base, suffix);
+        suffix += 1;
+    }
+    name
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "macro", "override", "priv", "try", "typeof",
+    "unsized", "virtual", "yield",
+];
+
+/// Keywords that can't be raw identifiers either.
+const RESERVED: &[&str] = &["crate", "self", "Self", "super"];
+
+/// Appends an underscore to a name that can't be an identifier even as a raw one. The field or
+/// variant is then renamed to its element.
+fn escape_reserved(name: String) -> String {
+    if RESERVED.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
+fn raw_identifier(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Splits a name into words at separators and case changes, keeping acronyms together:
+/// `videoInputID` gives `video`, `Input`, `ID`.
+fn words(name: &str) -> Vec<String> {
+    let local_name = name.rsplit(':').next().unwrap_or(name);
+    let chars: Vec<char> = local_name.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        let previous = index.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(index + 1);
+        let boundary = c.is_uppercase()
+            && match previous {
+                Some(previous) if previous.is_lowercase() || previous.is_numeric() => true,
+                Some(previous) if previous.is_uppercase() => {
+                    next.is_some_and(|next| next.is_lowercase())
+                }
+                _ => false,
+            };
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn snake_case(name: &str) -> String {
+    let snake = words(name)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("_");
+    match snake.chars().next() {
+        Some(c) if c.is_alphabetic() => snake,
+        _ => format!("_{}", snake),
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    let pascal: String = words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    match pascal.chars().next() {
+        Some(c) if c.is_alphabetic() => pascal,
+        _ => format!("_{}", pascal),
+    }
+}
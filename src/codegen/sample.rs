@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::io::Read;
+
+use xml::reader::{EventReader, ParserConfig, XmlEvent};
+
+use super::{Field, FieldType, ItemKind, Module, Occurs};
+use crate::error::Result;
+
+/// What the samples show of the elements with a given name.
+#[derive(Default)]
+struct ElementStats {
+    occurrences: usize,
+    /// Attributes in order of appearance, with the number of elements carrying them.
+    attributes: Vec<(String, usize, Kind)>,
+    /// Child elements in order of appearance.
+    children: Vec<(String, ChildStats)>,
+    /// Number of elements with text content.
+    with_text: usize,
+    text: Kind,
+}
+
+#[derive(Default)]
+struct ChildStats {
+    /// Number of parents containing the child at least once.
+    parents: usize,
+    /// Largest number of times the child appears in a single parent.
+    max_per_parent: usize,
+}
+
+/// The narrowest Rust type for the values seen so far.
+#[derive(Clone, Copy, PartialEq, Default)]
+enum Kind {
+    #[default]
+    Unknown,
+    Bool,
+    Integer,
+    Float,
+    Text,
+}
+
+impl Kind {
+    fn of(value: &str) -> Kind {
+        let value = value.trim();
+        if value == "true" || value == "false" {
+            Kind::Bool
+        } else if value.parse::<i64>().is_ok() {
+            Kind::Integer
+        } else if value.parse::<f64>().is_ok() && value.chars().any(|c| c.is_ascii_digit()) {
+            Kind::Float
+        } else {
+            Kind::Text
+        }
+    }
+
+    fn join(self, other: Kind) -> Kind {
+        match (self, other) {
+            (Kind::Unknown, kind) | (kind, Kind::Unknown) => kind,
+            (a, b) if a == b => a,
+            (Kind::Integer, Kind::Float) | (Kind::Float, Kind::Integer) => Kind::Float,
+            _ => Kind::Text,
+        }
+    }
+
+    fn rust_type(self) -> &'static str {
+        match self {
+            Kind::Bool => "bool",
+            Kind::Integer => "i64",
+            Kind::Float => "f64",
+            Kind::Unknown | Kind::Text => "String",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inference {
+    /// Element names in order of appearance.
+    order: Vec<String>,
+    elements: HashMap<String, ElementStats>,
+    roots: Vec<String>,
+}
+
+impl Inference {
+    fn stats(&mut self, name: &str) -> &mut ElementStats {
+        if !self.elements.contains_key(name) {
+            self.order.push(name.to_string());
+        }
+        self.elements.entry(name.to_string()).or_default()
+    }
+
+    fn read<R: Read>(&mut self, reader: R) -> Result<()> {
+        let config = ParserConfig::new()
+            .trim_whitespace(true)
+            .cdata_to_characters(true)
+            .coalesce_characters(true);
+        // Child counts of the open elements
+        let mut stack: Vec<(String, Vec<(String, usize)>)> = Vec::new();
+
+        for event in EventReader::new_with_config(reader, config) {
+            match event? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => {
+                    let name = name.local_name;
+                    match stack.last_mut() {
+                        Some((_, children)) => {
+                            match children.iter_mut().find(|(c, _)| *c == name) {
+                                Some((_, count)) => *count += 1,
+                                None => children.push((name.clone(), 1)),
+                            }
+                        }
+                        None if !self.roots.contains(&name) => self.roots.push(name.clone()),
+                        None => {}
+                    }
+
+                    let stats = self.stats(&name);
+                    stats.occurrences += 1;
+                    for attribute in attributes {
+                        let kind = Kind::of(&attribute.value);
+                        let attr_name = attribute.name.local_name;
+                        match stats.attributes.iter_mut().find(|(a, ..)| *a == attr_name) {
+                            Some((_, count, known)) => {
+                                *count += 1;
+                                *known = known.join(kind);
+                            }
+                            None => stats.attributes.push((attr_name, 1, kind)),
+                        }
+                    }
+                    stack.push((name, Vec::new()));
+                }
+                XmlEvent::Characters(text) => {
+                    if let Some((name, _)) = stack.last() {
+                        let name = name.clone();
+                        let stats = self.stats(&name);
+                        stats.with_text += 1;
+                        stats.text = stats.text.join(Kind::of(&text));
+                    }
+                }
+                XmlEvent::EndElement { .. } => {
+                    if let Some((name, children)) = stack.pop() {
+                        let stats = self.stats(&name);
+                        for (child, count) in children {
+                            let index = match stats.children.iter().position(|(c, _)| *c == child) {
+                                Some(index) => index,
+                                None => {
+                                    stats.children.push((child, ChildStats::default()));
+                                    stats.children.len() - 1
+                                }
+                            };
+                            let child_stats = &mut stats.children[index].1;
+                            child_stats.parents += 1;
+                            child_stats.max_per_parent = child_stats.max_per_parent.max(count);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(super) fn generate<I>(samples: I, module: &mut Module) -> Result<()>
+where
+    I: IntoIterator,
+    I::Item: Read,
+{
+    let mut inference = Inference::default();
+    for sample in samples {
+        inference.read(sample)?;
+    }
+
+    // Elements with attributes or children become structs, the others are read as text
+    let mut names = HashMap::new();
+    for element in &inference.order {
+        let stats = &inference.elements[element];
+        if !stats.attributes.is_empty()
+            || !stats.children.is_empty()
+            || inference.roots.contains(element)
+        {
+            names.insert(element.clone(), module.reserve_name(element));
+        }
+    }
+
+    for element in &inference.order {
+        let name = match names.get(element) {
+            Some(name) => name.clone(),
+            None => continue,
+        };
+        let stats = &inference.elements[element];
+        let mut fields = Vec::new();
+
+        for (attribute, count, kind) in &stats.attributes {
+            let occurs = if *count < stats.occurrences {
+                Occurs::Optional
+            } else {
+                Occurs::One
+            };
+            fields.push(Field {
+                xml_name: format!("@{}", attribute),
+                ty: FieldType::primitive(kind.rust_type()).with_occurs(occurs),
+            });
+        }
+
+        // Text alongside child elements is dropped, since `$value` would take the elements too
+        if stats.children.is_empty() && stats.with_text > 0 {
+            let occurs = if stats.with_text < stats.occurrences && stats.text != Kind::Text {
+                Occurs::Optional
+            } else {
+                Occurs::One
+            };
+            fields.push(Field {
+                xml_name: "$value".to_string(),
+                ty: FieldType::primitive(stats.text.rust_type()).with_occurs(occurs),
+            });
+        }
+
+        for (child, child_stats) in &stats.children {
+            let leaf = &inference.elements[child];
+            // A leaf without text in some samples is read as `None` there, or as an empty string
+            // if repeated
+            let empty = !names.contains_key(child) && leaf.with_text < leaf.occurrences;
+            let repeated = child_stats.max_per_parent > 1;
+            let optional = child_stats.parents < stats.occurrences;
+            let occurs = match (repeated, optional || empty) {
+                (true, _) => Occurs::Many { optional },
+                (false, true) => Occurs::Optional,
+                (false, false) => Occurs::One,
+            };
+            let ty = match names.get(child) {
+                Some(child_name) => FieldType::generated(child_name.clone()),
+                None if empty && repeated => FieldType::primitive(Kind::Text.rust_type()),
+                None => FieldType::primitive(leaf.text.rust_type()),
+            };
+            fields.push(Field {
+                xml_name: child.clone(),
+                ty: ty.with_occurs(occurs),
+            });
+        }
+
+        // Only the root element is named after its type
+        let xml_name = if inference.roots.contains(element) {
+            element.clone()
+        } else {
+            name.clone()
+        };
+        module.push(name, &xml_name, ItemKind::Struct(fields));
+    }
+    Ok(())
+}
@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+
+use super::{Field, FieldType, ItemKind, Module, Occurs, Variant};
+use crate::error::{Error, Result};
+use crate::schema::model::{ComplexType, ElementDecl, Particle, SimpleType, Term, TypeRef};
+use crate::schema::XsdSchema;
+
+pub(super) fn generate(schema: &XsdSchema, module: &mut Module) -> Result<()> {
+    let mut generator = Generator {
+        schema,
+        module,
+        named: HashMap::new(),
+    };
+    let mut roots: Vec<&ElementDecl> = schema.elements.values().collect();
+    roots.sort_by(|a, b| a.name.cmp(&b.name));
+    for root in roots {
+        generator.root(root)?;
+    }
+    Ok(())
+}
+
+/// The Rust type for a built-in XSD type.
+fn builtin_type(name: &str) -> &'static str {
+    match name {
+        "boolean" => "bool",
+        "byte" => "i8",
+        "short" => "i16",
+        "int" => "i32",
+        "long" | "integer" | "nonPositiveInteger" | "negativeInteger" => "i64",
+        "unsignedByte" => "u8",
+        "unsignedShort" => "u16",
+        "unsignedInt" => "u32",
+        "unsignedLong" | "nonNegativeInteger" | "positiveInteger" => "u64",
+        "float" => "f32",
+        "double" | "decimal" => "f64",
+        _ => "String",
+    }
+}
+
+/// Occurrences of a particle nested in another one.
+fn combine((min, max): (usize, Option<usize>), particle: &Particle) -> (usize, Option<usize>) {
+    let max = match (max, particle.max) {
+        (Some(a), Some(b)) => Some(a * b),
+        _ => None,
+    };
+    (min * particle.min, max)
+}
+
+fn occurs((min, max): (usize, Option<usize>)) -> Occurs {
+    match max {
+        Some(0) | Some(1) if min == 0 => Occurs::Optional,
+        Some(0) | Some(1) => Occurs::One,
+        _ => Occurs::Many { optional: min == 0 },
+    }
+}
+
+struct Generator<'a> {
+    schema: &'a XsdSchema,
+    module: &'a mut Module,
+    /// Generated items for the named types of the schema.
+    named: HashMap<String, String>,
+}
+
+impl<'a> Generator<'a> {
+    fn root(&mut self, element: &ElementDecl) -> Result<()> {
+        let name = match &element.type_ref {
+            TypeRef::Complex(complex) => {
+                let name = self.module.reserve_name(&element.name);
+                self.complex(name, complex)?
+            }
+            type_ref => match self.field_type(type_ref, &element.name)? {
+                ty if ty.generated && self.is_struct(&ty.base) => ty.base,
+                // Simple content at the root still needs a struct
+                ty => {
+                    let name = self.module.reserve_name(&element.name);
+                    let field = Field {
+                        xml_name: "$value".to_string(),
+                        ty,
+                    };
+                    self.module
+                        .push(name.clone(), &name, ItemKind::Struct(vec![field]));
+                    name
+                }
+            },
+        };
+        // The first root element of a type names it
+        if let Some(item) = self.module.items.iter_mut().find(|item| item.name == name) {
+            if item.xml_name == item.name {
+                item.xml_name = element.name.clone();
+            }
+        }
+        Ok(())
+    }
+
+    fn is_struct(&self, name: &str) -> bool {
+        matches!(
+            self.module.item(name),
+            Some(item) if matches!(item.kind, ItemKind::Struct(_))
+        )
+    }
+
+    fn field_type(&mut self, type_ref: &TypeRef, element_name: &str) -> Result<FieldType> {
+        match type_ref {
+            TypeRef::Builtin(name) => Ok(FieldType::primitive(builtin_type(name))),
+            TypeRef::Named(name) => {
+                if let Some(generated) = self.named.get(name) {
+                    return Ok(FieldType::generated(generated.clone()));
+                }
+                if let Some(complex) = self.schema.complex_types.get(name) {
+                    let generated = self.module.reserve_name(name);
+                    self.named.insert(name.clone(), generated.clone());
+                    self.complex(generated.clone(), complex)?;
+                    Ok(FieldType::generated(generated))
+                } else if let Some(simple) = self.schema.simple_types.get(name) {
+                    let ty = self.simple(simple, name)?;
+                    if ty.generated {
+                        self.named.insert(name.clone(), ty.base.clone());
+                    }
+                    Ok(ty)
+                } else {
+                    Err(Error::InvalidSchema {
+                        message: format!("unknown type '{}'", name),
+                    })
+                }
+            }
+            TypeRef::Complex(complex) => {
+                let name = self.module.reserve_name(element_name);
+                Ok(FieldType::generated(self.complex(name, complex)?))
+            }
+            TypeRef::Simple(simple) => self.simple(simple, element_name),
+            TypeRef::AnyType => Ok(FieldType::primitive("serde_xml_rs::RawXml")),
+        }
+    }
+
+    /// A unit-only enum for an enumeration, or the Rust type of the base type otherwise.
+    fn simple(&mut self, simple: &SimpleType, name: &str) -> Result<FieldType> {
+        match simple {
+            SimpleType::Restriction { facets, .. } if !facets.enumeration.is_empty() => {
+                let generated = self.module.reserve_name(name);
+                let variants = facets
+                    .enumeration
+                    .iter()
+                    .map(|value| Variant {
+                        xml_name: value.clone(),
+                        ty: None,
+                    })
+                    .collect();
+                self.module
+                    .push(generated.clone(), &generated, ItemKind::Enum(variants));
+                Ok(FieldType::generated(generated))
+            }
+            SimpleType::Restriction { base, .. } => self.field_type(base, name),
+            SimpleType::List(_) | SimpleType::Union(_) => Ok(FieldType::primitive("String")),
+        }
+    }
+
+    /// Generates a struct for a complex type, returning its name.
+    fn complex(&mut self, name: String, complex: &ComplexType) -> Result<String> {
+        // Push the item first, so that types are emitted before the types they contain
+        let index = self.module.items.len();
+        self.module
+            .push(name.clone(), &name, ItemKind::Struct(Vec::new()));
+
+        let complex = self.schema.resolve_complex(complex)?;
+        let mut fields = Vec::new();
+        for attribute in &complex.attributes {
+            let occurs = if attribute.required {
+                Occurs::One
+            } else {
+                Occurs::Optional
+            };
+            fields.push(Field {
+                xml_name: format!("@{}", attribute.name),
+                ty: self
+                    .field_type(&attribute.type_ref, &attribute.name)?
+                    .with_occurs(occurs),
+            });
+        }
+        if complex.any_attribute {
+            fields.push(Field {
+                xml_name: "@*".to_string(),
+                ty: FieldType::primitive("std::collections::BTreeMap<String, String>"),
+            });
+        }
+        if let Some(simple_content) = &complex.simple_content {
+            fields.push(Field {
+                xml_name: "$value".to_string(),
+                ty: self.field_type(simple_content, &name)?,
+            });
+        }
+
+        if let Some(particle) = &complex.content {
+            match choice(particle) {
+                // A choice of elements is read in place, as an enum
+                Some(choice) => {
+                    let mut elements = Vec::new();
+                    self.elements(choice, &mut elements)?;
+                    let content = self.choice(&format!("{}Content", name), elements)?;
+                    fields.push(Field {
+                        xml_name: "$value".to_string(),
+                        ty: content.with_occurs(occurs(combine((1, Some(1)), choice))),
+                    });
+                }
+                // Elements that may interleave can't be read into a field each, so all of them
+                // are read in place, as a sequence of an enum
+                None if self.interleaves(particle)? => {
+                    let mut elements = Vec::new();
+                    self.elements(particle, &mut elements)?;
+                    let content = self.choice(&format!("{}Content", name), elements)?;
+                    fields.push(Field {
+                        xml_name: "$value".to_string(),
+                        ty: content.with_occurs(Occurs::Many { optional: true }),
+                    });
+                }
+                None => self.flatten(particle, (1, Some(1)), &mut fields)?,
+            }
+        }
+
+        self.module.items[index].kind = ItemKind::Struct(fields);
+        Ok(name)
+    }
+
+    /// Generates an enum with a variant for each of the elements.
+    fn choice(&mut self, name: &str, elements: Vec<ElementDecl>) -> Result<FieldType> {
+        let generated = self.module.reserve_name(name);
+        let index = self.module.items.len();
+        self.module
+            .push(generated.clone(), &generated, ItemKind::Enum(Vec::new()));
+
+        let mut variants = Vec::new();
+        for element in elements {
+            // Elements without attributes or content are unit variants
+            let ty = if self.is_empty(&element.type_ref) {
+                None
+            } else {
+                Some(self.field_type(&element.type_ref, &element.name)?)
+            };
+            variants.push(Variant {
+                xml_name: element.name,
+                ty,
+            });
+        }
+
+        self.module.items[index].kind = ItemKind::Enum(variants);
+        Ok(FieldType::generated(generated))
+    }
+
+    fn is_empty(&self, type_ref: &TypeRef) -> bool {
+        let complex = match type_ref {
+            TypeRef::Complex(complex) => complex,
+            TypeRef::Named(name) => match self.schema.complex_types.get(name) {
+                Some(complex) => complex,
+                None => return false,
+            },
+            _ => return false,
+        };
+        complex.content.is_none()
+            && complex.attributes.is_empty()
+            && complex.simple_content.is_none()
+            && complex.base.is_none()
+            && !complex.any_attribute
+    }
+
+    fn global_element(&self, name: &str) -> Result<ElementDecl> {
+        self.schema
+            .elements
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::InvalidSchema {
+                message: format!("unknown element '{}'", name),
+            })
+    }
+
+    /// Collects the elements of a particle and of the particles nested in it, once each.
+    fn elements(&self, particle: &Particle, elements: &mut Vec<ElementDecl>) -> Result<()> {
+        let element = match &particle.term {
+            Term::Element(element) => element.clone(),
+            Term::ElementRef(reference) => self.global_element(reference)?,
+            Term::Sequence(particles) | Term::Choice(particles) | Term::All(particles) => {
+                for particle in particles {
+                    self.elements(particle, elements)?;
+                }
+                return Ok(());
+            }
+            Term::Any => return Ok(()),
+        };
+        if !elements.iter().any(|known| known.name == element.name) {
+            elements.push(element);
+        }
+        Ok(())
+    }
+
+    /// Whether the elements of a particle may come interleaved: a group of different elements
+    /// that repeats, such as a choice with `maxOccurs="unbounded"`, or an element appearing in
+    /// several places.
+    fn interleaves(&self, particle: &Particle) -> Result<bool> {
+        let mut names = Vec::new();
+        Ok(self.repeats_group(particle, (1, Some(1)), &mut names)?
+            || names
+                .iter()
+                .enumerate()
+                .any(|(index, name)| names[..index].contains(name)))
+    }
+
+    /// Whether a group of different elements within a particle repeats, collecting the names of
+    /// the elements in the order they appear.
+    fn repeats_group(
+        &self,
+        particle: &Particle,
+        outer: (usize, Option<usize>),
+        names: &mut Vec<String>,
+    ) -> Result<bool> {
+        let (min, max) = combine(outer, particle);
+        let particles = match &particle.term {
+            Term::Element(element) => {
+                names.push(element.name.clone());
+                return Ok(false);
+            }
+            Term::ElementRef(reference) => {
+                names.push(self.global_element(reference)?.name);
+                return Ok(false);
+            }
+            Term::Sequence(particles) | Term::Choice(particles) | Term::All(particles) => particles,
+            Term::Any => return Ok(false),
+        };
+        let mut repeats = false;
+        for particle in particles {
+            repeats |= self.repeats_group(particle, (min, max), names)?;
+        }
+        let mut elements = Vec::new();
+        self.elements(particle, &mut elements)?;
+        Ok(repeats || (!matches!(max, Some(0) | Some(1)) && elements.len() > 1))
+    }
+
+    /// Collects fields for the elements of a particle, nested in particles with the given
+    /// occurrences.
+    fn flatten(
+        &mut self,
+        particle: &Particle,
+        outer: (usize, Option<usize>),
+        fields: &mut Vec<Field>,
+    ) -> Result<()> {
+        let (min, max) = combine(outer, particle);
+        let element = match &particle.term {
+            Term::Element(element) => element.clone(),
+            Term::ElementRef(reference) => self.global_element(reference)?,
+            Term::Sequence(particles) | Term::All(particles) => {
+                for particle in particles {
+                    self.flatten(particle, (min, max), fields)?;
+                }
+                return Ok(());
+            }
+            // Any of the elements of a choice may be missing
+            Term::Choice(particles) => {
+                for particle in particles {
+                    self.flatten(particle, (0, max), fields)?;
+                }
+                return Ok(());
+            }
+            Term::Any => {
+                if !fields.iter().any(|field| field.xml_name == "$unknown") {
+                    fields.push(Field {
+                        xml_name: "$unknown".to_string(),
+                        ty: FieldType::primitive("serde_xml_rs::RawXml")
+                            .with_occurs(Occurs::Many { optional: true }),
+                    });
+                }
+                return Ok(());
+            }
+        };
+
+        // An element appearing several times in the content is repeated
+        if let Some(field) = fields.iter_mut().find(|f| f.xml_name == element.name) {
+            field.ty.occurs = Occurs::Many { optional: true };
+            return Ok(());
+        }
+        let ty = self.field_type(&element.type_ref, &element.name)?;
+        fields.push(Field {
+            xml_name: element.name,
+            ty: ty.with_occurs(occurs((min, max))),
+        });
+        Ok(())
+    }
+}
+
+/// The choice making up the whole content of a complex type, if any.
+fn choice(particle: &Particle) -> Option<&Particle> {
+    match &particle.term {
+        Term::Choice(_) => Some(particle),
+        Term::Sequence(particles) if particles.len() == 1 && particle.max == Some(1) => {
+            choice(&particles[0])
+        }
+        _ => None,
+    }
+}
@@ -1,6 +1,9 @@
 use crate::debug_expect;
+use crate::document::{Prolog, XmlDeclaration};
 use crate::error::Result;
+use crate::schema::Validator;
 use std::{collections::VecDeque, io::Read};
+use xml::common::{Position, TextPosition};
 use xml::reader::{EventReader, XmlEvent};
 
 /// Retrieve XML events from an underlying reader.
@@ -11,22 +14,139 @@ pub trait BufferedXmlReader<R: Read> {
     /// Get the next event without consuming.
     fn peek(&mut self) -> Result<&XmlEvent>;
 
+    /// Get the position in the document of the next event, without consuming it.
+    fn peek_position(&mut self) -> Result<TextPosition>;
+
     /// Spawn a child buffer whose cursor starts at the same position as this buffer.
     fn child_buffer<'a>(&'a mut self) -> ChildXmlBuffer<'a, R>;
 }
 
-pub struct RootXmlBuffer<R: Read> {
+/// The underlying reader, along with what has been read of the document outside of the buffered
+/// events.
+pub struct XmlSource<R: Read> {
     reader: EventReader<R>,
+    /// Whether comments and processing instructions are passed on instead of being skipped.
+    keep_misc: bool,
+    prolog: Prolog,
+    /// Whether the root element has been reached, which ends the prolog.
+    in_root: bool,
+    validator: Option<Validator>,
+    /// An event read past the end of some text, while looking for more of it.
+    pending: Option<(XmlEvent, TextPosition)>,
+}
+
+impl<R: Read> XmlSource<R> {
+    /// Reads the next XML event we're interested in, and its position.
+    fn next_significant_event(&mut self) -> Result<(XmlEvent, TextPosition)> {
+        let (first, position) = match self.pending.take() {
+            Some(event) => event,
+            None => self.next_reader_event()?,
+        };
+        let (mut text, mut all_cdata) = match first {
+            XmlEvent::Characters(text) => (text, false),
+            XmlEvent::CData(text) => (text, true),
+            other => return Ok((other, position)),
+        };
+        // Adjacent text and CDATA sections are read as a single text event, which is a CDATA
+        // event if all of it is in CDATA sections
+        loop {
+            match self.next_reader_event()? {
+                (XmlEvent::Characters(more), _) => {
+                    text.push_str(&more);
+                    all_cdata = false;
+                }
+                (XmlEvent::CData(more), _) => text.push_str(&more),
+                other => {
+                    self.pending = Some(other);
+                    break;
+                }
+            }
+        }
+        let text = if all_cdata {
+            XmlEvent::CData(text)
+        } else {
+            XmlEvent::Characters(text)
+        };
+        Ok((text, position))
+    }
+
+    /// Reads the next XML event from the underlying reader, skipping events we're not interested
+    /// in. The declaration and processing instructions before the root element are recorded in
+    /// the prolog.
+    fn next_reader_event(&mut self) -> Result<(XmlEvent, TextPosition)> {
+        loop {
+            match self.reader.next()? {
+                XmlEvent::StartDocument {
+                    version,
+                    encoding,
+                    standalone,
+                } => {
+                    self.prolog.declaration = Some(XmlDeclaration {
+                        version,
+                        encoding: Some(encoding),
+                        standalone,
+                    });
+                }
+                XmlEvent::ProcessingInstruction { name, data } if !self.in_root => {
+                    self.prolog.processing_instructions.push(match data {
+                        Some(data) => format!("{} {}", name, data),
+                        None => name,
+                    });
+                }
+                XmlEvent::Whitespace { .. } => { /* skip */ }
+                XmlEvent::ProcessingInstruction { .. } | XmlEvent::Comment(_)
+                    if !self.keep_misc =>
+                { /* skip */ }
+                other => {
+                    if let XmlEvent::StartElement { .. } = other {
+                        self.in_root = true;
+                    }
+                    if let Some(validator) = &mut self.validator {
+                        validator.check(&other)?;
+                    }
+                    return Ok((other, self.reader.position()));
+                }
+            }
+        }
+    }
+}
+
+pub struct RootXmlBuffer<R: Read> {
+    source: XmlSource<R>,
     buffer: VecDeque<CachedXmlEvent>,
 }
 
 impl<R: Read> RootXmlBuffer<R> {
     pub fn new(reader: EventReader<R>) -> Self {
         RootXmlBuffer {
-            reader,
+            source: XmlSource {
+                reader,
+                keep_misc: false,
+                prolog: Prolog {
+                    declaration: None,
+                    ..Prolog::default()
+                },
+                in_root: false,
+                validator: None,
+                pending: None,
+            },
             buffer: VecDeque::new(),
         }
     }
+
+    pub fn set_keep_misc(&mut self, keep_misc: bool) {
+        self.source.keep_misc = keep_misc;
+    }
+
+    /// Checks the events against a schema as they are read from the underlying reader.
+    pub fn set_validator(&mut self, validator: Validator) {
+        self.source.validator = Some(validator);
+    }
+
+    /// The prolog read so far.
+    pub fn prolog(&self) -> &Prolog {
+        &self.source.prolog
+    }
 }
 
 impl<R: Read> BufferedXmlReader<R> for RootXmlBuffer<R> {
@@ -34,21 +154,26 @@ impl<R: Read> BufferedXmlReader<R> for RootXmlBuffer<R> {
     fn next(&mut self) -> Result<XmlEvent> {
         loop {
             match self.buffer.pop_front() {
-                Some(CachedXmlEvent::Unused(ev)) => break Ok(ev),
+                Some(CachedXmlEvent::Unused(ev, _)) => break Ok(ev),
                 Some(CachedXmlEvent::Used) => continue,
-                None => break next_significant_event(&mut self.reader),
+                None => break self.source.next_significant_event().map(|(ev, _)| ev),
             }
         }
     }
 
     fn peek(&mut self) -> Result<&XmlEvent> {
-        get_from_buffer_or_reader(&mut self.buffer, &mut self.reader, &mut 0)
+        get_from_buffer_or_reader(&mut self.buffer, &mut self.source, &mut 0).map(|(ev, _)| ev)
+    }
+
+    fn peek_position(&mut self) -> Result<TextPosition> {
+        get_from_buffer_or_reader(&mut self.buffer, &mut self.source, &mut 0)
+            .map(|(_, position)| position)
     }
 
     fn child_buffer<'root>(&'root mut self) -> ChildXmlBuffer<'root, R> {
-        let RootXmlBuffer { reader, buffer } = self;
+        let RootXmlBuffer { source, buffer } = self;
         ChildXmlBuffer {
-            reader,
+            source,
             buffer,
             cursor: 0,
         }
@@ -56,7 +181,7 @@ impl<R: Read> BufferedXmlReader<R> for RootXmlBuffer<R> {
 }
 
 pub struct ChildXmlBuffer<'parent, R: Read> {
-    reader: &'parent mut EventReader<R>,
+    source: &'parent mut XmlSource<R>,
     buffer: &'parent mut VecDeque<CachedXmlEvent>,
     cursor: usize,
 }
@@ -78,14 +203,14 @@ impl<'parent, R: Read> BufferedXmlReader<R> for ChildXmlBuffer<'parent, R> {
     fn next(&mut self) -> Result<XmlEvent> {
         loop {
             match self.buffer.get_mut(self.cursor) {
-                Some(entry @ CachedXmlEvent::Unused(_)) => {
+                Some(entry @ CachedXmlEvent::Unused(..)) => {
                     let taken = if self.cursor == 0 {
                         self.buffer.pop_front().unwrap()
                     } else {
                         std::mem::replace(entry, CachedXmlEvent::Used)
                     };
 
-                    return debug_expect!(taken, CachedXmlEvent::Unused(ev) => Ok(ev));
+                    return debug_expect!(taken, CachedXmlEvent::Unused(ev, _) => Ok(ev));
                 }
                 Some(CachedXmlEvent::Used) => {
                     debug_assert!(
@@ -99,25 +224,30 @@ impl<'parent, R: Read> BufferedXmlReader<R> for ChildXmlBuffer<'parent, R> {
                     debug_assert_eq!(self.buffer.len(), self.cursor);
 
                     // Skip creation of buffer entry when consuming event straight away
-                    return next_significant_event(&mut self.reader);
+                    return self.source.next_significant_event().map(|(ev, _)| ev);
                 }
             }
         }
     }
 
     fn peek(&mut self) -> Result<&XmlEvent> {
-        get_from_buffer_or_reader(self.buffer, self.reader, &mut self.cursor)
+        get_from_buffer_or_reader(self.buffer, self.source, &mut self.cursor).map(|(ev, _)| ev)
+    }
+
+    fn peek_position(&mut self) -> Result<TextPosition> {
+        get_from_buffer_or_reader(self.buffer, self.source, &mut self.cursor)
+            .map(|(_, position)| position)
     }
 
     fn child_buffer<'a>(&'a mut self) -> ChildXmlBuffer<'a, R> {
         let ChildXmlBuffer {
-            reader,
+            source,
             buffer,
             cursor,
         } = self;
 
         ChildXmlBuffer {
-            reader,
+            source,
             buffer,
             cursor: *cursor,
         }
@@ -126,44 +256,39 @@ impl<'parent, R: Read> BufferedXmlReader<R> for ChildXmlBuffer<'parent, R> {
 
 #[derive(Debug)]
 enum CachedXmlEvent {
-    Unused(XmlEvent),
+    Unused(XmlEvent, TextPosition),
     Used,
 }
 
 fn get_from_buffer_or_reader<'buf>(
     buffer: &'buf mut VecDeque<CachedXmlEvent>,
-    reader: &mut EventReader<impl Read>,
+    source: &mut XmlSource<impl Read>,
     index: &mut usize,
-) -> Result<&'buf XmlEvent> {
+) -> Result<(&'buf XmlEvent, TextPosition)> {
     // We should only be attempting to get an event already in the buffer, or the next event to place in the buffer
     debug_assert!(*index <= buffer.len());
 
     loop {
         match buffer.get_mut(*index) {
-            Some(CachedXmlEvent::Unused(_)) => break,
+            Some(CachedXmlEvent::Unused(..)) => break,
             Some(CachedXmlEvent::Used) => {
                 *index += 1;
             }
             None => {
-                let next = next_significant_event(reader)?;
-                buffer.push_back(CachedXmlEvent::Unused(next));
+                let (next, position) = source.next_significant_event()?;
+                buffer.push_back(CachedXmlEvent::Unused(next, position));
             }
         }
     }
 
     // Returning of borrowed data must be done after of loop/match due to current limitation of borrow checker
-    debug_expect!(buffer.get_mut(*index), Some(CachedXmlEvent::Unused(event)) => Ok(event))
+    debug_expect!(buffer.get_mut(*index), Some(CachedXmlEvent::Unused(event, position)) => Ok((&*event, *position)))
 }
 
-/// Reads the next XML event from the underlying reader, skipping events we're not interested in.
-fn next_significant_event(reader: &mut EventReader<impl Read>) -> Result<XmlEvent> {
-    loop {
-        match reader.next()? {
-            XmlEvent::StartDocument { .. }
-            | XmlEvent::ProcessingInstruction { .. }
-            | XmlEvent::Whitespace { .. }
-            | XmlEvent::Comment(_) => { /* skip */ }
-            other => return Ok(other),
-        }
-    }
+/// Whether an event is a comment or a processing instruction.
+pub fn is_misc(event: &XmlEvent) -> bool {
+    matches!(
+        event,
+        XmlEvent::ProcessingInstruction { .. } | XmlEvent::Comment(_)
+    )
 }
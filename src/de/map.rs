@@ -1,36 +1,428 @@
 use std::io::Read;
+use std::rc::Rc;
 
-use serde::de::{self, IntoDeserializer, Unexpected};
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, Deserialize, IntoDeserializer, Unexpected};
 use serde::forward_to_deserialize_any;
 use xml::attribute::OwnedAttribute;
+use xml::name::OwnedName;
+use xml::namespace::{Namespace, NS_EMPTY_URI, NS_NO_PREFIX, NS_XMLNS_PREFIX, NS_XML_PREFIX};
 use xml::reader::XmlEvent;
 
+use crate::anchored::ANCHORED_TOKEN;
 use crate::error::{Error, Result};
-use crate::Deserializer;
+use crate::layout::BoolFormat;
+use crate::layout::MapLayout;
+use crate::{expect, Deserializer};
 
 use super::buffer::BufferedXmlReader;
+use super::raw::capture_element;
+use super::{misc_event, parse_float, DuplicateElements, COMMENT, PROCESSING_INSTRUCTION};
+
+/// Special field name collecting the attributes that don't match any other field.
+const EXTRA_ATTRIBUTES: &str = "@*";
+/// Special field name collecting the child elements that don't match any other field.
+const UNKNOWN_ELEMENTS: &str = "$unknown";
+
+/// Value to be read by the visitor call to `next_value_seed` following a `next_key_seed`.
+enum PendingValue {
+    Attr(String),
+    ExtraAttrs(Vec<(String, String)>),
+    UnknownElements,
+    /// Comments or processing instructions, depending on the special name.
+    Misc(&'static str),
+}
 
 pub struct MapAccess<'a, R: Read, B: BufferedXmlReader<R>> {
+    /// Local name of the element whose contents are being read; used in error messages.
+    element: String,
     attrs: ::std::vec::IntoIter<OwnedAttribute>,
-    /// Cache of attribute value, populated when visitor calls `next_key_seed`; should be read & emptied straight after
+    /// Attributes that don't match any field, if the struct has a `@*` field to collect them.
+    extra_attrs: Option<Vec<(String, String)>>,
+    /// Whether child elements that don't match any field are collected into a `$unknown` field,
+    /// and if so whether that field has been visited yet.
+    unknown_elements: Option<bool>,
+    /// Whether comments are collected into a `$comment` field, and if so whether that field has
+    /// been visited yet.
+    comments: Option<bool>,
+    /// Same as `comments`, for processing instructions and the `$pi` field.
+    processing_instructions: Option<bool>,
+    /// Whether a `$value` key has been emitted, so that a comment or processing instruction only
+    /// starts the `$value` field if nothing else did.
+    value_seen: bool,
+    /// Cache of the value, populated when visitor calls `next_key_seed`; should be read & emptied straight after
     /// by visitor call to `next_value_seed`
-    next_attr_value: Option<String>,
+    next_value: Option<PendingValue>,
     de: &'a mut Deserializer<R, B>,
     /// Whether this `MapAccess` is to deserialize all inner contents of an outer element.
     inner_value: bool,
+    /// Fields of the struct being deserialized, if any.
+    fields: Option<&'static [&'static str]>,
+    /// Index in `fields` of the last child element seen, for element order enforcement.
+    last_field_index: usize,
+    /// Names of the child elements read so far, if a duplicate element policy is set.
+    seen: Vec<String>,
+    /// Names of the items inside the element whose key was just emitted, if it's a wrapper for a
+    /// field named with a path such as `Items/Item`.
+    item_path: Vec<String>,
+    /// Namespaces in scope inside the element.
+    namespace: Rc<Namespace>,
 }
 
 impl<'a, R: 'a + Read, B: BufferedXmlReader<R>> MapAccess<'a, R, B> {
     pub fn new(
         de: &'a mut Deserializer<R, B>,
+        element: String,
         attrs: Vec<OwnedAttribute>,
-        inner_value: bool,
+        namespace: Namespace,
+        fields: Option<&'static [&'static str]>,
     ) -> Self {
+        let inner_value = fields.is_some_and(|fields| fields.contains(&"$value"));
+        let (attrs, extra_attrs) = match fields {
+            Some(fields) if fields.contains(&EXTRA_ATTRIBUTES) => {
+                let (known, extra): (Vec<_>, Vec<_>) = attrs
+                    .into_iter()
+                    .partition(|attr| is_known_attribute(fields, &attr.name.local_name));
+                // Namespace declarations are attributes too, as far as the element is concerned
+                let mut extra_attrs =
+                    declared_namespaces(&namespace, de.outer_namespace.as_deref());
+                extra_attrs.extend(
+                    extra
+                        .into_iter()
+                        .map(|attr| (qualified_name(&attr.name), attr.value)),
+                );
+                (known, Some(extra_attrs))
+            }
+            _ => (attrs, None),
+        };
+        let unknown_elements = match fields {
+            Some(fields) if !inner_value && fields.contains(&UNKNOWN_ELEMENTS) => Some(false),
+            _ => None,
+        };
+        let collects = |name| match fields {
+            Some(fields) if fields.contains(&name) => Some(false),
+            _ => None,
+        };
+
         MapAccess {
+            element,
             attrs: attrs.into_iter(),
-            next_attr_value: None,
-            de: de,
-            inner_value: inner_value,
+            extra_attrs,
+            unknown_elements,
+            comments: collects(COMMENT),
+            processing_instructions: collects(PROCESSING_INSTRUCTION),
+            value_seen: false,
+            next_value: None,
+            de,
+            inner_value,
+            fields,
+            last_field_index: 0,
+            seen: Vec::new(),
+            item_path: Vec::new(),
+            namespace: Rc::new(namespace),
+        }
+    }
+
+    /// The key for an attribute: the name of the field it's read into, if any.
+    fn attribute_key(&self, name: String) -> String {
+        match self
+            .fields
+            .and_then(|fields| attribute_field(fields, &name))
+        {
+            Some(field) => field.to_string(),
+            None => name,
+        }
+    }
+
+    /// The key for a child element: the name of the field it's read into, which is the element
+    /// name itself unless the field is named with a path starting with it.
+    fn element_key(&self, name: &str) -> String {
+        match self.fields.and_then(|fields| field_for(fields, name)) {
+            Some(field) => field.to_string(),
+            None => name.to_string(),
+        }
+    }
+
+    /// Whether a child element should be collected into the `$unknown` field.
+    fn is_unknown_element(&self, name: &str) -> bool {
+        match (self.fields, self.unknown_elements) {
+            (Some(fields), Some(_)) => field_for(fields, name).is_none(),
+            _ => false,
+        }
+    }
+
+    /// Emits the `$unknown` key, if the struct has such a field and it hasn't been visited yet.
+    fn next_unknown_elements_key<'de, K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>> {
+        match self.unknown_elements {
+            Some(false) => {
+                self.unknown_elements = Some(true);
+                self.next_value = Some(PendingValue::UnknownElements);
+                seed.deserialize(UNKNOWN_ELEMENTS.into_deserializer())
+                    .map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Emits the first of the `$unknown`, `$comment` and `$pi` keys not visited yet, so that these
+    /// fields are present even if there was nothing to collect.
+    fn next_collected_key<'de, K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>> {
+        if self.unknown_elements == Some(false) {
+            return self.next_unknown_elements_key(seed);
+        }
+        match self
+            .next_misc_key(COMMENT)
+            .or_else(|| self.next_misc_key(PROCESSING_INSTRUCTION))
+        {
+            Some(key) => seed.deserialize(key.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Marks the `$comment` or `$pi` field as visited and returns its key, if the struct has such
+    /// a field and it hasn't been visited yet.
+    fn next_misc_key(&mut self, name: &'static str) -> Option<&'static str> {
+        let collected = if name == COMMENT {
+            &mut self.comments
+        } else {
+            &mut self.processing_instructions
+        };
+        match collected {
+            Some(false) => {
+                *collected = Some(true);
+                self.next_value = Some(PendingValue::Misc(name));
+                Some(name)
+            }
+            _ => None,
+        }
+    }
+
+    /// Finds the key for the comments and processing instructions at the cursor. They are
+    /// collected into their special field if the struct has one, or else start the `$value`
+    /// field if it hasn't been started yet. The others are skipped.
+    fn misc_key(&mut self) -> Result<Option<&'static str>> {
+        while let Some((name, _)) = misc_event(self.de.peek_raw()?) {
+            if let Some(key) = self.next_misc_key(name) {
+                return Ok(Some(key));
+            }
+            if self.inner_value && !self.value_seen {
+                self.value_seen = true;
+                return Ok(Some("$value"));
+            }
+            self.de.buffered_reader.next()?;
+        }
+        Ok(None)
+    }
+
+    /// Collects all remaining comments or processing instructions directly inside the element,
+    /// wherever they are among its children. Each of them comes with the field of the next known
+    /// element, if any, so that it can be read into an `Anchored` item.
+    fn collect_misc(&mut self, name: &'static str) -> Result<Vec<Captured>> {
+        let fields = self.fields.unwrap_or_default();
+        let mut child = self.de.child();
+        let mut collected = Vec::new();
+        let mut local_depth = 0usize;
+
+        loop {
+            let event = child.buffered_reader.peek()?;
+            match misc_event(event) {
+                Some((found, text)) if local_depth == 0 && found == name => {
+                    collected.push(Captured { text, before: None });
+                    child.buffered_reader.next()?;
+                    continue;
+                }
+                _ => {}
+            }
+            match event {
+                XmlEvent::StartElement { name, .. } => {
+                    if local_depth == 0 {
+                        if let Some(field) = field_for(fields, &name.local_name) {
+                            anchor_before(&mut collected, field);
+                        }
+                    }
+                    local_depth += 1;
+                }
+                XmlEvent::EndElement { .. } if local_depth > 0 => local_depth -= 1,
+                XmlEvent::EndElement { .. } | XmlEvent::EndDocument => break,
+                _ => {}
+            }
+            child.buffered_reader.skip();
+        }
+
+        Ok(collected)
+    }
+
+    /// Captures all remaining child elements that don't match any field, wherever they are among
+    /// their siblings. Elements matching a field are left in place for their own field. Each
+    /// captured element comes with the field of the next known element, if any, so that it can be
+    /// read into an `Anchored` item.
+    fn capture_unknown_elements(&mut self) -> Result<Vec<Captured>> {
+        let fields = self.fields.unwrap_or_default();
+        let mut child = self.de.child();
+        let mut captured: Vec<Captured> = Vec::new();
+        let mut local_depth = 0usize;
+
+        loop {
+            match child.buffered_reader.peek()? {
+                XmlEvent::StartElement { name, .. } if local_depth == 0 => {
+                    match field_for(fields, &name.local_name) {
+                        Some(field) => {
+                            anchor_before(&mut captured, field);
+                            local_depth += 1;
+                            child.buffered_reader.skip();
+                        }
+                        None => captured.push(Captured {
+                            text: capture_element(&mut child.buffered_reader)?,
+                            before: None,
+                        }),
+                    }
+                }
+                XmlEvent::StartElement { .. } => {
+                    local_depth += 1;
+                    child.buffered_reader.skip();
+                }
+                XmlEvent::EndElement { .. } if local_depth > 0 => {
+                    local_depth -= 1;
+                    child.buffered_reader.skip();
+                }
+                XmlEvent::EndElement { .. } | XmlEvent::EndDocument => break,
+                _ => child.buffered_reader.skip(),
+            }
+        }
+
+        Ok(captured)
+    }
+
+    /// Reads the value of a child element, or the text of the element itself for `$value`.
+    fn next_child_value<'de, V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        self.de.item_path = std::mem::take(&mut self.item_path);
+        self.de.field_item_names = None;
+        if !self.inner_value {
+            if let XmlEvent::StartElement { .. } = *self.de.peek()? {
+                self.de.set_map_value();
+            }
+        }
+        if self.de.duplicate_elements == Some(DuplicateElements::Collect) {
+            let search_non_contiguous = self.de.non_contiguous_seq_elements;
+            self.de.non_contiguous_seq_elements = true;
+            let result = seed.deserialize(&mut *self.de);
+            self.de.non_contiguous_seq_elements = search_non_contiguous;
+            return result;
+        }
+        let result = seed.deserialize(&mut *self.de);
+        self.de.last_of_adjacent = false;
+        result
+    }
+
+    fn check_attribute(&self, name: &str) -> Result<()> {
+        match self.fields {
+            Some(fields) if self.de.strict.deny_unknown_attributes => {
+                if is_known_attribute(fields, name) {
+                    Ok(())
+                } else {
+                    Err(Error::UnknownAttribute {
+                        name: name.to_string(),
+                        element: self.element.clone(),
+                    })
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_text(&self) -> Result<()> {
+        match self.fields {
+            Some(_) if self.de.strict.deny_unexpected_text && !self.inner_value => {
+                Err(Error::UnexpectedText {
+                    element: self.element.clone(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Applies the duplicate element policy, if any, to the child element at the cursor. Returns
+    /// whether the element is to be skipped.
+    fn skip_duplicate(&mut self, name: &str) -> Result<bool> {
+        let policy = match self.de.duplicate_elements {
+            Some(policy) if !self.inner_value => policy,
+            _ => return Ok(false),
+        };
+        if let Some(fields) = self.fields {
+            if !fields.contains(&name) {
+                return Ok(false);
+            }
+        }
+        let repeated = self.seen.iter().any(|seen| seen == name);
+        match policy {
+            DuplicateElements::FirstWins if repeated => Ok(true),
+            // A map keeps the last value for a key anyway
+            DuplicateElements::LastWins if repeated && self.fields.is_none() => Ok(false),
+            _ if repeated => Err(Error::DuplicateElement {
+                name: name.to_string(),
+                element: self.element.clone(),
+                position: self.de.buffered_reader.peek_position()?,
+            }),
+            DuplicateElements::LastWins if self.has_later_occurrence(name)? => Ok(true),
+            _ => {
+                // Adjacent elements are all read if the value is a sequence, and only the last
+                // of them otherwise, which the value decides as it is read
+                self.de.last_of_adjacent = policy == DuplicateElements::LastWins;
+                self.seen.push(name.to_string());
+                Ok(false)
+            }
+        }
+    }
+
+    /// Whether another child element with the given name follows the one at the cursor,
+    /// separated from it by an element with another name.
+    fn has_later_occurrence(&mut self, name: &str) -> Result<bool> {
+        let mut child = self.de.child();
+        let buffer = &mut child.buffered_reader;
+        let mut depth = 0usize;
+        let mut adjacent = true;
+        loop {
+            match buffer.peek()? {
+                XmlEvent::StartElement { name: element, .. } => {
+                    if depth == 0 {
+                        if element.local_name != name {
+                            adjacent = false;
+                        } else if !adjacent {
+                            return Ok(true);
+                        }
+                    }
+                    depth += 1;
+                }
+                XmlEvent::EndElement { .. } if depth == 0 => return Ok(false),
+                XmlEvent::EndElement { .. } => depth -= 1,
+                XmlEvent::EndDocument => return Ok(false),
+                _ => {}
+            }
+            buffer.skip();
+        }
+    }
+
+    fn check_element_order(&mut self, name: &str) -> Result<()> {
+        match self.fields {
+            Some(fields) if self.de.strict.enforce_element_order && !self.inner_value => {
+                if let Some(index) = fields.iter().position(|field| *field == name) {
+                    if index < self.last_field_index {
+                        return Err(Error::OutOfOrderElement {
+                            name: name.to_string(),
+                            after: fields[self.last_field_index].to_string(),
+                        });
+                    }
+                    self.last_field_index = index;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
         }
     }
 }
@@ -39,44 +431,88 @@ impl<'de, 'a, R: 'a + Read, B: BufferedXmlReader<R>> de::MapAccess<'de> for MapA
     type Error = Error;
 
     fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
-        debug_assert_eq!(self.next_attr_value, None);
+        debug_assert!(self.next_value.is_none());
         match self.attrs.next() {
             // Read all attributes first
             Some(OwnedAttribute { name, value }) => {
-                self.next_attr_value = Some(value);
-                seed.deserialize(name.local_name.into_deserializer())
+                self.check_attribute(&name.local_name)?;
+                self.next_value = Some(PendingValue::Attr(value));
+                seed.deserialize(self.attribute_key(name.local_name).into_deserializer())
+                    .map(Some)
+            }
+            None if self.extra_attrs.is_some() => {
+                let extra_attrs = self.extra_attrs.take().unwrap_or_default();
+                self.next_value = Some(PendingValue::ExtraAttrs(extra_attrs));
+                seed.deserialize(EXTRA_ATTRIBUTES.into_deserializer())
                     .map(Some)
             }
-            None => match *self.de.peek()? {
-                XmlEvent::StartElement { ref name, .. } => seed
-                    .deserialize(
+            None => {
+                if let Some(key) = self.misc_key()? {
+                    return seed.deserialize(key.into_deserializer()).map(Some);
+                }
+                match self.de.peek()? {
+                    XmlEvent::StartElement { name, .. } => {
+                        let name = name.local_name.clone();
+                        let name = self.element_key(&name);
+                        if self.is_unknown_element(&name) {
+                            // Unknown elements are all captured the first time one is encountered.
+                            return self.next_unknown_elements_key(seed);
+                        }
+                        if self.skip_duplicate(&name)? {
+                            de::IgnoredAny::deserialize(&mut *self.de)?;
+                            return self.next_key_seed(seed);
+                        }
+                        self.check_element_order(&name)?;
+                        self.value_seen |= self.inner_value;
                         if !self.inner_value {
-                            name.local_name.as_str()
-                        } else {
-                            "$value"
+                            self.item_path = name.split('/').skip(1).map(str::to_string).collect();
                         }
-                        .into_deserializer(),
-                    )
-                    .map(Some),
-                XmlEvent::Characters(_) => seed.deserialize("$value".into_deserializer()).map(Some),
-                // Any other event: assume end of map values (actual check for `EndElement` done by the originating
-                // `Deserializer`)
-                _ => Ok(None),
-            },
+                        seed.deserialize(
+                            if !self.inner_value {
+                                name.as_str()
+                            } else {
+                                "$value"
+                            }
+                            .into_deserializer(),
+                        )
+                        .map(Some)
+                    }
+                    XmlEvent::Characters(_) | XmlEvent::CData(_) => {
+                        self.check_text()?;
+                        self.value_seen = true;
+                        seed.deserialize("$value".into_deserializer()).map(Some)
+                    }
+                    // Any other event: assume end of map values (actual check for `EndElement` done by the originating
+                    // `Deserializer`)
+                    _ => self.next_collected_key(seed),
+                }
+            }
         }
     }
 
     fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
-        match self.next_attr_value.take() {
-            Some(value) => seed.deserialize(AttrValueDeserializer(value)),
+        match self.next_value.take() {
+            Some(PendingValue::Attr(value)) => {
+                seed.deserialize(AttrValueDeserializer(value, self.de.bool_format))
+            }
+            Some(PendingValue::ExtraAttrs(attrs)) => {
+                seed.deserialize(MapDeserializer::new(attrs.into_iter().map(
+                    |(name, value)| (name, AttrValueDeserializer(value, self.de.bool_format)),
+                )))
+            }
+            Some(PendingValue::UnknownElements) => {
+                let captured = self.capture_unknown_elements()?;
+                seed.deserialize(SeqDeserializer::new(captured.into_iter()))
+            }
+            Some(PendingValue::Misc(name)) => {
+                let collected = self.collect_misc(name)?;
+                seed.deserialize(MiscValueDeserializer(collected))
+            }
             None => {
-                if !self.inner_value {
-                    if let XmlEvent::StartElement { .. } = *self.de.peek()? {
-                        self.de.set_map_value();
-                    }
-                }
-                let result = seed.deserialize(&mut *self.de)?;
-                Ok(result)
+                let outer_namespace = self.de.outer_namespace.replace(self.namespace.clone());
+                let result = self.next_child_value(seed);
+                self.de.outer_namespace = outer_namespace;
+                result
             }
         }
     }
@@ -86,7 +522,198 @@ impl<'de, 'a, R: 'a + Read, B: BufferedXmlReader<R>> de::MapAccess<'de> for MapA
     }
 }
 
-struct AttrValueDeserializer(String);
+/// Reads the entries of a map laid out as `MapLayout::KeyAttribute` or
+/// `MapLayout::KeyValueElements`, from the children of the map element.
+pub struct EntryMapAccess<'a, R: Read, B: BufferedXmlReader<R>> {
+    de: &'a mut Deserializer<R, B>,
+    layout: MapLayout,
+}
+
+impl<'a, R: 'a + Read, B: BufferedXmlReader<R>> EntryMapAccess<'a, R, B> {
+    pub fn new(de: &'a mut Deserializer<R, B>, layout: MapLayout) -> Self {
+        EntryMapAccess { de, layout }
+    }
+
+    /// Checks that the next event starts an element with the given name.
+    fn expect_start(&mut self, expected: &str) -> Result<()> {
+        match self.de.peek()? {
+            XmlEvent::StartElement { name, .. } if name.local_name == expected => Ok(()),
+            actual => Err(Error::UnexpectedToken {
+                token: format!("<{}>", expected),
+                found: format!("{:?}", actual),
+            }),
+        }
+    }
+}
+
+impl<'de, 'a, R: 'a + Read, B: BufferedXmlReader<R>> de::MapAccess<'de>
+    for EntryMapAccess<'a, R, B>
+{
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if let XmlEvent::EndElement { .. } = self.de.peek()? {
+            return Ok(None);
+        }
+        match &self.layout {
+            MapLayout::KeyAttribute { element, attribute } => {
+                let (element, attribute) = (element.clone(), attribute.clone());
+                self.expect_start(&element)?;
+                let key = match self.de.peek()? {
+                    XmlEvent::StartElement { attributes, .. } => attributes
+                        .iter()
+                        .find(|attr| attr.name.local_name == attribute)
+                        .map(|attr| attr.value.clone()),
+                    _ => None,
+                };
+                let key = key.ok_or_else(|| Error::Custom {
+                    field: format!("missing attribute '{}' on <{}>", attribute, element),
+                })?;
+                seed.deserialize(AttrValueDeserializer(key, self.de.bool_format))
+                    .map(Some)
+            }
+            MapLayout::KeyValueElements { element, key, .. } => {
+                let (element, key) = (element.clone(), key.clone());
+                self.expect_start(&element)?;
+                self.de.next()?;
+                self.expect_start(&key)?;
+                self.de.set_map_value();
+                seed.deserialize(&mut *self.de).map(Some)
+            }
+            MapLayout::ElementNames => unreachable!("maps keyed by element names use MapAccess"),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        if let MapLayout::KeyValueElements { value, .. } = &self.layout {
+            let value = value.clone();
+            self.expect_start(&value)?;
+            self.de.set_map_value();
+            let result = seed.deserialize(&mut *self.de)?;
+            expect!(self.de.next()?, XmlEvent::EndElement { .. } => Ok(result))
+        } else {
+            self.de.set_map_value();
+            seed.deserialize(&mut *self.de)
+        }
+    }
+}
+
+/// The field a child element is read into: the field with the element name, or the field named
+/// with a path starting with it, such as `Items/Item` for `<Items>`.
+fn field_for(fields: &[&'static str], name: &str) -> Option<&'static str> {
+    fields.iter().copied().find(|field| {
+        *field == name
+            || field
+                .split_once('/')
+                .is_some_and(|(wrapper, _)| wrapper == name)
+    })
+}
+
+/// An element captured into a `$unknown` field, or a comment or processing instruction collected
+/// into its special field.
+struct Captured {
+    text: String,
+    /// Field of the next known child element, if any.
+    before: Option<&'static str>,
+}
+
+/// Anchors the captured items not followed by a known element yet to the given field.
+fn anchor_before(captured: &mut [Captured], field: &'static str) {
+    for item in captured.iter_mut().rev() {
+        if item.before.is_some() {
+            break;
+        }
+        item.before = Some(field);
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Captured {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Reads a captured item as its text, or as an `Anchored` item along with its anchor.
+impl<'de> de::Deserializer<'de> for Captured {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.text)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        if name == ANCHORED_TOKEN {
+            let before = self.before.unwrap_or_default().to_string();
+            visitor.visit_newtype_struct(SeqDeserializer::new(vec![before, self.text].into_iter()))
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string unit bytes map unit_struct
+        seq option tuple_struct struct identifier tuple enum ignored_any byte_buf
+    }
+}
+
+fn is_known_attribute(fields: &[&'static str], name: &str) -> bool {
+    attribute_field(fields, name).is_some()
+}
+
+/// The field an attribute is read into: the one named after it with an `@` prefix, or else the
+/// one named after it as is.
+fn attribute_field(fields: &[&'static str], name: &str) -> Option<&'static str> {
+    let mut fields = fields.iter().copied();
+    fields
+        .clone()
+        .find(|field| field.strip_prefix('@') == Some(name))
+        .or_else(|| fields.find(|field| *field == name))
+}
+
+/// The namespace declarations made by an element, as the `xmlns` and `xmlns:prefix` attributes
+/// that make them, given the namespaces in scope inside and around it.
+fn declared_namespaces(namespace: &Namespace, outer: Option<&Namespace>) -> Vec<(String, String)> {
+    namespace
+        .into_iter()
+        .filter(|(prefix, uri)| {
+            let outer_uri = outer.and_then(|outer| outer.get(prefix));
+            match *prefix {
+                NS_XML_PREFIX | NS_XMLNS_PREFIX => false,
+                NS_NO_PREFIX => outer_uri.unwrap_or(NS_EMPTY_URI) != *uri,
+                _ => outer_uri != Some(*uri),
+            }
+        })
+        .map(|(prefix, uri)| match prefix {
+            NS_NO_PREFIX => ("xmlns".to_string(), uri.to_string()),
+            _ => (format!("xmlns:{}", prefix), uri.to_string()),
+        })
+        .collect()
+}
+
+/// Attribute name including its prefix, if any, so that it can be written back as is.
+fn qualified_name(name: &OwnedName) -> String {
+    match &name.prefix {
+        Some(prefix) => format!("{}:{}", prefix, name.local_name),
+        None => name.local_name.clone(),
+    }
+}
+
+/// Value of an attribute, along with the format of booleans.
+struct AttrValueDeserializer(String, BoolFormat);
+
+impl<'de> IntoDeserializer<'de, Error> for AttrValueDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
 
 macro_rules! deserialize_type_attr {
     ($deserialize:ident => $visit:ident) => {
@@ -111,8 +738,14 @@ impl<'de> de::Deserializer<'de> for AttrValueDeserializer {
     deserialize_type_attr!(deserialize_u16 => visit_u16);
     deserialize_type_attr!(deserialize_u32 => visit_u32);
     deserialize_type_attr!(deserialize_u64 => visit_u64);
-    deserialize_type_attr!(deserialize_f32 => visit_f32);
-    deserialize_type_attr!(deserialize_f64 => visit_f64);
+
+    fn deserialize_f32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_f32(parse_float(&self.0)?)
+    }
+
+    fn deserialize_f64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_f64(parse_float(&self.0)?)
+    }
 
     fn deserialize_enum<V: de::Visitor<'de>>(
         self,
@@ -128,10 +761,9 @@ impl<'de> de::Deserializer<'de> for AttrValueDeserializer {
     }
 
     fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        match self.0.as_str() {
-            "true" | "1" => visitor.visit_bool(true),
-            "false" | "0" => visitor.visit_bool(false),
-            _ => Err(de::Error::invalid_value(
+        match self.1.parse(&self.0) {
+            Some(v) => visitor.visit_bool(v),
+            None => Err(de::Error::invalid_value(
                 Unexpected::Str(&self.0),
                 &"a boolean",
             )),
@@ -143,3 +775,48 @@ impl<'de> de::Deserializer<'de> for AttrValueDeserializer {
         struct identifier tuple ignored_any byte_buf
     }
 }
+
+/// Deserializes the comments or processing instructions collected for a `$comment` or `$pi`
+/// field, as a sequence, an option or a single string.
+struct MiscValueDeserializer(Vec<Captured>);
+
+impl<'de> de::Deserializer<'de> for MiscValueDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let texts: Vec<_> = self.0.into_iter().map(|item| item.text).collect();
+        visitor.visit_string(texts.join("\n"))
+    }
+
+    fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(SeqDeserializer::new(self.0.into_iter()))
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    /// A single `Anchored` item is anchored where the first of the joined items was.
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        let before = self.0.first().and_then(|item| item.before);
+        let texts: Vec<_> = self.0.into_iter().map(|item| item.text).collect();
+        Captured {
+            text: texts.join("\n"),
+            before,
+        }
+        .deserialize_newtype_struct(name, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string unit bytes map unit_struct
+        tuple_struct struct identifier tuple enum ignored_any byte_buf
+    }
+}
@@ -1,23 +1,62 @@
-use std::{io::Read, marker::PhantomData};
+use std::num::ParseFloatError;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::{io::Read, iter, marker::PhantomData};
 
 use log::trace;
-use serde::de::{self, Unexpected};
+use serde::de::{
+    self,
+    value::{MapDeserializer, SeqDeserializer},
+    Unexpected,
+};
 use serde::forward_to_deserialize_any;
 use xml::name::OwnedName;
+use xml::namespace::Namespace;
 use xml::reader::{EventReader, ParserConfig, XmlEvent};
 
-use self::buffer::{BufferedXmlReader, ChildXmlBuffer, RootXmlBuffer};
-use self::map::MapAccess;
+use self::buffer::{is_misc, BufferedXmlReader, ChildXmlBuffer, RootXmlBuffer};
+use self::map::{EntryMapAccess, MapAccess};
+use self::raw::capture_element;
 use self::seq::SeqAccess;
 use self::var::EnumAccess;
+use crate::cdata::CDATA_TOKEN;
+use crate::document::Prolog;
+use crate::encoding::DecodingReader;
 use crate::error::{Error, Result};
+use crate::items::item_names_for;
+use crate::layout::{BoolFormat, EmptySequences, ItemNames, MapLayout, NoneValues};
+use crate::raw::RAW_XML_TOKEN;
+use crate::schema::{Validator, XsdSchema};
+use crate::ser::XSI_NAMESPACE;
 use crate::{debug_expect, expect};
 
 mod buffer;
 mod map;
+mod raw;
 mod seq;
 mod var;
 
+/// Special field and variant name under which comments are read.
+const COMMENT: &str = "$comment";
+/// Special field and variant name under which processing instructions are read.
+const PROCESSING_INSTRUCTION: &str = "$pi";
+
+/// Returns the special name and the text of a comment or processing instruction. The text of a
+/// processing instruction is its target, followed by its data if any.
+fn misc_event(event: &XmlEvent) -> Option<(&'static str, String)> {
+    match event {
+        XmlEvent::Comment(text) => Some((COMMENT, text.clone())),
+        XmlEvent::ProcessingInstruction { name, data } => Some((
+            PROCESSING_INSTRUCTION,
+            match data {
+                Some(data) => format!("{} {}", name, data),
+                None => name.clone(),
+            },
+        )),
+        _ => None,
+    }
+}
+
 /// A convenience method for deserialize some object from a string.
 ///
 /// ```rust
@@ -35,10 +74,12 @@ mod var;
 /// # }
 /// ```
 pub fn from_str<'de, T: de::Deserialize<'de>>(s: &str) -> Result<T> {
-    from_reader(s.as_bytes())
+    // Already decoded, whatever encoding the declaration names
+    T::deserialize(&mut Deserializer::new_from_reader(s.as_bytes()))
 }
 
-/// A convenience method for deserialize some object from a reader.
+/// A convenience method for deserialize some object from a reader. The document may be in any
+/// encoding supported by `DecodingReader`.
 ///
 /// ```rust
 /// # use serde::Deserialize;
@@ -55,7 +96,9 @@ pub fn from_str<'de, T: de::Deserialize<'de>>(s: &str) -> Result<T> {
 /// # }
 /// ```
 pub fn from_reader<'de, R: Read, T: de::Deserialize<'de>>(reader: R) -> Result<T> {
-    T::deserialize(&mut Deserializer::new_from_reader(reader))
+    T::deserialize(&mut Deserializer::new_from_reader(DecodingReader::new(
+        reader,
+    )))
 }
 
 type RootDeserializer<R> = Deserializer<R, RootXmlBuffer<R>>;
@@ -70,10 +113,59 @@ pub struct Deserializer<
     buffered_reader: B,
     is_map_value: bool,
     non_contiguous_seq_elements: bool,
+    strict_root: bool,
+    /// Name the root element must have, which also wraps sequences and enums at the root.
+    root_name: Option<String>,
+    map_layout: MapLayout,
+    item_names: ItemNames,
+    none_values: NoneValues,
+    empty_sequences: EmptySequences,
+    bool_format: BoolFormat,
+    duplicate_elements: Option<DuplicateElements>,
+    /// Whether the next value, unless it is a sequence, is read from the last of the adjacent
+    /// elements with the same name starting at the cursor.
+    last_of_adjacent: bool,
+    strict: StrictMode,
+    /// Names of the items of the sequence about to be read from the next element, a wrapper,
+    /// and of the items of sequences nested in them, for a field named with a path such as
+    /// `Items/Item`.
+    item_path: Vec<String>,
+    /// Names of the items of the sequence about to be read, if chosen for the field with
+    /// `items`.
+    field_item_names: Option<ItemNames>,
+    /// Namespaces in scope around the element at the cursor, once inside the root element. Used
+    /// to tell which namespaces an element declares itself.
+    outer_namespace: Option<Rc<Namespace>>,
     marker: PhantomData<R>,
 }
 
-impl<'de, R: Read> RootDeserializer<R> {
+/// What to do with a child element whose name was already read in the same element, when
+/// reading a struct or a map keyed by element names. Elements read together into a sequence,
+/// such as the adjacent `<item>` elements of a `Vec` field, don't count as repeated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateElements {
+    /// Report it as `Error::DuplicateElement`, along with its position in the document.
+    Error,
+    /// Keep the value of the first element and skip the others.
+    FirstWins,
+    /// Keep the value of the last element and skip the others. Adjacent elements read into a
+    /// sequence are all kept, as the items of the last group of them.
+    LastWins,
+    /// Read all elements with the same name into the same sequence, wherever they are among
+    /// their siblings. The value of each field or map entry has to be a sequence.
+    Collect,
+}
+
+/// Which strict mode checks are enabled. All checks only apply to elements deserialized into
+/// structs, since that is where the expected fields are known.
+#[derive(Clone, Copy, Default)]
+struct StrictMode {
+    deny_unknown_attributes: bool,
+    deny_unexpected_text: bool,
+    enforce_element_order: bool,
+}
+
+impl<R: Read> RootDeserializer<R> {
     pub fn new(reader: EventReader<R>) -> Self {
         let buffered_reader = RootXmlBuffer::new(reader);
 
@@ -82,6 +174,19 @@ impl<'de, R: Read> RootDeserializer<R> {
             depth: 0,
             is_map_value: false,
             non_contiguous_seq_elements: false,
+            strict_root: false,
+            root_name: None,
+            map_layout: MapLayout::default(),
+            item_names: ItemNames::default(),
+            none_values: NoneValues::default(),
+            empty_sequences: EmptySequences::default(),
+            bool_format: BoolFormat::default(),
+            duplicate_elements: None,
+            last_of_adjacent: false,
+            strict: StrictMode::default(),
+            item_path: Vec::new(),
+            field_item_names: None,
+            outer_namespace: None,
             marker: PhantomData,
         }
     }
@@ -90,8 +195,9 @@ impl<'de, R: Read> RootDeserializer<R> {
         let config = ParserConfig::new()
             .trim_whitespace(true)
             .whitespace_to_characters(true)
-            .cdata_to_characters(true)
-            .ignore_comments(true)
+            .cdata_to_characters(false)
+            // Comments are skipped later on, unless `preserve_comments_and_pis` is set
+            .ignore_comments(false)
             .coalesce_characters(true);
 
         Self::new(EventReader::new_with_config(reader, config))
@@ -129,15 +235,315 @@ impl<'de, R: Read> RootDeserializer<R> {
         self.non_contiguous_seq_elements = set;
         self
     }
+
+    /// Configures whether the name of the root element must match the name of the struct it is
+    /// deserialized into (after `#[serde(rename)]`). A mismatch is reported as
+    /// `Error::UnexpectedRoot`. Disabled by default.
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// # use serde_xml_rs::Error;
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Foo {
+    ///     bar: String,
+    /// }
+    /// # fn main() {
+    /// let s = r##"<Baz><bar>Hello, world</bar></Baz>"##;
+    /// let mut de = serde_xml_rs::Deserializer::new_from_reader(s.as_bytes())
+    ///     .strict_root(true);
+    /// match Foo::deserialize(&mut de) {
+    ///     Err(Error::UnexpectedRoot { expected, found }) => {
+    ///         assert_eq!(expected, "Foo");
+    ///         assert_eq!(found, "Baz");
+    ///     }
+    ///     other => panic!("unexpected result: {:?}", other),
+    /// }
+    /// # }
+    /// ```
+    pub fn strict_root(mut self, set: bool) -> Self {
+        self.strict_root = set;
+        self
+    }
+
+    /// Configures the name of the root element, which wraps the value whatever its type: a
+    /// sequence is read from the children of the root element, and an enum from its content. A
+    /// root element with another name is reported as `Error::UnexpectedRoot`. This reads back
+    /// what `Serializer::root_name` writes.
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Channel {
+    ///     id: u32,
+    /// }
+    /// # fn main() {
+    /// let s = r##"<channels><Channel><id>1</id></Channel><Channel><id>2</id></Channel></channels>"##;
+    /// let mut de = serde_xml_rs::Deserializer::new_from_reader(s.as_bytes())
+    ///     .root_name("channels");
+    /// let channels = Vec::<Channel>::deserialize(&mut de).unwrap();
+    /// assert_eq!(channels, vec![Channel { id: 1 }, Channel { id: 2 }]);
+    /// # }
+    /// ```
+    pub fn root_name(mut self, name: &str) -> Self {
+        self.root_name = Some(name.to_string());
+        self
+    }
+
+    /// Configures what happens to a child element whose name was already read in the same
+    /// element, for structs and maps keyed by element names. Unless set, a map keeps the last
+    /// value and a repeated struct field is rejected by the struct's `Deserialize`
+    /// implementation.
+    ///
+    /// ```rust
+    /// # use std::collections::HashMap;
+    /// # use serde::Deserialize;
+    /// # use serde_xml_rs::de::DuplicateElements;
+    /// # use serde_xml_rs::Error;
+    /// # fn main() {
+    /// let s = "<channels>\n  <front>1</front>\n  <back>2</back>\n  <front>3</front>\n</channels>";
+    /// let mut de = serde_xml_rs::Deserializer::new_from_reader(s.as_bytes())
+    ///     .duplicate_elements(DuplicateElements::FirstWins);
+    /// let channels = HashMap::<String, u32>::deserialize(&mut de).unwrap();
+    /// assert_eq!(channels["front"], 1);
+    ///
+    /// let mut de = serde_xml_rs::Deserializer::new_from_reader(s.as_bytes())
+    ///     .duplicate_elements(DuplicateElements::Error);
+    /// match HashMap::<String, u32>::deserialize(&mut de) {
+    ///     Err(Error::DuplicateElement { name, position, .. }) => {
+    ///         assert_eq!(name, "front");
+    ///         assert_eq!(position.to_string(), "4:3");
+    ///     }
+    ///     other => panic!("unexpected result: {:?}", other),
+    /// }
+    ///
+    /// let mut de = serde_xml_rs::Deserializer::new_from_reader(s.as_bytes())
+    ///     .duplicate_elements(DuplicateElements::Collect);
+    /// let channels = HashMap::<String, Vec<u32>>::deserialize(&mut de).unwrap();
+    /// assert_eq!(channels["front"], vec![1, 3]);
+    /// # }
+    /// ```
+    pub fn duplicate_elements(mut self, set: DuplicateElements) -> Self {
+        self.duplicate_elements = Some(set);
+        self
+    }
+
+    /// Configures how the entries of maps are laid out, to read back what a `Serializer` with
+    /// the same `MapLayout` writes. Keys are element names by default.
+    pub fn map_layout(mut self, set: MapLayout) -> Self {
+        self.map_layout = set;
+        self
+    }
+
+    /// Configures how the items of sequence fields are named, to read back what a `Serializer`
+    /// with the same `ItemNames` writes. Each item is named after the field by default.
+    pub fn item_names(mut self, set: ItemNames) -> Self {
+        self.item_names = set;
+        self
+    }
+
+    /// Configures whether empty elements are read as `None`, to read back what a `Serializer`
+    /// with the same `NoneValues` writes. Elements with `xsi:nil="true"` are read as `None`
+    /// whatever the policy.
+    pub fn none_values(mut self, set: NoneValues) -> Self {
+        self.none_values = set;
+        self
+    }
+
+    /// Configures whether an empty element is read as an empty sequence for a sequence field
+    /// whose items are named after the field, to read back what a `Serializer` with the same
+    /// `EmptySequences` writes.
+    pub fn empty_sequences(mut self, set: EmptySequences) -> Self {
+        self.empty_sequences = set;
+        self
+    }
+
+    /// Configures the format of booleans, to read back the values of `BoolFormat::Custom`.
+    /// `true`, `false`, `1` and `0` are read in any case whatever the format.
+    pub fn bool_format(mut self, set: BoolFormat) -> Self {
+        self.bool_format = set;
+        self
+    }
+
+    /// Enables or disables all strict mode checks at once: unknown attributes, unexpected text
+    /// content and element order. See the individual options for details.
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Foo {
+    ///     bar: String,
+    ///     baz: String,
+    /// }
+    /// # fn main() {
+    /// let s = r##"<Foo version="2"><baz>b</baz><bar>a</bar></Foo>"##;
+    /// let mut lenient = serde_xml_rs::Deserializer::new_from_reader(s.as_bytes());
+    /// assert!(Foo::deserialize(&mut lenient).is_ok());
+    ///
+    /// let mut strict = serde_xml_rs::Deserializer::new_from_reader(s.as_bytes())
+    ///     .strict_mode(true);
+    /// assert!(Foo::deserialize(&mut strict).is_err());
+    /// # }
+    /// ```
+    pub fn strict_mode(self, set: bool) -> Self {
+        self.deny_unknown_attributes(set)
+            .deny_unexpected_text(set)
+            .enforce_element_order(set)
+    }
+
+    /// Configures whether an attribute that doesn't match any field of the struct it belongs to
+    /// is reported as `Error::UnknownAttribute`, instead of being ignored. Unlike
+    /// `#[serde(deny_unknown_fields)]`, unknown child elements are still accepted. Disabled by
+    /// default.
+    pub fn deny_unknown_attributes(mut self, set: bool) -> Self {
+        self.strict.deny_unknown_attributes = set;
+        self
+    }
+
+    /// Configures whether text content inside an element deserialized into a struct without a
+    /// `$value` field is reported as `Error::UnexpectedText`, instead of being dropped. Disabled by
+    /// default.
+    pub fn deny_unexpected_text(mut self, set: bool) -> Self {
+        self.strict.deny_unexpected_text = set;
+        self
+    }
+
+    /// Configures whether child elements must appear in the order in which the fields are
+    /// declared in the struct, like an `xs:sequence`. Repeated elements for the same field are
+    /// accepted as long as they are not interleaved with later fields. A violation is reported as
+    /// `Error::OutOfOrderElement`. Disabled by default.
+    pub fn enforce_element_order(mut self, set: bool) -> Self {
+        self.strict.enforce_element_order = set;
+        self
+    }
+
+    /// Configures whether comments and processing instructions are read instead of being skipped.
+    /// They are collected into `$comment` and `$pi` fields of the struct of the element that
+    /// contains them, or read in place as `$comment` and `$pi` variants of an enum in a `$value`
+    /// sequence. Comments or processing instructions with no such field or variant are skipped.
+    /// Disabled by default.
+    ///
+    /// Comments and processing instructions collected into fields as strings are written back
+    /// together where the field is declared. Collect them as `Anchored` items, with the field
+    /// declared first, to write them back at their positions among the other fields.
+    ///
+    /// An `EventReader` passed to `new` must be configured with `ignore_comments(false)` for
+    /// comments to be read.
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Config {
+    ///     #[serde(rename = "$comment", default)]
+    ///     comments: Vec<String>,
+    ///     port: u16,
+    /// }
+    /// # fn main() {
+    /// let s = r##"<Config><!-- staging only --><port>8080</port></Config>"##;
+    /// let mut de = serde_xml_rs::Deserializer::new_from_reader(s.as_bytes())
+    ///     .preserve_comments_and_pis(true);
+    /// let config = Config::deserialize(&mut de).unwrap();
+    /// assert_eq!(config, Config { comments: vec![" staging only ".to_string()], port: 8080 });
+    /// # }
+    /// ```
+    pub fn preserve_comments_and_pis(mut self, set: bool) -> Self {
+        self.buffered_reader.set_keep_misc(set);
+        self
+    }
+
+    /// Validates the document against an XSD schema while it is deserialized. Events are checked
+    /// as they are read from the underlying reader, so a violation is reported as
+    /// `Error::Validation`, with the path of the offending element or attribute, even if it
+    /// occurs in a part of the document that is not deserialized. See `XsdSchema` for the
+    /// supported subset of XSD.
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// # use serde_xml_rs::{Error, XsdSchema};
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Order {
+    ///     status: String,
+    /// }
+    /// # fn main() {
+    /// let schema: XsdSchema = r##"
+    ///     <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+    ///         <xs:element name="Order">
+    ///             <xs:complexType>
+    ///                 <xs:sequence>
+    ///                     <xs:element name="status">
+    ///                         <xs:simpleType>
+    ///                             <xs:restriction base="xs:string">
+    ///                                 <xs:enumeration value="open" />
+    ///                                 <xs:enumeration value="closed" />
+    ///                             </xs:restriction>
+    ///                         </xs:simpleType>
+    ///                     </xs:element>
+    ///                 </xs:sequence>
+    ///             </xs:complexType>
+    ///         </xs:element>
+    ///     </xs:schema>
+    /// "##.parse().unwrap();
+    ///
+    /// let s = r##"<Order><status>pending</status></Order>"##;
+    /// let mut de = serde_xml_rs::Deserializer::new_from_reader(s.as_bytes()).validate(schema);
+    /// match Order::deserialize(&mut de) {
+    ///     Err(Error::Validation { path, .. }) => assert_eq!(path, "/Order/status"),
+    ///     other => panic!("unexpected result: {:?}", other),
+    /// }
+    /// # }
+    /// ```
+    pub fn validate(mut self, schema: XsdSchema) -> Self {
+        self.buffered_reader.set_validator(Validator::new(schema));
+        self
+    }
+
+    /// The prolog of the document, as far as it has been read. It is complete once the root
+    /// element has been reached, except for the DOCTYPE which only `Document` reads. The parser
+    /// reports a declaration with default values for a document without one, which only
+    /// `Document` tells apart.
+    pub fn prolog(&self) -> &Prolog {
+        self.buffered_reader.prolog()
+    }
+
+    /// Returns the name and namespace of the root element without consuming it, so that the
+    /// target type can be picked before deserializing.
+    ///
+    /// ```rust
+    /// # use serde::Deserialize;
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Foo {
+    ///     bar: String,
+    /// }
+    /// # fn main() {
+    /// let s = r##"<Foo xmlns="urn:example"><bar>Hello, world</bar></Foo>"##;
+    /// let mut de = serde_xml_rs::Deserializer::new_from_reader(s.as_bytes());
+    /// let root = de.peek_root_name().unwrap();
+    /// assert_eq!(root.local_name, "Foo");
+    /// assert_eq!(root.namespace.as_deref(), Some("urn:example"));
+    /// let foo = Foo::deserialize(&mut de).unwrap();
+    /// assert_eq!(foo, Foo { bar: "Hello, world".to_string() });
+    /// # }
+    /// ```
+    pub fn peek_root_name(&mut self) -> Result<OwnedName> {
+        expect!(self.peek()?, XmlEvent::StartElement { name, .. } => Ok(name.clone()))
+    }
 }
 
-impl<'de, R: Read, B: BufferedXmlReader<R>> Deserializer<R, B> {
-    fn child<'a>(&'a mut self) -> Deserializer<R, ChildXmlBuffer<'a, R>> {
+impl<R: Read, B: BufferedXmlReader<R>> Deserializer<R, B> {
+    fn child(&mut self) -> Deserializer<R, ChildXmlBuffer<'_, R>> {
         let Deserializer {
             buffered_reader,
             depth,
             is_map_value,
             non_contiguous_seq_elements,
+            strict_root,
+            map_layout,
+            item_names,
+            none_values,
+            empty_sequences,
+            bool_format,
+            duplicate_elements,
+            strict,
+            outer_namespace,
             ..
         } = self;
 
@@ -146,20 +552,55 @@ impl<'de, R: Read, B: BufferedXmlReader<R>> Deserializer<R, B> {
             depth: *depth,
             is_map_value: *is_map_value,
             non_contiguous_seq_elements: *non_contiguous_seq_elements,
+            strict_root: *strict_root,
+            root_name: None,
+            map_layout: map_layout.clone(),
+            item_names: item_names.clone(),
+            none_values: *none_values,
+            empty_sequences: *empty_sequences,
+            bool_format: *bool_format,
+            duplicate_elements: *duplicate_elements,
+            last_of_adjacent: false,
+            strict: *strict,
+            item_path: Vec::new(),
+            field_item_names: None,
+            outer_namespace: outer_namespace.clone(),
             marker: PhantomData,
         }
     }
 
-    /// Gets the next XML event without advancing the cursor.
+    /// Gets the next XML event without advancing the cursor. Comments and processing
+    /// instructions are skipped.
     fn peek(&mut self) -> Result<&XmlEvent> {
+        self.skip_misc()?;
+        let peeked = self.buffered_reader.peek()?;
+
+        trace!("Peeked {:?}", peeked);
+        Ok(peeked)
+    }
+
+    /// Gets the next XML event without advancing the cursor, including comments and processing
+    /// instructions.
+    fn peek_raw(&mut self) -> Result<&XmlEvent> {
         let peeked = self.buffered_reader.peek()?;
 
         trace!("Peeked {:?}", peeked);
         Ok(peeked)
     }
 
-    /// Gets the XML event at the cursor and advances the cursor.
+    /// Consumes any comments and processing instructions at the cursor.
+    fn skip_misc(&mut self) -> Result<()> {
+        while is_misc(self.buffered_reader.peek()?) {
+            let skipped = self.buffered_reader.next()?;
+            trace!("Skipped {:?}", skipped);
+        }
+        Ok(())
+    }
+
+    /// Gets the XML event at the cursor and advances the cursor. Comments and processing
+    /// instructions are skipped.
     fn next(&mut self) -> Result<XmlEvent> {
+        self.skip_misc()?;
         let next = self.buffered_reader.next()?;
 
         match next {
@@ -187,10 +628,10 @@ impl<'de, R: Read, B: BufferedXmlReader<R>> Deserializer<R, B> {
     /// `f` is expected to consume the entire inner contents of the element. The cursor will be moved to the end of the
     /// element.
     /// If `!self.is_map_value`: `f` will be performed without additional checks/advances for an outer XML element.
-    fn read_inner_value<V: de::Visitor<'de>, T, F: FnOnce(&mut Self) -> Result<T>>(
-        &mut self,
-        f: F,
-    ) -> Result<T> {
+    fn read_inner_value<T, F: FnOnce(&mut Self) -> Result<T>>(&mut self, f: F) -> Result<T> {
+        if self.is_map_value {
+            self.skip_to_last_duplicate()?;
+        }
         if self.unset_map_value() {
             debug_expect!(self.next(), Ok(XmlEvent::StartElement { name, .. }) => {
                 let result = f(self)?;
@@ -202,6 +643,152 @@ impl<'de, R: Read, B: BufferedXmlReader<R>> Deserializer<R, B> {
         }
     }
 
+    /// If `self.last_of_adjacent`: skips the element at the cursor as long as another element with
+    /// the same name follows right after it, for `DuplicateElements::LastWins`.
+    fn skip_to_last_duplicate(&mut self) -> Result<()> {
+        if !std::mem::take(&mut self.last_of_adjacent) {
+            return Ok(());
+        }
+        while self.is_followed_by_same_element()? {
+            let depth = self.depth;
+            loop {
+                self.next()?;
+                if self.depth == depth {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the element at the cursor is followed by a sibling with the same name, with no
+    /// other element between them.
+    fn is_followed_by_same_element(&mut self) -> Result<bool> {
+        let mut child = self.child();
+        let buffer = &mut child.buffered_reader;
+        let name = match buffer.peek()? {
+            XmlEvent::StartElement { name, .. } => name.local_name.clone(),
+            _ => return Ok(false),
+        };
+        let mut depth = 0usize;
+        loop {
+            match buffer.peek()? {
+                XmlEvent::StartElement { .. } => depth += 1,
+                XmlEvent::EndElement { .. } if depth == 1 => {
+                    buffer.skip();
+                    break;
+                }
+                XmlEvent::EndElement { .. } => depth -= 1,
+                XmlEvent::EndDocument => return Ok(false),
+                _ => {}
+            }
+            buffer.skip();
+        }
+        loop {
+            match buffer.peek()? {
+                XmlEvent::StartElement { name: next, .. } => return Ok(next.local_name == name),
+                XmlEvent::EndElement { .. } | XmlEvent::EndDocument => return Ok(false),
+                _ => buffer.skip(),
+            }
+        }
+    }
+
+    /// Whether the element at the cursor has neither attributes nor content.
+    fn is_empty_element(&mut self) -> Result<bool> {
+        let mut child = self.child();
+        let buffer = &mut child.buffered_reader;
+        match buffer.peek()? {
+            XmlEvent::StartElement { attributes, .. } if attributes.is_empty() => buffer.skip(),
+            _ => return Ok(false),
+        }
+        Ok(matches!(buffer.peek()?, XmlEvent::EndElement { .. }))
+    }
+
+    /// Whether the element at the cursor, the value of a field or sequence item, stands for
+    /// `None`: it has `xsi:nil="true"`, or it is empty if empty elements are read as `None`.
+    fn is_none_element(&mut self) -> Result<bool> {
+        if !self.is_map_value {
+            return Ok(false);
+        }
+        let nil = match self.peek()? {
+            XmlEvent::StartElement { attributes, .. } => attributes.iter().any(|attr| {
+                attr.name.local_name == "nil"
+                    && attr.name.namespace.as_deref() == Some(XSI_NAMESPACE)
+                    && matches!(attr.value.as_str(), "true" | "1")
+            }),
+            _ => return Ok(false),
+        };
+        Ok(nil || (self.none_values == NoneValues::EmptyElement && self.is_empty_element()?))
+    }
+
+    /// If `self.strict_root`: checks that the element about to be read as the document root matches
+    /// the name of the target struct.
+    fn check_root_name(&mut self, expected: &'static str) -> Result<()> {
+        self.check_configured_root()?;
+        if !self.strict_root || self.depth != 0 {
+            return Ok(());
+        }
+        if let XmlEvent::StartElement { name, .. } = self.peek()? {
+            let matches = match &name.prefix {
+                Some(prefix) => {
+                    expected == name.local_name
+                        || expected == format!("{}:{}", prefix, name.local_name)
+                }
+                None => expected == name.local_name,
+            };
+            if !matches {
+                return Err(Error::UnexpectedRoot {
+                    expected: expected.to_string(),
+                    found: name.local_name.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the name of the root element against the one set with `root_name`, if any. Returns
+    /// whether a root name is set and the cursor is at the root.
+    fn check_configured_root(&mut self) -> Result<bool> {
+        if self.depth != 0 {
+            return Ok(false);
+        }
+        let expected = match &self.root_name {
+            Some(expected) => expected.clone(),
+            None => return Ok(false),
+        };
+        if let XmlEvent::StartElement { name, .. } = self.peek()? {
+            if name.local_name != expected {
+                return Err(Error::UnexpectedRoot {
+                    expected,
+                    found: name.local_name.clone(),
+                });
+            }
+        }
+        Ok(true)
+    }
+
+    /// Reads an element as a map of its attributes and children. `fields` is known when the map
+    /// is a struct, and enables the strict mode checks.
+    fn read_map<'de, V: de::Visitor<'de>>(
+        &mut self,
+        fields: Option<&'static [&'static str]>,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.skip_to_last_duplicate()?;
+        self.unset_map_value();
+        expect!(self.next()?, XmlEvent::StartElement { name, attributes, namespace } => {
+            let map_value = visitor.visit_map(MapAccess::new(
+                self,
+                name.local_name.clone(),
+                attributes,
+                namespace,
+                fields,
+            ))?;
+            self.expect_end_element(name)?;
+            Ok(map_value)
+        })
+    }
+
     fn expect_end_element(&mut self, start_name: OwnedName) -> Result<()> {
         expect!(self.next()?, XmlEvent::EndElement { name, .. } => {
             if name == start_name {
@@ -216,11 +803,12 @@ impl<'de, R: Read, B: BufferedXmlReader<R>> Deserializer<R, B> {
         })
     }
 
-    fn prepare_parse_type<V: de::Visitor<'de>>(&mut self) -> Result<String> {
+    fn prepare_parse_type(&mut self) -> Result<String> {
+        self.check_configured_root()?;
         if let XmlEvent::StartElement { .. } = *self.peek()? {
             self.set_map_value()
         }
-        self.read_inner_value::<V, String, _>(|this| {
+        self.read_inner_value::<String, _>(|this| {
             if let XmlEvent::EndElement { .. } = *this.peek()? {
                 return Err(Error::UnexpectedToken {
                     token: "EndElement".into(),
@@ -228,9 +816,7 @@ impl<'de, R: Read, B: BufferedXmlReader<R>> Deserializer<R, B> {
                 });
             }
 
-            expect!(this.next()?, XmlEvent::Characters(s) => {
-                return Ok(s)
-            })
+            expect!(this.next()?, XmlEvent::Characters(s) | XmlEvent::CData(s) => Ok(s))
         })
     }
 }
@@ -238,15 +824,33 @@ impl<'de, R: Read, B: BufferedXmlReader<R>> Deserializer<R, B> {
 macro_rules! deserialize_type {
     ($deserialize:ident => $visit:ident) => {
         fn $deserialize<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-            let value = self.prepare_parse_type::<V>()?.parse()?;
+            let value = self.prepare_parse_type()?.parse()?;
+            visitor.$visit(value)
+        }
+    };
+}
+
+macro_rules! deserialize_float {
+    ($deserialize:ident => $visit:ident) => {
+        fn $deserialize<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            let value = parse_float(&self.prepare_parse_type()?)?;
             visitor.$visit(value)
         }
     };
 }
 
-impl<'de, 'a, R: Read, B: BufferedXmlReader<R>> de::Deserializer<'de>
-    for &'a mut Deserializer<R, B>
-{
+/// Parses a float, accepting the `xs:double` forms of infinity, `INF` and `-INF`, as well as the
+/// forms `str::parse` accepts.
+fn parse_float<F: FromStr<Err = ParseFloatError>>(s: &str) -> Result<F> {
+    let s = match s {
+        "INF" | "+INF" => "inf",
+        "-INF" => "-inf",
+        s => s,
+    };
+    Ok(s.parse()?)
+}
+
+impl<'de, R: Read, B: BufferedXmlReader<R>> de::Deserializer<'de> for &mut Deserializer<R, B> {
     type Error = Error;
 
     forward_to_deserialize_any! {
@@ -255,20 +859,12 @@ impl<'de, 'a, R: Read, B: BufferedXmlReader<R>> de::Deserializer<'de>
 
     fn deserialize_struct<V: de::Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
-        self.unset_map_value();
-        expect!(self.next()?, XmlEvent::StartElement { name, attributes, .. } => {
-            let map_value = visitor.visit_map(MapAccess::new(
-                self,
-                attributes,
-                fields.contains(&"$value")
-            ))?;
-            self.expect_end_element(name)?;
-            Ok(map_value)
-        })
+        self.check_root_name(name)?;
+        self.read_map(Some(fields), visitor)
     }
 
     deserialize_type!(deserialize_i8 => visit_i8);
@@ -279,24 +875,24 @@ impl<'de, 'a, R: Read, B: BufferedXmlReader<R>> de::Deserializer<'de>
     deserialize_type!(deserialize_u16 => visit_u16);
     deserialize_type!(deserialize_u32 => visit_u32);
     deserialize_type!(deserialize_u64 => visit_u64);
-    deserialize_type!(deserialize_f32 => visit_f32);
-    deserialize_type!(deserialize_f64 => visit_f64);
+    deserialize_float!(deserialize_f32 => visit_f32);
+    deserialize_float!(deserialize_f64 => visit_f64);
 
     fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.check_configured_root()?;
         if let XmlEvent::StartElement { .. } = *self.peek()? {
             self.set_map_value()
         }
-        self.read_inner_value::<V, V::Value, _>(|this| {
+        self.read_inner_value::<V::Value, _>(|this| {
             if let XmlEvent::EndElement { .. } = *this.peek()? {
                 return visitor.visit_bool(false);
             }
-            expect!(this.next()?, XmlEvent::Characters(s) => {
-                match s.as_str() {
-                    "true" | "1" => visitor.visit_bool(true),
-                    "false" | "0" => visitor.visit_bool(false),
-                    _ => Err(de::Error::invalid_value(Unexpected::Str(&s), &"a boolean")),
+            let bool_format = this.bool_format;
+            expect!(this.next()?, XmlEvent::Characters(s) | XmlEvent::CData(s) => {
+                match bool_format.parse(&s) {
+                    Some(v) => visitor.visit_bool(v),
+                    None => Err(de::Error::invalid_value(Unexpected::Str(&s), &"a boolean")),
                 }
-
             })
         })
     }
@@ -318,10 +914,11 @@ impl<'de, 'a, R: Read, B: BufferedXmlReader<R>> de::Deserializer<'de>
     }
 
     fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.check_configured_root()?;
         if let XmlEvent::StartElement { .. } = *self.peek()? {
             self.set_map_value()
         }
-        self.read_inner_value::<V, V::Value, _>(
+        self.read_inner_value::<V::Value, _>(
             |this| expect!(this.peek()?, &XmlEvent::EndElement { .. } => visitor.visit_unit()),
         )
     }
@@ -336,9 +933,36 @@ impl<'de, 'a, R: Read, B: BufferedXmlReader<R>> de::Deserializer<'de>
 
     fn deserialize_newtype_struct<V: de::Visitor<'de>>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value> {
+        if name == RAW_XML_TOKEN {
+            self.skip_to_last_duplicate()?;
+            self.unset_map_value();
+            expect!(self.peek()?, XmlEvent::StartElement { .. } => Ok(()))?;
+            let xml = capture_element(&mut self.buffered_reader)?;
+            return visitor.visit_string(xml);
+        }
+        if let Some(item_names) = item_names_for(name) {
+            self.field_item_names = Some(item_names);
+            return visitor.visit_newtype_struct(self);
+        }
+        if name == CDATA_TOKEN {
+            self.check_configured_root()?;
+            if let XmlEvent::StartElement { .. } = *self.peek()? {
+                self.set_map_value()
+            }
+            return self.read_inner_value::<V::Value, _>(|this| {
+                let (kind, text) = match this.peek()? {
+                    XmlEvent::EndElement { .. } => ("text", String::new()),
+                    XmlEvent::CData(_) => {
+                        expect!(this.next()?, XmlEvent::CData(s) => Ok(("cdata", s)))?
+                    }
+                    _ => expect!(this.next()?, XmlEvent::Characters(s) => Ok(("text", s)))?,
+                };
+                visitor.visit_map(MapDeserializer::new(iter::once((kind, text))))
+            });
+        }
         visitor.visit_newtype_struct(self)
     }
 
@@ -360,42 +984,107 @@ impl<'de, 'a, R: Read, B: BufferedXmlReader<R>> de::Deserializer<'de>
     fn deserialize_enum<V: de::Visitor<'de>>(
         self,
         _name: &'static str,
-        _variants: &'static [&'static str],
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
-        self.read_inner_value::<V, V::Value, _>(|this| visitor.visit_enum(EnumAccess::new(this)))
+        if let Some((variant, _)) = misc_event(self.peek_raw()?) {
+            if !variants.contains(&variant) {
+                self.skip_misc()?;
+            }
+        }
+        // The variant is inside the root element
+        if self.check_configured_root()? {
+            self.set_map_value();
+        }
+        self.read_inner_value::<V::Value, _>(|this| visitor.visit_enum(EnumAccess::new(this)))
     }
 
     fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.check_configured_root()?;
         if let XmlEvent::StartElement { .. } = *self.peek()? {
             self.set_map_value()
         }
-        self.read_inner_value::<V, V::Value, _>(|this| {
+        self.read_inner_value::<V::Value, _>(|this| {
             if let XmlEvent::EndElement { .. } = *this.peek()? {
                 return visitor.visit_str("");
             }
-            expect!(this.next()?, XmlEvent::Characters(s) => {
+            expect!(this.next()?, XmlEvent::Characters(s) | XmlEvent::CData(s) => {
                 visitor.visit_string(s)
             })
         })
     }
 
     fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let item_names = match self.field_item_names.take() {
+            Some(item_names) => item_names,
+            None => self.item_names.clone(),
+        };
+        // The items are the children of the root element
+        if self.check_configured_root()? {
+            self.unset_map_value();
+            return expect!(self.next()?, XmlEvent::StartElement { name, .. } => {
+                let value = visitor.visit_seq(SeqAccess::new(self.child(), None))?;
+                self.expect_end_element(name)?;
+                Ok(value)
+            });
+        }
+        // The items are the children of a wrapper element
+        if !self.item_path.is_empty() {
+            let mut item_path = std::mem::take(&mut self.item_path);
+            let item = item_path.remove(0);
+            return self.read_inner_value(|de| {
+                visitor.visit_seq(SeqAccess::wrapped(de.child(), item, item_path))
+            });
+        }
+        if self.is_map_value
+            && item_names == ItemNames::FieldName
+            && self.empty_sequences == EmptySequences::EmptyElement
+            && self.is_empty_element()?
+        {
+            <de::IgnoredAny as de::Deserialize>::deserialize(&mut *self)?;
+            return visitor.visit_seq(SeqDeserializer::<_, Error>::new(iter::empty::<()>()));
+        }
+        // The items are the children of the field element
+        if self.is_map_value {
+            match item_names {
+                ItemNames::FieldName => {}
+                ItemNames::TypeName => {
+                    return self.read_inner_value(|de| {
+                        visitor.visit_seq(SeqAccess::new(de.child(), None))
+                    });
+                }
+                ItemNames::Element(item) => {
+                    return self.read_inner_value(|de| {
+                        visitor.visit_seq(SeqAccess::wrapped(de.child(), item.into_owned(), vec![]))
+                    });
+                }
+            }
+        }
         let child_deserializer = self.child();
 
         visitor.visit_seq(SeqAccess::new(child_deserializer, None))
     }
 
     fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.check_configured_root()?;
+        if self.map_layout == MapLayout::ElementNames {
+            return self.read_map(None, visitor);
+        }
+        self.skip_to_last_duplicate()?;
         self.unset_map_value();
-        expect!(self.next()?, XmlEvent::StartElement { name, attributes, .. } => {
-            let map_value = visitor.visit_map(MapAccess::new(self, attributes, false))?;
+        expect!(self.next()?, XmlEvent::StartElement { name, .. } => {
+            let layout = self.map_layout.clone();
+            let map_value = visitor.visit_map(EntryMapAccess::new(self, layout))?;
             self.expect_end_element(name)?;
             Ok(map_value)
         })
     }
 
     fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.is_none_element()? {
+            <de::IgnoredAny as de::Deserialize>::deserialize(&mut *self)?;
+            return visitor.visit_none();
+        }
         match *self.peek()? {
             XmlEvent::EndElement { .. } => visitor.visit_none(),
             _ => visitor.visit_some(self),
@@ -403,6 +1092,7 @@ impl<'de, 'a, R: Read, B: BufferedXmlReader<R>> de::Deserializer<'de>
     }
 
     fn deserialize_ignored_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.skip_to_last_duplicate()?;
         self.unset_map_value();
         let depth = self.depth;
         loop {
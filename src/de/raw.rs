@@ -0,0 +1,67 @@
+use std::collections::BTreeSet;
+use std::io::Read;
+
+use xml::reader::XmlEvent;
+use xml::writer::EmitterConfig;
+
+use super::buffer::BufferedXmlReader;
+use crate::error::{Error, Result};
+
+/// Consumes the element at the cursor, including all of its descendants, and writes it back out
+/// as a standalone XML fragment. The namespaces used in the fragment are declared on its root, so
+/// that it can be re-emitted elsewhere; other namespaces in scope are left out.
+///
+/// The fragment is written from the parsed events, so it is equivalent to the original text but
+/// not necessarily identical: whitespace the parser trimmed is gone, and quoting, escaping and
+/// empty element tags follow the writer's conventions.
+pub fn capture_element<R: Read, B: BufferedXmlReader<R>>(reader: &mut B) -> Result<String> {
+    let mut events = Vec::new();
+    let mut used_prefixes = BTreeSet::new();
+    let mut depth = 0usize;
+
+    loop {
+        let event = reader.next()?;
+        match &event {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                depth += 1;
+                used_prefixes.insert(name.prefix.clone().unwrap_or_default());
+                used_prefixes.extend(
+                    attributes
+                        .iter()
+                        .filter_map(|attr| attr.name.prefix.clone()),
+                );
+            }
+            XmlEvent::EndElement { .. } => depth -= 1,
+            XmlEvent::EndDocument => {
+                return Err(Error::Custom {
+                    field: "unexpected end of document while capturing an element".to_string(),
+                })
+            }
+            _ => {}
+        }
+        events.push(event);
+
+        if depth == 0 {
+            break;
+        }
+    }
+
+    let mut writer = EmitterConfig::new()
+        .write_document_declaration(false)
+        .perform_indent(false)
+        .create_writer(Vec::new());
+    for mut event in events {
+        if let XmlEvent::StartElement { namespace, .. } = &mut event {
+            namespace
+                .0
+                .retain(|prefix, _| used_prefixes.contains(prefix));
+        }
+        if let Some(event) = event.as_writer_event() {
+            writer.write(event)?;
+        }
+    }
+
+    Ok(String::from_utf8(writer.into_inner())?)
+}
@@ -11,6 +11,8 @@ pub struct SeqAccess<'a, R: Read> {
     de: ChildDeserializer<'a, R>,
     max_size: Option<usize>,
     seq_type: SeqType,
+    /// Names of the items of sequences nested in the items, for a field named with a path.
+    item_path: Vec<String>,
 }
 
 pub enum SeqType {
@@ -26,7 +28,7 @@ pub enum SeqType {
 impl<'a, R: 'a + Read> SeqAccess<'a, R> {
     pub fn new(mut de: ChildDeserializer<'a, R>, max_size: Option<usize>) -> Self {
         let seq_type = if de.unset_map_value() {
-            debug_expect!(de.peek(), Ok(&XmlEvent::StartElement { ref name, .. }) => {
+            debug_expect!(de.peek(), Ok(XmlEvent::StartElement { name, .. }) => {
                 SeqType::ByElementName {
                     expected_name: name.local_name.clone(),
                     search_non_contiguous: de.non_contiguous_seq_elements
@@ -39,6 +41,21 @@ impl<'a, R: 'a + Read> SeqAccess<'a, R> {
             de,
             max_size,
             seq_type,
+            item_path: Vec::new(),
+        }
+    }
+
+    /// Sequence of the elements with the given name inside a wrapper element.
+    pub fn wrapped(de: ChildDeserializer<'a, R>, item: String, item_path: Vec<String>) -> Self {
+        let search_non_contiguous = de.non_contiguous_seq_elements;
+        SeqAccess {
+            de,
+            max_size: None,
+            seq_type: SeqType::ByElementName {
+                expected_name: item,
+                search_non_contiguous,
+            },
+            item_path,
         }
     }
 }
@@ -75,6 +92,7 @@ impl<'de, 'a, R: 'a + Read> de::SeqAccess<'de> for SeqAccess<'a, R> {
                             if &name.local_name == expected_name && local_depth == 0 =>
                         {
                             self.de.set_map_value();
+                            self.de.item_path = self.item_path.clone();
                             return seed.deserialize(&mut self.de).map(Some);
                         }
                         XmlEvent::StartElement { .. } => {
@@ -103,13 +121,13 @@ impl<'de, 'a, R: 'a + Read> de::SeqAccess<'de> for SeqAccess<'a, R> {
                 }
             }
             SeqType::AllMembers => {
-                let next_element = self.de.peek()?;
+                // Comments and processing instructions are members too, for enums with a
+                // `$comment` or `$pi` variant.
+                let next_element = self.de.peek_raw()?;
 
                 match next_element {
-                    XmlEvent::EndElement { .. } | XmlEvent::EndDocument => return Ok(None),
-                    _ => {
-                        return seed.deserialize(&mut self.de).map(Some);
-                    }
+                    XmlEvent::EndElement { .. } | XmlEvent::EndDocument => Ok(None),
+                    _ => seed.deserialize(&mut self.de).map(Some),
                 }
             }
         }
@@ -4,7 +4,7 @@ use serde::de::{self, Deserializer as SerdeDeserializer, IntoDeserializer};
 use xml::name::OwnedName;
 use xml::reader::XmlEvent;
 
-use crate::de::Deserializer;
+use crate::de::{misc_event, Deserializer};
 use crate::error::{Error, Result};
 use crate::expect;
 
@@ -16,7 +16,7 @@ pub struct EnumAccess<'a, R: Read, B: BufferedXmlReader<R>> {
 
 impl<'a, R: 'a + Read, B: BufferedXmlReader<R>> EnumAccess<'a, R, B> {
     pub fn new(de: &'a mut Deserializer<R, B>) -> Self {
-        EnumAccess { de: de }
+        EnumAccess { de }
     }
 }
 
@@ -28,10 +28,18 @@ impl<'de, 'a, R: 'a + Read, B: BufferedXmlReader<R>> de::EnumAccess<'de> for Enu
         self,
         seed: V,
     ) -> Result<(V::Value, VariantAccess<'a, R, B>)> {
+        // Comments and processing instructions are only passed on to enums that have a variant
+        // for them, see `Deserializer::deserialize_enum`.
+        if let Some((variant, text)) = misc_event(self.de.peek_raw()?) {
+            self.de.buffered_reader.next()?;
+            let name = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(variant))?;
+            return Ok((name, VariantAccess::misc(self.de, text)));
+        }
         let name = expect!(
             self.de.peek()?,
 
             &XmlEvent::Characters(ref name) |
+            &XmlEvent::CData(ref name) |
             &XmlEvent::StartElement { name: OwnedName { local_name: ref name, .. }, .. } => {
                 seed.deserialize(name.as_str().into_deserializer())
             }
@@ -43,11 +51,20 @@ impl<'de, 'a, R: 'a + Read, B: BufferedXmlReader<R>> de::EnumAccess<'de> for Enu
 
 pub struct VariantAccess<'a, R: Read, B: BufferedXmlReader<R>> {
     de: &'a mut Deserializer<R, B>,
+    /// Text of the comment or processing instruction already consumed for this variant, if any.
+    misc: Option<String>,
 }
 
 impl<'a, R: 'a + Read, B: BufferedXmlReader<R>> VariantAccess<'a, R, B> {
     pub fn new(de: &'a mut Deserializer<R, B>) -> Self {
-        VariantAccess { de: de }
+        VariantAccess { de, misc: None }
+    }
+
+    fn misc(de: &'a mut Deserializer<R, B>, text: String) -> Self {
+        VariantAccess {
+            de,
+            misc: Some(text),
+        }
     }
 }
 
@@ -57,6 +74,9 @@ impl<'de, 'a, R: 'a + Read, B: BufferedXmlReader<R>> de::VariantAccess<'de>
     type Error = Error;
 
     fn unit_variant(self) -> Result<()> {
+        if self.misc.is_some() {
+            return Ok(());
+        }
         self.de.unset_map_value();
         match self.de.next()? {
             XmlEvent::StartElement {
@@ -68,12 +88,15 @@ impl<'de, 'a, R: 'a + Read, B: BufferedXmlReader<R>> de::VariantAccess<'de>
                     Err(de::Error::invalid_length(attributes.len(), &"0"))
                 }
             }
-            XmlEvent::Characters(_) => Ok(()),
+            XmlEvent::Characters(_) | XmlEvent::CData(_) => Ok(()),
             _ => unreachable!(),
         }
     }
 
     fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        if let Some(text) = self.misc {
+            return seed.deserialize(text.into_deserializer());
+        }
         seed.deserialize(&mut *self.de)
     }
 
@@ -83,9 +106,9 @@ impl<'de, 'a, R: 'a + Read, B: BufferedXmlReader<R>> de::VariantAccess<'de>
 
     fn struct_variant<V: de::Visitor<'de>>(
         self,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
-        self.de.deserialize_map(visitor)
+        self.de.read_map(Some(fields), visitor)
     }
 }
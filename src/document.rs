@@ -0,0 +1,277 @@
+use std::cell::RefCell;
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use xml::common::XmlVersion;
+
+use crate::de::Deserializer;
+use crate::encoding::DecodingReader;
+use crate::error::{Error, Result};
+use crate::ser::Serializer;
+
+/// The XML declaration, as in `<?xml version="1.0" encoding="UTF-8" standalone="yes"?>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlDeclaration {
+    pub version: XmlVersion,
+    /// When writing, the output encoding is declared if not set, and a supported encoding set
+    /// here is used as the output encoding.
+    pub encoding: Option<String>,
+    pub standalone: Option<bool>,
+}
+
+impl Default for XmlDeclaration {
+    fn default() -> Self {
+        XmlDeclaration {
+            version: XmlVersion::Version10,
+            encoding: None,
+            standalone: None,
+        }
+    }
+}
+
+/// Everything in a document before its root element.
+///
+/// The parser reports a declaration with default values for a document without one, and doesn't
+/// report the DOCTYPE, so `Document` scans the start of the document for both. The prolog of
+/// `Deserializer::prolog` comes from the parser alone: its declaration is never `None` once the
+/// document has started, and its DOCTYPE always is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prolog {
+    /// Omitted if `None`. Defaults to an XML 1.0 declaration.
+    pub declaration: Option<XmlDeclaration>,
+    /// Contents of the DOCTYPE, between `<!DOCTYPE ` and `>`.
+    pub doctype: Option<String>,
+    /// Processing instructions such as `xml-stylesheet`, each given as its target followed by its
+    /// data if any.
+    pub processing_instructions: Vec<String>,
+}
+
+impl Default for Prolog {
+    fn default() -> Self {
+        Prolog {
+            declaration: Some(XmlDeclaration::default()),
+            doctype: None,
+            processing_instructions: Vec::new(),
+        }
+    }
+}
+
+/// A value together with the prolog of the document it is the root element of.
+///
+/// ```rust
+/// # use serde::{Deserialize, Serialize};
+/// # use serde_xml_rs::Document;
+/// #[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// struct Page {
+///     title: String,
+/// }
+///
+/// # fn main() {
+/// let src = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><?xml-stylesheet href="page.xsl"?><Page><title>Home</title></Page>"#;
+/// let document: Document<Page> = src.parse().unwrap();
+/// assert_eq!(document.root.title, "Home");
+/// assert_eq!(
+///     document.prolog.processing_instructions,
+///     vec![r#"xml-stylesheet href="page.xsl""#.to_string()]
+/// );
+/// assert_eq!(document.to_string().unwrap(), src);
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Document<T> {
+    pub prolog: Prolog,
+    pub root: T,
+}
+
+impl<T> Document<T> {
+    /// Wraps a value into a document with the default prolog.
+    pub fn new(root: T) -> Self {
+        Document {
+            prolog: Prolog::default(),
+            root,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Document<T> {
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        Self::from_utf8_reader(DecodingReader::new(reader))
+    }
+
+    fn from_utf8_reader<R: Read>(reader: R) -> Result<Self> {
+        let scan = Rc::new(RefCell::new(PrologScan::default()));
+        let reader = PrologReader {
+            inner: reader,
+            scan: scan.clone(),
+        };
+        let mut de = Deserializer::new_from_reader(reader);
+        let root = T::deserialize(&mut de)?;
+        let mut prolog = de.prolog().clone();
+        let scan = scan.take();
+        if let Some(declaration) = scan.declaration {
+            prolog.declaration = declaration;
+        }
+        if let Some(doctype) = scan.doctype {
+            prolog.doctype = doctype;
+        }
+        Ok(Document { prolog, root })
+    }
+}
+
+/// What is known of the parts of the prolog the parser doesn't report as written: whether the
+/// document has a declaration, and its DOCTYPE.
+#[derive(Default)]
+struct PrologScan {
+    /// The start of the document read so far, while it doesn't tell yet.
+    head: Vec<u8>,
+    /// The declaration, if the document has one, once known.
+    declaration: Option<Option<XmlDeclaration>>,
+    /// The contents of the DOCTYPE, if the document has one, once known.
+    doctype: Option<Option<String>>,
+}
+
+/// Passes a document on to the parser, and scans its start for a declaration and for a DOCTYPE
+/// before its root element.
+struct PrologReader<R: Read> {
+    inner: R,
+    scan: Rc<RefCell<PrologScan>>,
+}
+
+impl<R: Read> Read for PrologReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        let mut scan = self.scan.borrow_mut();
+        if scan.doctype.is_none() {
+            scan.head.extend_from_slice(&buf[..read]);
+            let head = match std::str::from_utf8(&scan.head) {
+                Ok(head) => Some(head),
+                // A character split between two reads
+                Err(e) if e.error_len().is_none() => {
+                    Some(std::str::from_utf8(&scan.head[..e.valid_up_to()]).unwrap_or_default())
+                }
+                Err(_) => None,
+            };
+            let (declaration, doctype) = match head {
+                Some(head) => (find_declaration(head), find_doctype(head)),
+                None => (Some(None), Some(None)),
+            };
+            if scan.declaration.is_none() {
+                scan.declaration = match declaration {
+                    None if read == 0 => Some(None),
+                    found => found,
+                };
+            }
+            scan.doctype = match doctype {
+                None if read == 0 => Some(None),
+                found => found,
+            };
+            if scan.doctype.is_some() {
+                scan.head = Vec::new();
+            }
+        }
+        Ok(read)
+    }
+}
+
+/// Reads the declaration at the start of a document. Returns `None` if more of the document is
+/// needed to tell, and `Some(None)` if the document has no declaration.
+fn find_declaration(head: &str) -> Option<Option<XmlDeclaration>> {
+    let head = head.trim_start_matches('\u{feff}');
+    let declaration = match head.strip_prefix("<?xml") {
+        Some(declaration) if declaration.starts_with(char::is_whitespace) => declaration,
+        // Cut short, or a processing instruction such as `<?xml-stylesheet ...?>`
+        Some("") => return None,
+        Some(_) => return Some(None),
+        None if "<?xml".starts_with(head) => return None,
+        None => return Some(None),
+    };
+    let end = declaration.find("?>")?;
+    let mut result = XmlDeclaration::default();
+    let mut rest = &declaration[..end];
+    while let Some((name, value, after)) = pseudo_attribute(rest) {
+        match name {
+            "version" if value == "1.1" => result.version = XmlVersion::Version11,
+            "encoding" => result.encoding = Some(value.to_string()),
+            "standalone" => result.standalone = Some(value == "yes"),
+            _ => {}
+        }
+        rest = after;
+    }
+    Some(Some(result))
+}
+
+/// Splits the first `name="value"` pair off the contents of a declaration, returning its name,
+/// its value and the rest of the contents.
+fn pseudo_attribute(contents: &str) -> Option<(&str, &str, &str)> {
+    let (name, rest) = contents.split_once('=')?;
+    let rest = rest.trim_start();
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let (value, rest) = rest[1..].split_once(quote)?;
+    Some((name.trim(), value, rest))
+}
+
+/// Finds the contents of the DOCTYPE in the start of a document, skipping the declaration,
+/// processing instructions, comments and whitespace before it. Returns `None` if more of the
+/// document is needed to tell, and `Some(None)` if there is no DOCTYPE before the root element.
+fn find_doctype(head: &str) -> Option<Option<String>> {
+    let mut rest = head.trim_start_matches('\u{feff}');
+    loop {
+        rest = rest.trim_start();
+        let skipped = if let Some(pi) = rest.strip_prefix("<?") {
+            pi.find("?>").map(|end| &pi[end + 2..])
+        } else if let Some(comment) = rest.strip_prefix("<!--") {
+            comment.find("-->").map(|end| &comment[end + 3..])
+        } else if let Some(doctype) = rest.strip_prefix("<!DOCTYPE") {
+            return doctype_end(doctype).map(|end| Some(doctype[..end].trim().to_string()));
+        } else if rest.is_empty() || "<!DOCTYPE".starts_with(rest) || "<!--".starts_with(rest) {
+            return None;
+        } else {
+            return Some(None);
+        };
+        rest = skipped?;
+    }
+}
+
+/// The position of the `>` closing a DOCTYPE, outside of quoted strings and of the internal
+/// subset between `[` and `]`.
+fn doctype_end(doctype: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut in_subset = false;
+    for (i, c) in doctype.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[') => in_subset = true,
+            (None, ']') => in_subset = false,
+            (None, '>') if !in_subset => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+impl<T: DeserializeOwned> FromStr for Document<T> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        // Already decoded, whatever encoding the declaration names
+        Self::from_utf8_reader(s.as_bytes())
+    }
+}
+
+impl<T: Serialize> Document<T> {
+    pub fn to_writer<W: Write>(&self, writer: W, is_pretty: bool) -> Result<()> {
+        let mut ser = Serializer::new(writer, is_pretty).prolog(self.prolog.clone());
+        self.root.serialize(&mut ser)
+    }
+
+    pub fn to_string(&self) -> Result<String> {
+        let mut writer = Vec::with_capacity(128);
+        self.to_writer(&mut writer, false)?;
+        Ok(String::from_utf8(writer)?)
+    }
+}
@@ -0,0 +1,402 @@
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+use crate::error::{Error, Result};
+
+/// Character encodings supported when reading and writing documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// US-ASCII
+    Ascii,
+    /// ISO-8859-1
+    Latin1,
+    Windows1251,
+    Windows1252,
+}
+
+impl Encoding {
+    /// Looks up an encoding by the name it is declared with, ignoring case.
+    pub fn for_label(label: &str) -> Option<Encoding> {
+        match label.trim().to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(Encoding::Utf8),
+            "utf-16" | "utf-16le" => Some(Encoding::Utf16Le),
+            "utf-16be" => Some(Encoding::Utf16Be),
+            "us-ascii" | "ascii" => Some(Encoding::Ascii),
+            "iso-8859-1" | "iso8859-1" | "iso_8859-1" | "latin1" | "l1" => Some(Encoding::Latin1),
+            "windows-1251" | "cp1251" => Some(Encoding::Windows1251),
+            "windows-1252" | "cp1252" => Some(Encoding::Windows1252),
+            _ => None,
+        }
+    }
+
+    /// The name the encoding is declared with.
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le | Encoding::Utf16Be => "UTF-16",
+            Encoding::Ascii => "US-ASCII",
+            Encoding::Latin1 => "ISO-8859-1",
+            Encoding::Windows1251 => "windows-1251",
+            Encoding::Windows1252 => "windows-1252",
+        }
+    }
+
+    /// Upper half of the code page of a single-byte encoding, `'\0'` marking unassigned bytes.
+    fn high_half(self) -> Option<&'static [char; 128]> {
+        match self {
+            Encoding::Windows1251 => Some(&WINDOWS_1251),
+            Encoding::Windows1252 => Some(&WINDOWS_1252),
+            _ => None,
+        }
+    }
+
+    fn decode_byte(self, byte: u8) -> Option<char> {
+        match self.high_half() {
+            _ if byte < 0x80 => Some(byte as char),
+            Some(table) => Some(table[byte as usize - 0x80]).filter(|c| *c != '\0'),
+            None if self == Encoding::Ascii => None,
+            None => Some(byte as char),
+        }
+    }
+
+    /// The byte for a character in a single-byte encoding, if it has one.
+    fn encode_byte(self, c: char) -> Option<u8> {
+        match self.high_half() {
+            _ if (c as u32) < 0x80 => Some(c as u8),
+            Some(table) => table.iter().position(|t| *t == c).map(|i| (i + 0x80) as u8),
+            None if self == Encoding::Ascii => None,
+            None => u8::try_from(c as u32).ok(),
+        }
+    }
+
+    /// Checks that the encoding can represent all characters of some text written where a
+    /// character reference can't stand in for a character, such as a name or a comment.
+    pub(crate) fn check_encodable(self, text: &str) -> Result<()> {
+        if matches!(self, Encoding::Utf8 | Encoding::Utf16Le | Encoding::Utf16Be) {
+            return Ok(());
+        }
+        match text.chars().find(|c| self.encode_byte(*c).is_none()) {
+            Some(character) => Err(Error::UnencodableCharacter {
+                character,
+                encoding: self.name().to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Encodes a character of text or of an attribute value. Characters that the encoding can't
+    /// represent are written as character references.
+    fn encode_char(self, c: char, out: &mut Vec<u8>) {
+        match self {
+            Encoding::Utf8 => out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    out.extend_from_slice(&if self == Encoding::Utf16Le {
+                        unit.to_le_bytes()
+                    } else {
+                        unit.to_be_bytes()
+                    });
+                }
+            }
+            _ => {
+                match self.encode_byte(c) {
+                    Some(byte) => out.push(byte),
+                    // Not representable: fall back to a character reference
+                    None => {
+                        for b in format!("&#x{:X};", c as u32).bytes() {
+                            self.encode_char(b as char, out);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Wraps a reader of an XML document in any supported encoding, and yields the document as UTF-8.
+///
+/// The encoding is detected from the byte order mark, or else from the `encoding` of the XML
+/// declaration, defaulting to UTF-8. A document declared in an encoding that isn't supported fails
+/// to read with `Error::UnsupportedEncoding`. `from_reader` does this already; use it directly
+/// with `Deserializer::new` and a custom `EventReader`.
+///
+/// ```rust
+/// # use serde::Deserialize;
+/// # use serde_xml_rs::from_reader;
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Item {
+///     name: String,
+/// }
+///
+/// # fn main() {
+/// let src = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><Item><name>Caf\xe9</name></Item>";
+/// let item: Item = from_reader(&src[..]).unwrap();
+/// assert_eq!(item.name, "Café");
+/// # }
+/// ```
+pub struct DecodingReader<R: Read> {
+    inner: R,
+    /// `None` until detected from the start of the document.
+    encoding: Option<Encoding>,
+    /// Bytes read from `inner` that are not decoded yet.
+    pending: Vec<u8>,
+    /// Decoded bytes that have not been read yet.
+    decoded: Vec<u8>,
+    position: usize,
+    eof: bool,
+}
+
+impl<R: Read> DecodingReader<R> {
+    pub fn new(inner: R) -> Self {
+        DecodingReader {
+            inner,
+            encoding: None,
+            pending: Vec::new(),
+            decoded: Vec::new(),
+            position: 0,
+            eof: false,
+        }
+    }
+
+    /// The detected encoding, once something has been read.
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn fill_pending(&mut self) -> io::Result<()> {
+        let mut chunk = [0; 4096];
+        let read = self.inner.read(&mut chunk)?;
+        if read == 0 {
+            self.eof = true;
+        }
+        self.pending.extend_from_slice(&chunk[..read]);
+        Ok(())
+    }
+
+    /// Detects the encoding once enough of the document has been read, and strips the byte
+    /// order mark.
+    fn detect(&mut self) -> io::Result<Option<Encoding>> {
+        let (encoding, bom) = match self.pending.as_slice() {
+            [0xEF, 0xBB, 0xBF, ..] => (Encoding::Utf8, 3),
+            [0xFF, 0xFE, ..] => (Encoding::Utf16Le, 2),
+            [0xFE, 0xFF, ..] => (Encoding::Utf16Be, 2),
+            [0x3C, 0x00, 0x3F, 0x00, ..] => (Encoding::Utf16Le, 0),
+            [0x00, 0x3C, 0x00, 0x3F, ..] => (Encoding::Utf16Be, 0),
+            pending if pending.len() < 4 && !self.eof => return Ok(None),
+            pending if pending.starts_with(b"<?xml") => {
+                match pending.windows(2).position(|w| w == b"?>") {
+                    Some(end) => (declared_encoding(&pending[..end])?, 0),
+                    None if !self.eof && pending.len() < 1024 => return Ok(None),
+                    None => (Encoding::Utf8, 0),
+                }
+            }
+            _ => (Encoding::Utf8, 0),
+        };
+        self.pending.drain(..bom);
+        Ok(Some(encoding))
+    }
+
+    /// Decodes as much of the pending bytes as possible.
+    fn decode(&mut self, encoding: Encoding) -> io::Result<()> {
+        let consumed = match encoding {
+            Encoding::Utf8 => {
+                self.decoded.extend_from_slice(&self.pending);
+                self.pending.len()
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let units: Vec<u16> = self
+                    .pending
+                    .chunks_exact(2)
+                    .map(|pair| {
+                        if encoding == Encoding::Utf16Le {
+                            u16::from_le_bytes([pair[0], pair[1]])
+                        } else {
+                            u16::from_be_bytes([pair[0], pair[1]])
+                        }
+                    })
+                    .collect();
+                // Keep a trailing high surrogate until its pair has been read
+                let complete = match units.last() {
+                    Some(0xD800..=0xDBFF) if !self.eof => units.len() - 1,
+                    _ => units.len(),
+                };
+                for c in char::decode_utf16(units[..complete].iter().copied()) {
+                    let c = c.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                    self.decoded
+                        .extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                complete * 2
+            }
+            _ => {
+                for &byte in &self.pending {
+                    let c = encoding.decode_byte(byte).ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("byte 0x{:X} is not valid {}", byte, encoding.name()),
+                        )
+                    })?;
+                    self.decoded
+                        .extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                self.pending.len()
+            }
+        };
+        self.pending.drain(..consumed);
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.decoded.len() {
+            self.decoded.clear();
+            self.position = 0;
+            if self.eof && self.pending.is_empty() {
+                return Ok(0);
+            }
+            self.fill_pending()?;
+            let encoding = match self.encoding {
+                Some(encoding) => encoding,
+                None => match self.detect()? {
+                    Some(encoding) => *self.encoding.insert(encoding),
+                    None => continue,
+                },
+            };
+            self.decode(encoding)?;
+            if self.eof && !self.pending.is_empty() && self.decoded.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "incomplete character at end of document",
+                ));
+            }
+        }
+
+        let read = buf.len().min(self.decoded.len() - self.position);
+        buf[..read].copy_from_slice(&self.decoded[self.position..self.position + read]);
+        self.position += read;
+        Ok(read)
+    }
+}
+
+/// Finds the encoding named in an XML declaration, without the closing `?>`, defaulting to UTF-8
+/// if none is named. An encoding that isn't supported is an error, carrying an
+/// `Error::UnsupportedEncoding` for the `Deserializer` to report.
+fn declared_encoding(declaration: &[u8]) -> io::Result<Encoding> {
+    let declaration = String::from_utf8_lossy(declaration);
+    let label = declaration.find("encoding").and_then(|start| {
+        let rest = declaration[start + "encoding".len()..]
+            .trim_start()
+            .strip_prefix('=')?
+            .trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        rest[1..].split(quote).next()
+    });
+    match label {
+        Some(label) => Encoding::for_label(label).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                Error::UnsupportedEncoding {
+                    encoding: label.to_string(),
+                },
+            )
+        }),
+        None => Ok(Encoding::Utf8),
+    }
+}
+
+/// Encodes the UTF-8 written by the `EventWriter` into the output encoding. UTF-16 output starts
+/// with a byte order mark.
+pub(crate) struct EncodingWriter<W: Write> {
+    inner: W,
+    encoding: Encoding,
+    /// Bytes of a character split across writes.
+    partial: Vec<u8>,
+    started: bool,
+}
+
+impl<W: Write> EncodingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        EncodingWriter {
+            inner,
+            encoding: Encoding::Utf8,
+            partial: Vec::new(),
+            started: false,
+        }
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+}
+
+impl<W: Write> Write for EncodingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.encoding == Encoding::Utf8 {
+            return self.inner.write(buf);
+        }
+
+        let mut out = Vec::with_capacity(buf.len() * 2);
+        if !self.started {
+            self.started = true;
+            if matches!(self.encoding, Encoding::Utf16Le | Encoding::Utf16Be) {
+                self.encoding.encode_char('\u{FEFF}', &mut out);
+            }
+        }
+        self.partial.extend_from_slice(buf);
+        let valid = match std::str::from_utf8(&self.partial) {
+            Ok(s) => s.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        let text = std::str::from_utf8(&self.partial[..valid]).unwrap_or_default();
+        for c in text.chars() {
+            self.encoding.encode_char(c, &mut out);
+        }
+        self.partial.drain(..valid);
+        self.inner.write_all(&out)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[rustfmt::skip]
+static WINDOWS_1251: [char; 128] = [
+    'Ђ', 'Ѓ', '‚', 'ѓ', '„', '…', '†', '‡', '€', '‰', 'Љ', '‹', 'Њ', 'Ќ', 'Ћ', 'Џ',
+    'ђ', '‘', '’', '“', '”', '•', '–', '—', '\0', '™', 'љ', '›', 'њ', 'ќ', 'ћ', 'џ',
+    '\u{A0}', 'Ў', 'ў', 'Ј', '¤', 'Ґ', '¦', '§', 'Ё', '©', 'Є', '«', '¬', '\u{AD}', '®', 'Ї',
+    '°', '±', 'І', 'і', 'ґ', 'µ', '¶', '·', 'ё', '№', 'є', '»', 'ј', 'Ѕ', 'ѕ', 'ї',
+    'А', 'Б', 'В', 'Г', 'Д', 'Е', 'Ж', 'З', 'И', 'Й', 'К', 'Л', 'М', 'Н', 'О', 'П',
+    'Р', 'С', 'Т', 'У', 'Ф', 'Х', 'Ц', 'Ч', 'Ш', 'Щ', 'Ъ', 'Ы', 'Ь', 'Э', 'Ю', 'Я',
+    'а', 'б', 'в', 'г', 'д', 'е', 'ж', 'з', 'и', 'й', 'к', 'л', 'м', 'н', 'о', 'п',
+    'р', 'с', 'т', 'у', 'ф', 'х', 'ц', 'ч', 'ш', 'щ', 'ъ', 'ы', 'ь', 'э', 'ю', 'я',
+];
+
+#[rustfmt::skip]
+static WINDOWS_1252: [char; 128] = [
+    '€', '\0', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\0', 'Ž', '\0',
+    '\0', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\0', 'ž', 'Ÿ',
+    '\u{A0}', '¡', '¢', '£', '¤', '¥', '¦', '§', '¨', '©', 'ª', '«', '¬', '\u{AD}', '®', '¯',
+    '°', '±', '²', '³', '´', 'µ', '¶', '·', '¸', '¹', 'º', '»', '¼', '½', '¾', '¿',
+    'À', 'Á', 'Â', 'Ã', 'Ä', 'Å', 'Æ', 'Ç', 'È', 'É', 'Ê', 'Ë', 'Ì', 'Í', 'Î', 'Ï',
+    'Ð', 'Ñ', 'Ò', 'Ó', 'Ô', 'Õ', 'Ö', '×', 'Ø', 'Ù', 'Ú', 'Û', 'Ü', 'Ý', 'Þ', 'ß',
+    'à', 'á', 'â', 'ã', 'ä', 'å', 'æ', 'ç', 'è', 'é', 'ê', 'ë', 'ì', 'í', 'î', 'ï',
+    'ð', 'ñ', 'ò', 'ó', 'ô', 'õ', 'ö', '÷', 'ø', 'ù', 'ú', 'û', 'ü', 'ý', 'þ', 'ÿ',
+];
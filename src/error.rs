@@ -2,15 +2,40 @@ use serde::de::Error as DeError;
 use serde::ser::Error as SerError;
 use std::fmt::Display;
 use thiserror::Error;
+use xml::common::TextPosition;
 
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Expected token {token}, found {found}")]
     UnexpectedToken { token: String, found: String },
+    #[error("Expected root element <{expected}>, found <{found}>")]
+    UnexpectedRoot { expected: String, found: String },
+    #[error("Unknown attribute '{name}' on <{element}>")]
+    UnknownAttribute { name: String, element: String },
+    #[error("Unexpected text content in <{element}>")]
+    UnexpectedText { element: String },
+    #[error("'{name}' is not a valid element name")]
+    InvalidName { name: String },
+    #[error("Duplicate element <{name}> in <{element}> at {position}")]
+    DuplicateElement {
+        name: String,
+        element: String,
+        position: TextPosition,
+    },
+    #[error("Element <{name}> must appear before <{after}>")]
+    OutOfOrderElement { name: String, after: String },
+    #[error("Invalid document at {path}: {message}")]
+    Validation { path: String, message: String },
+    #[error("Invalid schema: {message}")]
+    InvalidSchema { message: String },
     #[error("custom: {field}")]
     Custom { field: String },
     #[error("unsupported operation: '{operation}'")]
     UnsupportedOperation { operation: String },
+    #[error("Unsupported encoding '{encoding}'")]
+    UnsupportedEncoding { encoding: String },
+    #[error("'{character}' can't be written in {encoding} outside text and attribute values")]
+    UnencodableCharacter { character: char, encoding: String },
 
     #[error("IO error: {source}")]
     Io {
@@ -44,7 +69,7 @@ pub enum Error {
 
     #[error("Syntax: {source}")]
     Syntax {
-        #[from]
+        #[source]
         source: ::xml::reader::Error,
     },
 
@@ -111,3 +136,20 @@ impl SerError for Error {
         }
     }
 }
+
+impl From<::xml::reader::Error> for Error {
+    fn from(source: ::xml::reader::Error) -> Self {
+        // An unsupported encoding found by the `DecodingReader` is reported as such, rather than
+        // as the reading error the parser wraps it into
+        if let ::xml::reader::ErrorKind::Io(io) = source.kind() {
+            if let Some(Error::UnsupportedEncoding { encoding }) =
+                io.get_ref().and_then(|inner| inner.downcast_ref())
+            {
+                return Error::UnsupportedEncoding {
+                    encoding: encoding.clone(),
+                };
+            }
+        }
+        Error::Syntax { source }
+    }
+}
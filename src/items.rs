@@ -0,0 +1,138 @@
+//! Names of the items of a single sequence field, whatever the `ItemNames` of the serializer and
+//! deserializer, for use with `#[serde(with = "...")]`. A field can also name its items
+//! explicitly with a path such as `Items/Item`.
+//!
+//! ```rust
+//! # use serde::{Deserialize, Serialize};
+//! # use serde_xml_rs::{from_str, to_string};
+//! #[derive(Debug, Serialize, Deserialize, PartialEq)]
+//! struct Channel {
+//!     id: u32,
+//! }
+//!
+//! #[derive(Debug, Serialize, Deserialize, PartialEq)]
+//! struct Device {
+//!     #[serde(with = "serde_xml_rs::items::type_name")]
+//!     channels: Vec<Channel>,
+//!     #[serde(rename = "presets/preset")]
+//!     presets: Vec<u32>,
+//!     port: Vec<u16>,
+//! }
+//!
+//! # fn main() {
+//! let device = Device {
+//!     channels: vec![Channel { id: 1 }, Channel { id: 2 }],
+//!     presets: vec![3],
+//!     port: vec![80, 443],
+//! };
+//! let xml = to_string(&device).unwrap();
+//! assert_eq!(
+//!     xml,
+//!     concat!(
+//!         r#"<?xml version="1.0" encoding="UTF-8"?><Device>"#,
+//!         "<channels><Channel><id>1</id></Channel><Channel><id>2</id></Channel></channels>",
+//!         "<presets><preset>3</preset></presets>",
+//!         "<port>80</port><port>443</port>",
+//!         "</Device>"
+//!     )
+//! );
+//! assert_eq!(from_str::<Device>(&xml).unwrap(), device);
+//! # }
+//! ```
+
+use std::marker::PhantomData;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::layout::ItemNames;
+
+/// Names of the newtype structs through which the item names of a field are recognised by this
+/// crate's `Serializer` and `Deserializer`.
+const FIELD_NAME_TOKEN: &str = "$serde_xml_rs::items::FieldName";
+const TYPE_NAME_TOKEN: &str = "$serde_xml_rs::items::TypeName";
+
+/// The item names chosen for a field, given the name of a newtype struct.
+pub(crate) fn item_names_for(name: &str) -> Option<ItemNames> {
+    match name {
+        FIELD_NAME_TOKEN => Some(ItemNames::FieldName),
+        TYPE_NAME_TOKEN => Some(ItemNames::TypeName),
+        _ => None,
+    }
+}
+
+fn serialize_as<T, S>(token: &'static str, value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ?Sized + Serialize,
+    S: Serializer,
+{
+    serializer.serialize_newtype_struct(token, value)
+}
+
+fn deserialize_as<'de, T, D>(token: &'static str, deserializer: D) -> Result<T, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    struct ItemsVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> de::Visitor<'de> for ItemsVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a sequence")
+        }
+
+        fn visit_newtype_struct<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<T, D::Error> {
+            T::deserialize(deserializer)
+        }
+    }
+
+    deserializer.deserialize_newtype_struct(token, ItemsVisitor(PhantomData))
+}
+
+/// `<field>a</field><field>b</field>`: each item is an element named after the field.
+pub mod field_name {
+    use super::*;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serialize_as(FIELD_NAME_TOKEN, value, serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserialize_as(FIELD_NAME_TOKEN, deserializer)
+    }
+}
+
+/// `<field><Channel>a</Channel><Channel>b</Channel></field>`: the items are the children of the
+/// field element, named after their type for structs and enum variants and `item` otherwise.
+pub mod type_name {
+    use super::*;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        serialize_as(TYPE_NAME_TOKEN, value, serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserialize_as(TYPE_NAME_TOKEN, deserializer)
+    }
+}
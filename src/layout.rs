@@ -0,0 +1,256 @@
+use std::borrow::Cow;
+
+/// How the entries of a map are laid out as elements. The same layout must be set on the
+/// `Serializer`, through `SerializerConfig::map_layout`, and on the `Deserializer` to read a map
+/// back. It applies to maps, not to structs, except structs with `#[serde(flatten)]` fields:
+/// serde serializes and deserializes those as maps, so they are only supported with the default
+/// `ElementNames`, and the `Serializer` reports them as `Error::UnsupportedOperation` with any
+/// other layout.
+///
+/// ```rust
+/// # use std::collections::BTreeMap;
+/// # use serde::Deserialize;
+/// # use serde_xml_rs::ser::to_string_with_config;
+/// # use serde_xml_rs::{Deserializer, MapLayout, SerializerConfig};
+/// # fn main() {
+/// let mut ports = BTreeMap::new();
+/// ports.insert(80, "http".to_string());
+/// ports.insert(443, "https".to_string());
+///
+/// let layout = MapLayout::key_attribute("port", "number");
+/// let config = SerializerConfig::new()
+///     .xml_declaration(false)
+///     .map_layout(layout.clone());
+/// let mut buffer = Vec::new();
+/// let mut ser = serde_xml_rs::Serializer::with_config(&mut buffer, &config).root_name("ports");
+/// serde::Serialize::serialize(&ports, &mut ser).unwrap();
+/// let xml = String::from_utf8(buffer).unwrap();
+/// assert_eq!(
+///     xml,
+///     r#"<ports><port number="80">http</port><port number="443">https</port></ports>"#
+/// );
+///
+/// let mut de = Deserializer::new_from_reader(xml.as_bytes()).map_layout(layout);
+/// assert_eq!(BTreeMap::<u16, String>::deserialize(&mut de).unwrap(), ports);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum MapLayout {
+    /// `<key>value</key>`. Keys that aren't valid element names are reported as
+    /// `Error::InvalidName`.
+    #[default]
+    ElementNames,
+    /// `<entry key="...">value</entry>`, with the names of the element and the attribute.
+    KeyAttribute {
+        element: Cow<'static, str>,
+        attribute: Cow<'static, str>,
+    },
+    /// `<entry><key>...</key><value>...</value></entry>`, with the names of the three elements.
+    KeyValueElements {
+        element: Cow<'static, str>,
+        key: Cow<'static, str>,
+        value: Cow<'static, str>,
+    },
+}
+
+impl MapLayout {
+    pub fn key_attribute<E, A>(element: E, attribute: A) -> Self
+    where
+        E: Into<Cow<'static, str>>,
+        A: Into<Cow<'static, str>>,
+    {
+        MapLayout::KeyAttribute {
+            element: element.into(),
+            attribute: attribute.into(),
+        }
+    }
+
+    pub fn key_value_elements<E, K, V>(element: E, key: K, value: V) -> Self
+    where
+        E: Into<Cow<'static, str>>,
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+    {
+        MapLayout::KeyValueElements {
+            element: element.into(),
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// How the items of sequence fields are named. The same choice must be set on the `Serializer`,
+/// through `SerializerConfig::item_names`, and on the `Deserializer` to read the items back.
+///
+/// The choice applies to every sequence field, unless a field makes its own with the `items`
+/// module or names its items explicitly with a path such as `Items/Item`. The items of a `$value`
+/// sequence are always named after their type.
+///
+/// ```rust
+/// # use serde::{Deserialize, Serialize};
+/// # use serde_xml_rs::ser::to_string_with_config;
+/// # use serde_xml_rs::{Deserializer, ItemNames, SerializerConfig};
+/// #[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// struct Channel {
+///     id: u32,
+/// }
+///
+/// #[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// struct Channels {
+///     channels: Vec<Channel>,
+/// }
+///
+/// # fn main() {
+/// let channels = Channels {
+///     channels: vec![Channel { id: 1 }, Channel { id: 2 }],
+/// };
+/// let config = SerializerConfig::new()
+///     .xml_declaration(false)
+///     .item_names(ItemNames::TypeName);
+/// let xml = to_string_with_config(&channels, &config).unwrap();
+/// assert_eq!(
+///     xml,
+///     "<Channels><channels><Channel><id>1</id></Channel><Channel><id>2</id></Channel></channels></Channels>"
+/// );
+///
+/// let mut de = Deserializer::new_from_reader(xml.as_bytes()).item_names(ItemNames::TypeName);
+/// assert_eq!(Channels::deserialize(&mut de).unwrap(), channels);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ItemNames {
+    /// `<field>a</field><field>b</field>`: each item is an element named after the field.
+    #[default]
+    FieldName,
+    /// `<field><Channel>…</Channel><Channel>…</Channel></field>`: the items are the children of
+    /// the field element, named after their type for structs and enum variants, and `item`
+    /// otherwise, as in `<field><item>1</item><item>2</item></field>`.
+    /// Nested sequences can't be read back.
+    TypeName,
+    /// `<field><name>a</name><name>b</name></field>`: the items are the children of the field
+    /// element, with the given name.
+    Element(Cow<'static, str>),
+}
+
+impl ItemNames {
+    pub fn element<N: Into<Cow<'static, str>>>(name: N) -> Self {
+        ItemNames::Element(name.into())
+    }
+}
+
+/// How `None` is written for a field or a sequence item. Attributes with a `None` value are
+/// always omitted. The `Deserializer` reads `xsi:nil` elements as `None` whatever the policy, and
+/// empty elements too if set to `EmptyElement`.
+///
+/// ```rust
+/// # use serde::{Deserialize, Serialize};
+/// # use serde_xml_rs::ser::to_string_with_config;
+/// # use serde_xml_rs::{Deserializer, NoneValues, SerializerConfig};
+/// #[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// struct Channel {
+///     name: Option<String>,
+///     bitrate: Option<u32>,
+/// }
+///
+/// # fn main() {
+/// let channel = Channel { name: Some("Front".to_string()), bitrate: None };
+/// let config = SerializerConfig::new()
+///     .xml_declaration(false)
+///     .none_values(NoneValues::XsiNil);
+/// let xml = to_string_with_config(&channel, &config).unwrap();
+/// assert_eq!(
+///     xml,
+///     r#"<Channel><name>Front</name><bitrate xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:nil="true" /></Channel>"#
+/// );
+///
+/// let mut de = Deserializer::new_from_reader(xml.as_bytes());
+/// assert_eq!(Channel::deserialize(&mut de).unwrap(), channel);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoneValues {
+    /// Nothing is written.
+    #[default]
+    Omit,
+    /// `<field />`, which can't be told apart from an empty string.
+    EmptyElement,
+    /// `<field xsi:nil="true" />`, along with the declaration of the `xsi` namespace.
+    XsiNil,
+}
+
+/// How an empty sequence is written for a field. Omitted fields are only read back as empty
+/// sequences with `#[serde(default)]`. An empty element is read back as an empty sequence by a
+/// `Deserializer` with the same policy, rather than as a sequence of one empty item.
+///
+/// Omitting a sequence is lossy for optional sequences: `Some(vec![])` is written like `None`,
+/// and read back as `None`. Use `EmptyElement` to keep them apart for every field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptySequences {
+    /// The wrapper element of the items is written without items, for a field named with a path
+    /// such as `Items/Item` or with `ItemNames` other than `FieldName`. Nothing is written for a
+    /// field whose items are named after it.
+    #[default]
+    EmptyWrapper,
+    /// Nothing is written, not even the wrapper element of the items if there is one.
+    Omit,
+    /// `<field />`, or the wrapper element without any items.
+    EmptyElement,
+}
+
+/// How booleans are written, in elements and attributes alike. A `Deserializer` reads `true`,
+/// `false`, `1` and `0` in any case, and the values of the format it's configured with. See also
+/// the `bools` module for the format of a single field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoolFormat {
+    /// `true` and `false`.
+    #[default]
+    TrueFalse,
+    /// `1` and `0`.
+    OneZero,
+    /// `TRUE` and `FALSE`.
+    UpperCase,
+    /// The given values for `true` and `false`.
+    Custom(&'static str, &'static str),
+}
+
+impl BoolFormat {
+    pub fn format(&self, v: bool) -> &'static str {
+        let (true_value, false_value) = match *self {
+            BoolFormat::TrueFalse => ("true", "false"),
+            BoolFormat::OneZero => ("1", "0"),
+            BoolFormat::UpperCase => ("TRUE", "FALSE"),
+            BoolFormat::Custom(true_value, false_value) => (true_value, false_value),
+        };
+        if v {
+            true_value
+        } else {
+            false_value
+        }
+    }
+
+    /// Reads a boolean written in this format, or else as `true`, `false`, `1` or `0` in any
+    /// case. The values of the format come first, so that a custom format can swap the others.
+    pub fn parse(&self, s: &str) -> Option<bool> {
+        if s == self.format(true) {
+            Some(true)
+        } else if s == self.format(false) {
+            Some(false)
+        } else if s == "1" || s.eq_ignore_ascii_case("true") {
+            Some(true)
+        } else if s == "0" || s.eq_ignore_ascii_case("false") {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether a string is a valid XML element name.
+pub(crate) fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' || c == ':' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.' | '\u{B7}'))
+}
@@ -99,9 +99,13 @@
 //!
 //! ## Repeated tags
 //!
+//! The items of a `$value` sequence are the children of the element, and enum items are written
+//! as elements named after their variant.
+//!
 //! ```rust
 //! # use serde::{Deserialize, Serialize};
 //! # use serde_xml_rs::{from_str, to_string};
+//! # use serde_xml_rs::ser::to_string_with_root;
 //!
 //! #[derive(Debug, Serialize, Deserialize, PartialEq)]
 //! struct PlateAppearance {
@@ -118,8 +122,11 @@
 //!
 //! #[derive(Debug, Serialize, Deserialize, PartialEq)]
 //! struct Pitch {
+//!     #[serde(rename = "@speed")]
 //!     speed: u32,
+//!     #[serde(rename = "@type")]
 //!     r#type: PitchType,
+//!     #[serde(rename = "@outcome")]
 //!     outcome: PitchOutcome,
 //! }
 //!
@@ -131,7 +138,12 @@
 //!
 //! #[derive(Debug, Serialize, Deserialize, PartialEq)]
 //! struct Runner {
-//!     from: Base, to: Option<Base>, outcome: RunnerOutcome,
+//!     #[serde(rename = "@from")]
+//!     from: Base,
+//!     #[serde(rename = "@to")]
+//!     to: Option<Base>,
+//!     #[serde(rename = "@outcome")]
+//!     outcome: RunnerOutcome,
 //! }
 //!
 //! #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -151,6 +163,74 @@
 //!         </plate-appearance>"#;
 //!     let plate_appearance: PlateAppearance = from_str(document).unwrap();
 //!     assert_eq!(plate_appearance.events[0], Event::Pitch(Pitch { speed: 95, r#type: PitchType::FourSeam, outcome: PitchOutcome::Ball }));
+//!
+//!     let serialized = to_string_with_root("plate-appearance", &[], &plate_appearance).unwrap();
+//!     assert!(serialized.contains(r#"<runner from="First" to="Second" outcome="Steal" />"#));
+//!     assert_eq!(from_str::<PlateAppearance>(&serialized).unwrap(), plate_appearance);
+//! }
+//! ```
+//!
+//! ## Wrapped lists
+//!
+//! A field named with a path such as `Items/Item` holds the `<Item>` children of an `<Items>`
+//! element, without a struct for the wrapper. Each further name wraps a level of nested
+//! sequences. The wrapper is omitted for `None`, and written without items for an empty sequence
+//! unless `SerializerConfig::empty_sequences` says otherwise.
+//!
+//! ```rust
+//! # use serde::{Deserialize, Serialize};
+//! # use serde_xml_rs::{from_str, to_string};
+//! #[derive(Debug, Serialize, Deserialize, PartialEq)]
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! #[derive(Debug, Serialize, Deserialize, PartialEq)]
+//! struct Region {
+//!     #[serde(rename = "RegionCoordinatesList/RegionCoordinates")]
+//!     coordinates: Vec<Point>,
+//! }
+//!
+//! fn main() {
+//!     let src = r#"<?xml version="1.0" encoding="UTF-8"?><Region><RegionCoordinatesList><RegionCoordinates><x>0</x><y>0</y></RegionCoordinates><RegionCoordinates><x>4</x><y>3</y></RegionCoordinates></RegionCoordinatesList></Region>"#;
+//!     let region: Region = from_str(src).unwrap();
+//!     assert_eq!(region.coordinates[1], Point { x: 4, y: 3 });
+//!     assert_eq!(to_string(&region).unwrap(), src);
+//! }
+//! ```
+//!
+//! ## Unknown attributes and elements
+//!
+//! Attributes and child elements that don't match any field are normally dropped. A `@*` field
+//! collects the extra attributes into a map, along with the namespace declarations made on the
+//! element, and a `$unknown` field collects the extra elements as XML strings. Both are written
+//! back when serializing, so that they survive a round trip.
+//!
+//! Strings collected into a `$unknown` field are written together where the field is declared;
+//! `<a/><x/><b/>` read into a struct with the fields `a`, `b` and `$unknown` is written back as
+//! `<a/><b/><x/>`. To keep the unknown elements at their positions, collect them as `Anchored`
+//! items and declare the `$unknown` field first.
+//!
+//! ```rust
+//! # use serde::{Deserialize, Serialize};
+//! # use serde_xml_rs::{from_str, to_string};
+//! # use std::collections::BTreeMap;
+//! #[derive(Debug, Serialize, Deserialize, PartialEq)]
+//! struct Channel {
+//!     #[serde(rename = "@*")]
+//!     extra_attributes: BTreeMap<String, String>,
+//!     name: String,
+//!     #[serde(rename = "$unknown")]
+//!     unknown: Vec<String>,
+//! }
+//!
+//! fn main() {
+//!     let src = r#"<?xml version="1.0" encoding="UTF-8"?><Channel version="2.0"><name>Front door</name><bitrate>4096</bitrate></Channel>"#;
+//!     let channel: Channel = from_str(src).unwrap();
+//!     assert_eq!(channel.extra_attributes["version"], "2.0");
+//!     assert_eq!(channel.unknown, vec!["<bitrate>4096</bitrate>".to_string()]);
+//!     assert_eq!(to_string(&channel).unwrap(), src);
 //! }
 //! ```
 //!
@@ -184,11 +264,33 @@
 //! ```
 //!
 
+mod anchored;
+pub mod bools;
+mod cdata;
+pub mod codegen;
 pub mod de;
+mod document;
+mod encoding;
 mod error;
+pub mod items;
+mod layout;
+mod raw;
+mod root;
+mod schema;
 pub mod ser;
 
+pub use crate::anchored::Anchored;
+pub use crate::cdata::{CData, Text};
 pub use crate::de::{from_reader, from_str, Deserializer};
+pub use crate::document::{Document, Prolog, XmlDeclaration};
+pub use crate::encoding::{DecodingReader, Encoding};
 pub use crate::error::Error;
-pub use crate::ser::{to_string, to_string_pretty, to_writer, Serializer};
+pub use crate::layout::{BoolFormat, EmptySequences, ItemNames, MapLayout, NoneValues};
+pub use crate::raw::{Lazy, RawXml};
+pub use crate::root::Root;
+pub use crate::schema::{schema_for, Schema, XsdSchema};
+pub use crate::ser::{
+    to_string, to_string_pretty, to_writer, Serializer, SerializerConfig, XmlWriter,
+};
+pub use xml::common::XmlVersion;
 pub use xml::reader::{EventReader, ParserConfig};
@@ -0,0 +1,209 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned};
+use serde::ser;
+
+use crate::de::from_str;
+use crate::error::Result;
+
+/// Name of the newtype struct through which `RawXml` is recognised by this crate's `Serializer`
+/// and `Deserializer`.
+pub(crate) const RAW_XML_TOKEN: &str = "$serde_xml_rs::RawXml";
+
+/// An XML element captured as a string, including its attributes, descendants and the namespaces
+/// they use.
+///
+/// When deserialized, the whole element the value is read from is captured. When serialized, the
+/// captured element is written in place of the element the value would otherwise be written to.
+/// This allows subtrees such as vendor extensions to be forwarded without a type for them.
+///
+/// The element is written back from the parsed events rather than copied from the source text, so
+/// the captured string is equivalent to the original but not byte for byte the same: whitespace
+/// trimmed by the parser is gone, and quoting, escaping and empty element tags are normalized.
+/// Content whose exact bytes matter, such as an XML signature over non-canonical input, should
+/// not rely on it.
+///
+/// ```rust
+/// # use serde::{Deserialize, Serialize};
+/// # use serde_xml_rs::{from_str, to_string, RawXml};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct Envelope {
+///     id: u32,
+///     #[serde(rename = "Signature")]
+///     signature: RawXml,
+/// }
+///
+/// # fn main() {
+/// let src = r#"<?xml version="1.0" encoding="UTF-8"?><Envelope><id>1</id><Signature xmlns="urn:sig" alg="rsa"><Value>abc</Value></Signature></Envelope>"#;
+/// let envelope: Envelope = from_str(src).unwrap();
+/// assert_eq!(
+///     envelope.signature.as_str(),
+///     r#"<Signature xmlns="urn:sig" alg="rsa"><Value>abc</Value></Signature>"#
+/// );
+/// assert_eq!(to_string(&envelope).unwrap(), src);
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RawXml(String);
+
+impl RawXml {
+    /// Wraps an XML fragment consisting of a single element. The fragment is not checked until it
+    /// is serialized.
+    pub fn new(xml: impl Into<String>) -> Self {
+        RawXml(xml.into())
+    }
+
+    /// The captured element as an XML string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// Deserializes the captured element into a typed value.
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<T> {
+        from_str(&self.0)
+    }
+}
+
+impl fmt::Display for RawXml {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<String> for RawXml {
+    fn from(xml: String) -> Self {
+        RawXml(xml)
+    }
+}
+
+impl ser::Serialize for RawXml {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(RAW_XML_TOKEN, &self.0)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for RawXml {
+    fn deserialize<D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        struct RawXmlVisitor;
+
+        impl<'de> de::Visitor<'de> for RawXmlVisitor {
+            type Value = RawXml;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an XML element")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<RawXml, E> {
+                Ok(RawXml(v.to_string()))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> std::result::Result<RawXml, E> {
+                Ok(RawXml(v))
+            }
+
+            fn visit_newtype_struct<D: de::Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> std::result::Result<RawXml, D::Error> {
+                de::Deserialize::deserialize(deserializer).map(RawXml)
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(RAW_XML_TOKEN, RawXmlVisitor)
+    }
+}
+
+/// An element whose typed deserialization is deferred until `get` is called.
+///
+/// The element is captured like a `RawXml`, so a `Lazy<T>` that is never looked at is also written
+/// back unchanged when serialized.
+///
+/// ```rust
+/// # use serde::Deserialize;
+/// # use serde_xml_rs::{from_str, Lazy};
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Report {
+///     id: u32,
+///     details: Lazy<Details>,
+/// }
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Details {
+///     size: u64,
+/// }
+///
+/// # fn main() {
+/// let report: Report = from_str("<Report><id>1</id><details><size>42</size></details></Report>").unwrap();
+/// assert_eq!(report.details.get().unwrap(), Details { size: 42 });
+/// # }
+/// ```
+pub struct Lazy<T> {
+    raw: RawXml,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Lazy<T> {
+    /// The captured element.
+    pub fn raw(&self) -> &RawXml {
+        &self.raw
+    }
+
+    pub fn into_raw(self) -> RawXml {
+        self.raw
+    }
+}
+
+impl<T: DeserializeOwned> Lazy<T> {
+    /// Deserializes the captured element. The result is not cached.
+    pub fn get(&self) -> Result<T> {
+        self.raw.parse()
+    }
+}
+
+impl<T> From<RawXml> for Lazy<T> {
+    fn from(raw: RawXml) -> Self {
+        Lazy {
+            raw,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Lazy<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Lazy").field(&self.raw).finish()
+    }
+}
+
+impl<T> Clone for Lazy<T> {
+    fn clone(&self) -> Self {
+        self.raw.clone().into()
+    }
+}
+
+impl<T> PartialEq for Lazy<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl<T> ser::Serialize for Lazy<T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.raw.serialize(serializer)
+    }
+}
+
+impl<'de, T> de::Deserialize<'de> for Lazy<T> {
+    fn deserialize<D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        RawXml::deserialize(deserializer).map(Lazy::from)
+    }
+}
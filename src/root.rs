@@ -0,0 +1,82 @@
+use serde::ser::{self, SerializeMap};
+
+/// Name of the newtype struct through which `Root` is recognised by this crate's `Serializer`.
+pub(crate) const ROOT_TOKEN: &str = "$serde_xml_rs::Root";
+
+/// A value serialized with a given root element name and root attributes. A struct is written
+/// under this name instead of the tag named after its type, and other values are wrapped in it,
+/// as with `Serializer::root_name`. Attributes named `xmlns` or `xmlns:prefix` declare
+/// namespaces.
+///
+/// ```rust
+/// # use serde::Serialize;
+/// # use serde_xml_rs::{to_string, Root};
+/// #[derive(Serialize)]
+/// struct Channel {
+///     id: u32,
+/// }
+///
+/// # fn main() {
+/// let root = Root::new("ImageChannel", Channel { id: 1 })
+///     .attr("version", "2.0")
+///     .attr("xmlns", "http://www.example.com/ver20/XMLSchema");
+/// assert_eq!(
+///     to_string(&root).unwrap(),
+///     r#"<?xml version="1.0" encoding="UTF-8"?><ImageChannel xmlns="http://www.example.com/ver20/XMLSchema" version="2.0"><id>1</id></ImageChannel>"#
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Root<T> {
+    name: String,
+    attributes: Vec<(String, String)>,
+    value: T,
+}
+
+impl<T> Root<T> {
+    pub fn new<N: Into<String>>(name: N, value: T) -> Self {
+        Root {
+            name: name.into(),
+            attributes: Vec::new(),
+            value,
+        }
+    }
+
+    /// Adds an attribute to the root element. Attributes are written in the order they are added,
+    /// before the attributes of the value itself.
+    pub fn attr<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.attributes.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+/// The root element name, its attributes and the value, as a map: `$name`, then `@` keys for the
+/// attributes, then `$value`.
+struct RootParts<'a, T>(&'a Root<T>);
+
+impl<T: ser::Serialize> ser::Serialize for Root<T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(ROOT_TOKEN, &RootParts(self))
+    }
+}
+
+impl<'a, T: ser::Serialize> ser::Serialize for RootParts<'a, T> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let root = self.0;
+        let mut map = serializer.serialize_map(Some(root.attributes.len() + 2))?;
+        map.serialize_entry("$name", &root.name)?;
+        for (name, value) in &root.attributes {
+            map.serialize_entry(&format!("@{}", name), value)?;
+        }
+        map.serialize_entry("$value", &root.value)?;
+        map.end()
+    }
+}
@@ -0,0 +1,187 @@
+use serde::de::DeserializeOwned;
+use xml::writer::{EmitterConfig, EventWriter, XmlEvent};
+
+use self::trace::{TraceDeserializer, Tracer};
+use crate::error::Result;
+
+pub(crate) mod model;
+mod pattern;
+mod sample;
+mod trace;
+mod validate;
+mod xsd;
+
+pub use self::model::XsdSchema;
+pub(crate) use self::validate::Validator;
+
+/// Number of times a type is traced at most, to reach all variants of its enums.
+const MAX_PASSES: usize = 256;
+
+/// The shape of a value, as seen through `Deserialize`.
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    /// Not traced, e.g. the elements of a sequence cut short to stop a recursion.
+    Unknown,
+    /// A value written as text, with its XSD type.
+    Primitive(&'static str),
+    Option(Box<Shape>),
+    Seq(Box<Shape>),
+    Tuple(Vec<Shape>),
+    Map(Box<Shape>),
+    Unit,
+    /// A struct or enum, described in the types of the schema.
+    Named(&'static str),
+    /// A `RawXml` or `Lazy` element.
+    Raw,
+    /// Anything deserialized through `deserialize_any`.
+    Any,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TypeShape {
+    Struct(Vec<(&'static str, Shape)>),
+    /// Variants that haven't been traced yet are `None`.
+    Enum(Vec<(&'static str, Option<VariantShape>)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum VariantShape {
+    Unit,
+    Newtype(Shape),
+    Tuple(Vec<Shape>),
+    Struct(Vec<(&'static str, Shape)>),
+}
+
+/// The XML shape of a type, as traced by `schema_for`.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    root: Shape,
+    /// Structs and enums, in the order they were found.
+    types: Vec<(&'static str, TypeShape)>,
+}
+
+impl Schema {
+    /// An XSD document describing the documents the type is serialized to and deserialized from.
+    pub fn to_xsd(&self) -> Result<String> {
+        xsd::write_xsd(self)
+    }
+
+    /// A sample document matching the schema, with placeholder values. Optional values are
+    /// included, sequences have one item and enums take their first variant.
+    pub fn sample(&self) -> Result<String> {
+        sample::write_sample(self)
+    }
+
+    fn type_shape(&self, name: &str) -> Option<&TypeShape> {
+        self.types
+            .iter()
+            .find(|(type_name, _)| *type_name == name)
+            .map(|(_, shape)| shape)
+    }
+
+    /// Whether a named type is an enum with only unit variants, which is written as text.
+    fn is_simple_enum(&self, name: &str) -> bool {
+        match self.type_shape(name) {
+            Some(TypeShape::Enum(variants)) => variants
+                .iter()
+                .all(|(_, variant)| matches!(variant, Some(VariantShape::Unit) | None)),
+            _ => false,
+        }
+    }
+}
+
+/// Indented output shared by the XSD and sample writers.
+struct XmlOut {
+    writer: EventWriter<Vec<u8>>,
+}
+
+impl XmlOut {
+    fn new() -> Self {
+        XmlOut {
+            writer: EmitterConfig::new()
+                .perform_indent(true)
+                .create_writer(Vec::new()),
+        }
+    }
+
+    fn start(&mut self, name: &str, attrs: &[(&str, &str)]) -> Result<()> {
+        let element = attrs
+            .iter()
+            .fold(XmlEvent::start_element(name), |element, (name, value)| {
+                element.attr(*name, value)
+            });
+        self.writer.write(element)?;
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<()> {
+        self.writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+
+    /// Writes an element without content.
+    fn empty(&mut self, name: &str, attrs: &[(&str, &str)]) -> Result<()> {
+        self.start(name, attrs)?;
+        self.end()
+    }
+
+    fn text(&mut self, text: &str) -> Result<()> {
+        self.writer.write(XmlEvent::characters(text))?;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<String> {
+        Ok(String::from_utf8(self.writer.into_inner())?)
+    }
+}
+
+/// Traces the shape of a type through its `Deserialize` implementation, following the
+/// conventions of this crate: `@` fields are attributes, `$value` is the content of the
+/// element, sequences are repeated elements, enum variants are elements named after them and
+/// `Option` fields may be omitted.
+///
+/// ```rust
+/// # use serde::Deserialize;
+/// # use serde_xml_rs::schema_for;
+/// #[derive(Deserialize)]
+/// struct Order {
+///     #[serde(rename = "@id")]
+///     id: u32,
+///     customer: String,
+///     note: Option<String>,
+///     line: Vec<Line>,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Line {
+///     sku: String,
+///     quantity: u16,
+/// }
+///
+/// # fn main() {
+/// let schema = schema_for::<Order>().unwrap();
+/// let xsd = schema.to_xsd().unwrap();
+/// assert!(xsd.contains(r#"<xs:element name="note" type="xs:string" minOccurs="0" />"#));
+/// assert!(xsd.contains(r#"<xs:attribute name="id" type="xs:unsignedInt" use="required" />"#));
+///
+/// let sample = schema.sample().unwrap();
+/// assert!(sample.contains(r#"<Order id="0">"#));
+/// # }
+/// ```
+pub fn schema_for<T: DeserializeOwned>() -> Result<Schema> {
+    let mut tracer = Tracer::default();
+    let mut root = Shape::Unknown;
+
+    for _ in 0..MAX_PASSES {
+        root = Shape::Unknown;
+        T::deserialize(TraceDeserializer::new(&mut tracer, &mut root))?;
+        if !tracer.has_untraced_variants() {
+            break;
+        }
+    }
+
+    Ok(Schema {
+        root,
+        types: tracer.into_types(),
+    })
+}
@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::str::FromStr;
+
+use xml::name::OwnedName;
+use xml::namespace::Namespace;
+use xml::reader::{EventReader, XmlEvent};
+
+use super::pattern::Pattern;
+use crate::error::{Error, Result};
+
+pub(crate) const XSD_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+
+/// An XSD document loaded for validation, see `Deserializer::validate`.
+///
+/// The supported subset covers global and local `xs:element` declarations (including `ref`),
+/// named and anonymous `xs:complexType` with `xs:sequence`, `xs:choice`, `xs:all`, `xs:any`,
+/// `xs:attribute`, `xs:anyAttribute`, mixed content, and `xs:simpleContent` or
+/// `xs:complexContent` extensions, as well as `xs:simpleType` restrictions with the
+/// `enumeration`, `pattern`, `length`, `minLength`, `maxLength`, `minInclusive`,
+/// `maxInclusive`, `minExclusive` and `maxExclusive` facets, lists and unions. Names are
+/// compared without their namespace. Other constructs, such as groups or imports, are
+/// reported as `Error::InvalidSchema`.
+///
+/// ```rust
+/// # use serde_xml_rs::XsdSchema;
+/// let schema: XsdSchema = r##"
+///     <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+///         <xs:element name="port" type="xs:unsignedShort" />
+///     </xs:schema>
+/// "##.parse().unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct XsdSchema {
+    pub(crate) elements: HashMap<String, ElementDecl>,
+    pub(crate) complex_types: HashMap<String, ComplexType>,
+    pub(crate) simple_types: HashMap<String, SimpleType>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ElementDecl {
+    pub name: String,
+    pub type_ref: TypeRef,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum TypeRef {
+    /// A built-in type of the XSD namespace, by local name.
+    Builtin(String),
+    /// A type declared at the top level of the schema.
+    Named(String),
+    Complex(Box<ComplexType>),
+    Simple(Box<SimpleType>),
+    /// No type given: any content is accepted.
+    AnyType,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ComplexType {
+    pub content: Option<Particle>,
+    pub attributes: Vec<AttributeDecl>,
+    pub any_attribute: bool,
+    pub mixed: bool,
+    /// The type of the text content, for `xs:simpleContent`.
+    pub simple_content: Option<TypeRef>,
+    /// Base type extended through `xs:complexContent`.
+    pub base: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct AttributeDecl {
+    pub name: String,
+    pub type_ref: TypeRef,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Particle {
+    pub term: Term,
+    pub min: usize,
+    /// `None` if unbounded.
+    pub max: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Term {
+    Element(ElementDecl),
+    /// Reference to a global element.
+    ElementRef(String),
+    Sequence(Vec<Particle>),
+    Choice(Vec<Particle>),
+    All(Vec<Particle>),
+    Any,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum SimpleType {
+    Restriction { base: TypeRef, facets: Facets },
+    List(TypeRef),
+    Union(Vec<TypeRef>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Facets {
+    pub enumeration: Vec<String>,
+    pub patterns: Vec<Pattern>,
+    pub length: Option<usize>,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub min_inclusive: Option<String>,
+    pub max_inclusive: Option<String>,
+    pub min_exclusive: Option<String>,
+    pub max_exclusive: Option<String>,
+}
+
+impl XsdSchema {
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        let root = Node::read(EventReader::new(reader))?;
+        if !root.is_xsd("schema") {
+            return Err(invalid(format!(
+                "expected <xs:schema> as root element, found <{}>",
+                root.name.local_name
+            )));
+        }
+
+        let mut schema = XsdSchema::default();
+        for child in root.xsd_children() {
+            match child.name.local_name.as_str() {
+                "element" => {
+                    let element = element_decl(child)?;
+                    schema.elements.insert(element.name.clone(), element);
+                }
+                "complexType" => {
+                    let name = child.required_attr("name")?.to_string();
+                    schema.complex_types.insert(name, complex_type(child)?);
+                }
+                "simpleType" => {
+                    let name = child.required_attr("name")?.to_string();
+                    schema.simple_types.insert(name, simple_type(child)?);
+                }
+                _ => return Err(child.unsupported()),
+            }
+        }
+        Ok(schema)
+    }
+
+    /// The complex type a reference resolves to, including the content and attributes of the
+    /// types it extends.
+    pub(crate) fn resolve_complex(&self, complex: &ComplexType) -> Result<ComplexType> {
+        let mut resolved = complex.clone();
+        let mut base = complex.base.clone();
+        let mut depth = 0;
+        while let Some(name) = base {
+            depth += 1;
+            let base_type = match self.complex_types.get(&name) {
+                Some(base_type) if depth < 64 => base_type,
+                _ => return Err(invalid(format!("unknown base type '{}'", name))),
+            };
+            resolved.content = match (base_type.content.clone(), resolved.content) {
+                (Some(base_content), Some(content)) => Some(Particle {
+                    term: Term::Sequence(vec![base_content, content]),
+                    min: 1,
+                    max: Some(1),
+                }),
+                (base_content, content) => base_content.or(content),
+            };
+            let mut attributes = base_type.attributes.clone();
+            attributes.append(&mut resolved.attributes);
+            resolved.attributes = attributes;
+            resolved.any_attribute |= base_type.any_attribute;
+            resolved.mixed |= base_type.mixed;
+            base = base_type.base.clone();
+        }
+        Ok(resolved)
+    }
+}
+
+impl FromStr for XsdSchema {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        XsdSchema::from_reader(s.as_bytes())
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidSchema { message }
+}
+
+/// An element of the XSD document.
+struct Node {
+    name: OwnedName,
+    attributes: Vec<(String, String)>,
+    namespace: Namespace,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn read<R: Read>(reader: EventReader<R>) -> Result<Node> {
+        let mut stack: Vec<Node> = Vec::new();
+        for event in reader {
+            match event? {
+                XmlEvent::StartElement {
+                    name,
+                    attributes,
+                    namespace,
+                } => stack.push(Node {
+                    name,
+                    attributes: attributes
+                        .into_iter()
+                        .map(|attribute| (attribute.name.local_name, attribute.value))
+                        .collect(),
+                    namespace,
+                    children: Vec::new(),
+                }),
+                XmlEvent::EndElement { .. } => {
+                    let node = stack.pop().expect("end element without start element");
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(node),
+                        None => return Ok(node),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(invalid("empty schema document".to_string()))
+    }
+
+    fn is_xsd(&self, local_name: &str) -> bool {
+        self.name.namespace.as_deref() == Some(XSD_NAMESPACE) && self.name.local_name == local_name
+    }
+
+    /// Children in the XSD namespace, leaving out annotations.
+    fn xsd_children(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter(|child| {
+            child.name.namespace.as_deref() == Some(XSD_NAMESPACE)
+                && child.name.local_name != "annotation"
+        })
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attr, _)| attr == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn required_attr(&self, name: &str) -> Result<&str> {
+        self.attr(name).ok_or_else(|| {
+            invalid(format!(
+                "missing attribute '{}' on <xs:{}>",
+                name, self.name.local_name
+            ))
+        })
+    }
+
+    /// Resolves a qualified name used as the value of an attribute such as `type`.
+    fn type_attr(&self, name: &str) -> Option<TypeRef> {
+        self.attr(name).map(|qname| self.type_ref(qname))
+    }
+
+    fn type_ref(&self, qname: &str) -> TypeRef {
+        let (prefix, local_name) = match qname.split_once(':') {
+            Some((prefix, local_name)) => (Some(prefix), local_name),
+            None => (None, qname),
+        };
+        let namespace = self.namespace.get(prefix.unwrap_or(""));
+        if namespace == Some(XSD_NAMESPACE) {
+            TypeRef::Builtin(local_name.to_string())
+        } else {
+            TypeRef::Named(local_name.to_string())
+        }
+    }
+
+    fn occurs(&self) -> Result<(usize, Option<usize>)> {
+        let parse = |value: &str| {
+            value
+                .parse::<usize>()
+                .map_err(|_| invalid(format!("invalid occurrence '{}'", value)))
+        };
+        let min = self.attr("minOccurs").map_or(Ok(1), parse)?;
+        let max = match self.attr("maxOccurs") {
+            Some("unbounded") => None,
+            Some(max) => Some(parse(max)?),
+            None => Some(1),
+        };
+        Ok((min, max))
+    }
+
+    fn unsupported(&self) -> Error {
+        invalid(format!(
+            "unsupported XSD construct <xs:{}>",
+            self.name.local_name
+        ))
+    }
+}
+
+fn element_decl(node: &Node) -> Result<ElementDecl> {
+    let name = node.required_attr("name")?.to_string();
+    let mut type_ref = node.type_attr("type").unwrap_or(TypeRef::AnyType);
+    for child in node.xsd_children() {
+        type_ref = match child.name.local_name.as_str() {
+            "complexType" => TypeRef::Complex(Box::new(complex_type(child)?)),
+            "simpleType" => TypeRef::Simple(Box::new(simple_type(child)?)),
+            // Identity constraints are not checked
+            "unique" | "key" | "keyref" => continue,
+            _ => return Err(child.unsupported()),
+        };
+    }
+    Ok(ElementDecl { name, type_ref })
+}
+
+fn particle(node: &Node) -> Result<Particle> {
+    let (min, max) = node.occurs()?;
+    let term = match node.name.local_name.as_str() {
+        "element" => match node.attr("ref") {
+            Some(reference) => Term::ElementRef(local_name(reference).to_string()),
+            None => Term::Element(element_decl(node)?),
+        },
+        "sequence" => Term::Sequence(particles(node)?),
+        "choice" => Term::Choice(particles(node)?),
+        "all" => Term::All(particles(node)?),
+        "any" => Term::Any,
+        _ => return Err(node.unsupported()),
+    };
+    Ok(Particle { term, min, max })
+}
+
+fn particles(node: &Node) -> Result<Vec<Particle>> {
+    node.xsd_children().map(particle).collect()
+}
+
+fn local_name(qname: &str) -> &str {
+    qname
+        .split_once(':')
+        .map_or(qname, |(_, local_name)| local_name)
+}
+
+fn complex_type(node: &Node) -> Result<ComplexType> {
+    let mut complex = ComplexType {
+        mixed: node.attr("mixed") == Some("true"),
+        ..ComplexType::default()
+    };
+    complex_content(node, &mut complex)?;
+    Ok(complex)
+}
+
+/// Reads the particle and attributes of a complex type or of the derivation of one.
+fn complex_content(node: &Node, complex: &mut ComplexType) -> Result<()> {
+    for child in node.xsd_children() {
+        match child.name.local_name.as_str() {
+            "sequence" | "choice" | "all" => complex.content = Some(particle(child)?),
+            "attribute" => complex.attributes.push(attribute_decl(child)?),
+            "anyAttribute" => complex.any_attribute = true,
+            "simpleContent" | "complexContent" => {
+                complex.mixed |= child.attr("mixed") == Some("true");
+                let simple = child.name.local_name == "simpleContent";
+                for derivation in child.xsd_children() {
+                    let base = derivation.required_attr("base")?;
+                    match derivation.name.local_name.as_str() {
+                        "extension" | "restriction" if simple => {
+                            complex.simple_content = Some(derivation.type_ref(base));
+                        }
+                        "extension" => complex.base = Some(local_name(base).to_string()),
+                        // A restriction repeats the content it keeps from its base
+                        "restriction" => {}
+                        _ => return Err(derivation.unsupported()),
+                    }
+                    complex_content(derivation, complex)?;
+                }
+            }
+            // Facets of a simple content restriction are not checked
+            _ if complex.simple_content.is_some() => {}
+            _ => return Err(child.unsupported()),
+        }
+    }
+    Ok(())
+}
+
+fn attribute_decl(node: &Node) -> Result<AttributeDecl> {
+    let mut type_ref = node
+        .type_attr("type")
+        .unwrap_or_else(|| TypeRef::Builtin("string".to_string()));
+    for child in node.xsd_children() {
+        match child.name.local_name.as_str() {
+            "simpleType" => type_ref = TypeRef::Simple(Box::new(simple_type(child)?)),
+            _ => return Err(child.unsupported()),
+        }
+    }
+    Ok(AttributeDecl {
+        name: node.required_attr("name")?.to_string(),
+        type_ref,
+        required: node.attr("use") == Some("required"),
+    })
+}
+
+fn simple_type(node: &Node) -> Result<SimpleType> {
+    let derivation = node
+        .xsd_children()
+        .next()
+        .ok_or_else(|| invalid("empty <xs:simpleType>".to_string()))?;
+    // The base or item type is either referenced or declared inline
+    let inline = || -> Result<Option<TypeRef>> {
+        match derivation
+            .xsd_children()
+            .find(|child| child.is_xsd("simpleType"))
+        {
+            Some(inline) => Ok(Some(TypeRef::Simple(Box::new(simple_type(inline)?)))),
+            None => Ok(None),
+        }
+    };
+
+    match derivation.name.local_name.as_str() {
+        "restriction" => {
+            let base = match derivation.type_attr("base") {
+                Some(base) => base,
+                None => inline()?.ok_or_else(|| invalid("restriction without base".into()))?,
+            };
+            Ok(SimpleType::Restriction {
+                base,
+                facets: facets(derivation)?,
+            })
+        }
+        "list" => {
+            let item = match derivation.type_attr("itemType") {
+                Some(item) => item,
+                None => inline()?.ok_or_else(|| invalid("list without item type".into()))?,
+            };
+            Ok(SimpleType::List(item))
+        }
+        "union" => {
+            let mut members: Vec<TypeRef> = derivation
+                .attr("memberTypes")
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(|member| derivation.type_ref(member))
+                .collect();
+            for child in derivation.xsd_children() {
+                members.push(TypeRef::Simple(Box::new(simple_type(child)?)));
+            }
+            Ok(SimpleType::Union(members))
+        }
+        _ => Err(derivation.unsupported()),
+    }
+}
+
+fn facets(node: &Node) -> Result<Facets> {
+    let mut facets = Facets::default();
+    for child in node.xsd_children() {
+        if child.is_xsd("simpleType") {
+            continue;
+        }
+        let value = child.required_attr("value")?.to_string();
+        let length = || {
+            value
+                .parse::<usize>()
+                .map_err(|_| invalid(format!("invalid length '{}'", value)))
+        };
+        match child.name.local_name.as_str() {
+            "enumeration" => facets.enumeration.push(value.clone()),
+            "pattern" => facets.patterns.push(Pattern::new(&value).map_err(invalid)?),
+            "length" => facets.length = Some(length()?),
+            "minLength" => facets.min_length = Some(length()?),
+            "maxLength" => facets.max_length = Some(length()?),
+            "minInclusive" => facets.min_inclusive = Some(value),
+            "maxInclusive" => facets.max_inclusive = Some(value),
+            "minExclusive" => facets.min_exclusive = Some(value),
+            "maxExclusive" => facets.max_exclusive = Some(value),
+            // Not checked
+            "whiteSpace" | "totalDigits" | "fractionDigits" => {}
+            _ => return Err(child.unsupported()),
+        }
+    }
+    Ok(facets)
+}
@@ -0,0 +1,296 @@
+//! The subset of XSD regular expressions used by `xs:pattern` facets: literals, `.`, character
+//! classes with ranges and negation, the `\d`, `\w`, `\s`, `\i` and `\c` escapes and their
+//! negations, groups, alternatives and the `?`, `*`, `+` and `{n,m}` quantifiers. Unicode
+//! categories (`\p{..}`) and class subtraction are not supported.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    root: Alternatives,
+}
+
+type Alternatives = Vec<Branch>;
+type Branch = Vec<Piece>;
+
+#[derive(Debug, Clone)]
+struct Piece {
+    atom: Atom,
+    min: usize,
+    max: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum Atom {
+    Char(char),
+    Class(Class),
+    Group(Alternatives),
+}
+
+#[derive(Debug, Clone)]
+struct Class {
+    negated: bool,
+    items: Vec<ClassItem>,
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Escape(char),
+    Any,
+}
+
+impl Class {
+    fn matches(&self, c: char) -> bool {
+        self.items.iter().any(|item| item.matches(c)) != self.negated
+    }
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            ClassItem::Range(low, high) => low <= c && c <= high,
+            ClassItem::Any => c != '\n' && c != '\r',
+            ClassItem::Escape(escape) => {
+                let matches = match escape.to_ascii_lowercase() {
+                    'd' => c.is_numeric(),
+                    'w' => !(c.is_ascii_punctuation() || c.is_whitespace() || c.is_control()),
+                    's' => matches!(c, ' ' | '\t' | '\n' | '\r'),
+                    'i' => c.is_alphabetic() || c == '_' || c == ':',
+                    _ => c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.'),
+                };
+                matches == escape.is_ascii_lowercase()
+            }
+        }
+    }
+}
+
+impl Pattern {
+    pub fn new(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: source.chars().peekable(),
+        };
+        let root = parser.alternatives()?;
+        match parser.chars.next() {
+            None => Ok(Pattern {
+                source: source.to_string(),
+                root,
+            }),
+            Some(c) => Err(format!("unexpected '{}' in pattern '{}'", c, source)),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the whole value matches the pattern, which is implicitly anchored.
+    pub fn is_match(&self, value: &str) -> bool {
+        let input: Vec<char> = value.chars().collect();
+        alternatives_ends(&self.root, &input, 0).contains(&input.len())
+    }
+}
+
+/// Positions at which a match of the alternatives starting at `start` can end.
+fn alternatives_ends(alternatives: &[Branch], input: &[char], start: usize) -> Vec<usize> {
+    let mut ends: Vec<usize> = alternatives
+        .iter()
+        .flat_map(|branch| branch_ends(branch, input, start))
+        .collect();
+    ends.sort_unstable();
+    ends.dedup();
+    ends
+}
+
+fn branch_ends(branch: &[Piece], input: &[char], start: usize) -> Vec<usize> {
+    branch.iter().fold(vec![start], |positions, piece| {
+        let mut ends: Vec<usize> = positions
+            .into_iter()
+            .flat_map(|position| piece_ends(piece, input, position))
+            .collect();
+        ends.sort_unstable();
+        ends.dedup();
+        ends
+    })
+}
+
+fn piece_ends(piece: &Piece, input: &[char], start: usize) -> Vec<usize> {
+    let mut ends = Vec::new();
+    let mut current = vec![start];
+    let mut count = 0;
+    loop {
+        if count >= piece.min {
+            ends.extend(current.iter().copied());
+        }
+        if piece.max.is_some_and(|max| count >= max) || current.is_empty() {
+            break;
+        }
+        let mut next: Vec<usize> = current
+            .iter()
+            .flat_map(|&position| atom_ends(&piece.atom, input, position))
+            .collect();
+        next.sort_unstable();
+        next.dedup();
+        // Positions already reached with fewer repetitions have been explored further already
+        if count >= piece.min {
+            next.retain(|position| !ends.contains(position));
+        }
+        current = next;
+        count += 1;
+    }
+    ends.sort_unstable();
+    ends.dedup();
+    ends
+}
+
+fn atom_ends(atom: &Atom, input: &[char], start: usize) -> Vec<usize> {
+    match atom {
+        Atom::Char(c) => match input.get(start) {
+            Some(actual) if actual == c => vec![start + 1],
+            _ => Vec::new(),
+        },
+        Atom::Class(class) => match input.get(start) {
+            Some(&actual) if class.matches(actual) => vec![start + 1],
+            _ => Vec::new(),
+        },
+        Atom::Group(alternatives) => alternatives_ends(alternatives, input, start),
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn alternatives(&mut self) -> Result<Alternatives, String> {
+        let mut alternatives = vec![self.branch()?];
+        while self.chars.peek() == Some(&'|') {
+            self.chars.next();
+            alternatives.push(self.branch()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn branch(&mut self) -> Result<Branch, String> {
+        let mut branch = Vec::new();
+        while let Some(&c) = self.chars.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            let (min, max) = self.quantifier()?;
+            branch.push(Piece { atom, min, max });
+        }
+        Ok(branch)
+    }
+
+    fn atom(&mut self) -> Result<Atom, String> {
+        match self.chars.next() {
+            Some('(') => {
+                let alternatives = self.alternatives()?;
+                match self.chars.next() {
+                    Some(')') => Ok(Atom::Group(alternatives)),
+                    _ => Err("unclosed group in pattern".to_string()),
+                }
+            }
+            Some('[') => self.class(),
+            Some('.') => Ok(Atom::Class(Class {
+                negated: false,
+                items: vec![ClassItem::Any],
+            })),
+            Some('\\') => match self.escape()? {
+                ClassItem::Range(c, _) => Ok(Atom::Char(c)),
+                item => Ok(Atom::Class(Class {
+                    negated: false,
+                    items: vec![item],
+                })),
+            },
+            Some(c) if "?*+{".contains(c) => Err(format!("nothing to repeat before '{}'", c)),
+            Some(c) => Ok(Atom::Char(c)),
+            None => Err("unexpected end of pattern".to_string()),
+        }
+    }
+
+    /// Parses the escape following a `\`, as a single character range for literal characters.
+    fn escape(&mut self) -> Result<ClassItem, String> {
+        match self.chars.next() {
+            Some(c) if "dDwWsSiIcC".contains(c) => Ok(ClassItem::Escape(c)),
+            Some('n') => Ok(ClassItem::Range('\n', '\n')),
+            Some('r') => Ok(ClassItem::Range('\r', '\r')),
+            Some('t') => Ok(ClassItem::Range('\t', '\t')),
+            Some(c) if "\\|.-^?*+{}()[]".contains(c) => Ok(ClassItem::Range(c, c)),
+            Some(c) => Err(format!("unsupported escape '\\{}' in pattern", c)),
+            None => Err("unexpected end of pattern".to_string()),
+        }
+    }
+
+    fn class(&mut self) -> Result<Atom, String> {
+        let negated = self.chars.peek() == Some(&'^');
+        if negated {
+            self.chars.next();
+        }
+        let mut items = Vec::new();
+        loop {
+            let low = match self.chars.next() {
+                Some(']') if !items.is_empty() => break,
+                Some('\\') => match self.escape()? {
+                    ClassItem::Range(c, _) => c,
+                    item => {
+                        items.push(item);
+                        continue;
+                    }
+                },
+                Some('[') => return Err("class subtraction is not supported".to_string()),
+                Some(c) => c,
+                None => return Err("unclosed character class in pattern".to_string()),
+            };
+            let mut lookahead = self.chars.clone();
+            if lookahead.next() == Some('-') && !matches!(lookahead.next(), Some(']') | None) {
+                self.chars.next();
+                let high = match self.chars.next() {
+                    Some('\\') => match self.escape()? {
+                        ClassItem::Range(c, _) => c,
+                        _ => return Err("invalid range in character class".to_string()),
+                    },
+                    Some(c) => c,
+                    None => return Err("unclosed character class in pattern".to_string()),
+                };
+                items.push(ClassItem::Range(low, high));
+            } else {
+                items.push(ClassItem::Range(low, low));
+            }
+        }
+        Ok(Atom::Class(Class { negated, items }))
+    }
+
+    fn quantifier(&mut self) -> Result<(usize, Option<usize>), String> {
+        let quantifier = match self.chars.peek() {
+            Some('?') => (0, Some(1)),
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('{') => {
+                self.chars.next();
+                let mut spec = String::new();
+                loop {
+                    match self.chars.next() {
+                        Some('}') => break,
+                        Some(c) => spec.push(c),
+                        None => return Err("unclosed quantifier in pattern".to_string()),
+                    }
+                }
+                let invalid = || format!("invalid quantifier '{{{}}}' in pattern", spec);
+                let parse = |s: &str| s.trim().parse::<usize>().map_err(|_| invalid());
+                return match spec.split_once(',') {
+                    None => parse(&spec).map(|n| (n, Some(n))),
+                    Some((min, "")) => parse(min).map(|min| (min, None)),
+                    Some((min, max)) => Ok((parse(min)?, Some(parse(max)?))),
+                };
+            }
+            _ => return Ok((1, Some(1))),
+        };
+        self.chars.next();
+        Ok(quantifier)
+    }
+}
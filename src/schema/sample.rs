@@ -0,0 +1,159 @@
+use super::{Schema, Shape, TypeShape, VariantShape, XmlOut};
+use crate::error::{Error, Result};
+
+pub fn write_sample(schema: &Schema) -> Result<String> {
+    let mut sample = SampleWriter {
+        schema,
+        out: XmlOut::new(),
+        stack: Vec::new(),
+    };
+    match &schema.root {
+        Shape::Named(name) => match schema.type_shape(name) {
+            Some(TypeShape::Struct(fields)) => sample.element(name, fields)?,
+            Some(TypeShape::Enum(variants)) => sample.first_variant(variants)?,
+            None => {}
+        },
+        _ => {
+            return Err(Error::UnsupportedOperation {
+                operation: "sample for a root that is not a struct or an enum".to_string(),
+            })
+        }
+    }
+    sample.out.finish()
+}
+
+/// Placeholder text for a value of an XSD type.
+fn placeholder(xsd_type: &str) -> &'static str {
+    match xsd_type {
+        "xs:string" => "string",
+        "xs:boolean" => "true",
+        _ => "0",
+    }
+}
+
+struct SampleWriter<'a> {
+    schema: &'a Schema,
+    out: XmlOut,
+    /// Structs being written, to stop at recursions.
+    stack: Vec<&'a str>,
+}
+
+impl<'a> SampleWriter<'a> {
+    /// Text for a value written as text.
+    fn text(&self, shape: &Shape) -> &'a str {
+        match shape {
+            Shape::Primitive(xsd_type) => placeholder(xsd_type),
+            Shape::Option(inner) => self.text(inner),
+            Shape::Named(name) => match self.schema.type_shape(name) {
+                Some(TypeShape::Enum(variants)) => variants.first().map_or("", |(name, _)| name),
+                _ => "",
+            },
+            _ => "",
+        }
+    }
+
+    fn is_text(&self, shape: &Shape) -> bool {
+        match shape {
+            Shape::Primitive(_) => true,
+            Shape::Option(inner) | Shape::Seq(inner) => self.is_text(inner),
+            Shape::Named(name) => self.schema.is_simple_enum(name),
+            _ => false,
+        }
+    }
+
+    /// Writes an element for a struct.
+    fn element(&mut self, name: &str, fields: &'a [(&'static str, Shape)]) -> Result<()> {
+        let attrs: Vec<_> = fields
+            .iter()
+            .filter_map(|(field, shape)| match field.strip_prefix('@') {
+                Some("*") | None => None,
+                Some(attr) => Some((attr, self.text(shape))),
+            })
+            .collect();
+        self.out.start(name, &attrs)?;
+        self.content(fields)?;
+        self.out.end()
+    }
+
+    fn content(&mut self, fields: &'a [(&'static str, Shape)]) -> Result<()> {
+        for (field, shape) in fields {
+            if *field == "$value" {
+                self.value(shape)?;
+            } else if !field.starts_with('@') && !field.starts_with('$') {
+                self.field(field, shape)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn value(&mut self, shape: &'a Shape) -> Result<()> {
+        if self.is_text(shape) {
+            let text = self.text(shape);
+            return self.out.text(text);
+        }
+        match shape {
+            Shape::Option(inner) | Shape::Seq(inner) => self.value(inner),
+            Shape::Named(name) => match self.schema.type_shape(name) {
+                Some(TypeShape::Enum(variants)) => self.first_variant(variants),
+                _ => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn field(&mut self, name: &str, shape: &'a Shape) -> Result<()> {
+        // A field named with a path holds its items inside a wrapper element for each name
+        if let Some((wrapper, items)) = name.split_once('/') {
+            self.out.start(wrapper, &[])?;
+            self.field(items, shape)?;
+            return self.out.end();
+        }
+        match shape {
+            Shape::Option(inner) | Shape::Seq(inner) => self.field(name, inner),
+            Shape::Primitive(_) => {
+                let text = self.text(shape);
+                self.out.start(name, &[])?;
+                self.out.text(text)?;
+                self.out.end()
+            }
+            Shape::Named(type_name) => match self.schema.type_shape(type_name) {
+                // Recursive structs are only reachable through options and sequences
+                Some(TypeShape::Struct(_)) if self.stack.contains(type_name) => Ok(()),
+                Some(TypeShape::Struct(fields)) => {
+                    self.stack.push(type_name);
+                    self.element(name, fields)?;
+                    self.stack.pop();
+                    Ok(())
+                }
+                Some(TypeShape::Enum(_)) if self.schema.is_simple_enum(type_name) => {
+                    let text = self.text(shape);
+                    self.out.start(name, &[])?;
+                    self.out.text(text)?;
+                    self.out.end()
+                }
+                Some(TypeShape::Enum(variants)) => {
+                    self.out.start(name, &[])?;
+                    self.first_variant(variants)?;
+                    self.out.end()
+                }
+                None => self.out.empty(name, &[]),
+            },
+            _ => self.out.empty(name, &[]),
+        }
+    }
+
+    fn first_variant(
+        &mut self,
+        variants: &'a [(&'static str, Option<VariantShape>)],
+    ) -> Result<()> {
+        let variant = variants
+            .iter()
+            .find(|(name, _)| *name != "$comment" && *name != "$pi");
+        match variant {
+            Some((name, Some(VariantShape::Newtype(shape)))) => self.field(name, shape),
+            Some((name, Some(VariantShape::Struct(fields)))) => self.element(name, fields),
+            Some((name, _)) => self.out.empty(name, &[]),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use serde::de::{self, IntoDeserializer, Visitor};
+
+use super::{Shape, TypeShape, VariantShape};
+use crate::error::{Error, Result};
+use crate::raw::RAW_XML_TOKEN;
+
+/// Nesting of structs and enums beyond which a type is considered infinitely recursive.
+const MAX_DEPTH: usize = 128;
+
+/// State shared by the `TraceDeserializer`s of all passes over a type.
+#[derive(Default)]
+pub struct Tracer {
+    types: Vec<(&'static str, TypeShape)>,
+    /// Structs and enums being traced, from the outermost.
+    stack: Vec<&'static str>,
+    /// Whether values are produced without recording their shape, below a type that recurses
+    /// into itself. Options are then `None` and sequences empty, to end the recursion.
+    minimal: usize,
+}
+
+impl Tracer {
+    pub fn has_untraced_variants(&self) -> bool {
+        self.types.iter().any(|(_, shape)| match shape {
+            TypeShape::Enum(variants) => variants.iter().any(|(_, variant)| variant.is_none()),
+            TypeShape::Struct(_) => false,
+        })
+    }
+
+    pub fn into_types(self) -> Vec<(&'static str, TypeShape)> {
+        self.types
+    }
+
+    fn type_shape_mut(&mut self, name: &str) -> Option<&mut TypeShape> {
+        self.types
+            .iter_mut()
+            .find(|(type_name, _)| *type_name == name)
+            .map(|(_, shape)| shape)
+    }
+
+    fn set_type_shape(&mut self, name: &'static str, shape: TypeShape) {
+        match self.type_shape_mut(name) {
+            Some(existing) => *existing = shape,
+            None => self.types.push((name, shape)),
+        }
+    }
+
+    /// Whether a struct or enum is traced without recording it: below a recursion, or when it
+    /// recurses into itself.
+    fn is_minimal(&self, name: &str) -> bool {
+        self.minimal > 0 || self.stack.contains(&name)
+    }
+
+    fn enter(&mut self, name: &'static str) -> Result<()> {
+        if self.stack.len() >= MAX_DEPTH {
+            return Err(Error::Custom {
+                field: format!("type {} is infinitely recursive", name),
+            });
+        }
+        self.stack.push(name);
+        Ok(())
+    }
+
+    /// The variant to trace: the first one not traced yet, or below a recursion one that is
+    /// known to end it.
+    fn choose_variant(&mut self, name: &'static str, variants: &'static [&'static str]) -> usize {
+        let minimal = self.is_minimal(name);
+        let traced = match self.type_shape_mut(name) {
+            Some(TypeShape::Enum(traced)) => traced,
+            _ if minimal => return variants.len().saturating_sub(1),
+            _ => {
+                let untraced = variants.iter().map(|variant| (*variant, None)).collect();
+                self.types.push((name, TypeShape::Enum(untraced)));
+                return 0;
+            }
+        };
+        if minimal {
+            traced
+                .iter()
+                .position(|(_, variant)| matches!(variant, Some(VariantShape::Unit)))
+                .or_else(|| traced.iter().position(|(_, variant)| variant.is_some()))
+                .unwrap_or(variants.len().saturating_sub(1))
+        } else {
+            traced
+                .iter()
+                .position(|(_, variant)| variant.is_none())
+                .unwrap_or(0)
+        }
+    }
+}
+
+/// Records the shape of the value it deserializes into `shape`, producing a placeholder value.
+pub struct TraceDeserializer<'a> {
+    tracer: &'a mut Tracer,
+    shape: &'a mut Shape,
+}
+
+impl<'a> TraceDeserializer<'a> {
+    pub fn new(tracer: &'a mut Tracer, shape: &'a mut Shape) -> Self {
+        TraceDeserializer { tracer, shape }
+    }
+}
+
+macro_rules! trace_primitive {
+    ($deserialize:ident => $visit:ident($value:expr), $xsd:expr) => {
+        fn $deserialize<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            *self.shape = Shape::Primitive($xsd);
+            visitor.$visit($value)
+        }
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for TraceDeserializer<'a> {
+    type Error = Error;
+
+    trace_primitive!(deserialize_bool => visit_bool(false), "xs:boolean");
+    trace_primitive!(deserialize_i8 => visit_i8(0), "xs:byte");
+    trace_primitive!(deserialize_i16 => visit_i16(0), "xs:short");
+    trace_primitive!(deserialize_i32 => visit_i32(0), "xs:int");
+    trace_primitive!(deserialize_i64 => visit_i64(0), "xs:long");
+    trace_primitive!(deserialize_i128 => visit_i128(0), "xs:integer");
+    trace_primitive!(deserialize_u8 => visit_u8(0), "xs:unsignedByte");
+    trace_primitive!(deserialize_u16 => visit_u16(0), "xs:unsignedShort");
+    trace_primitive!(deserialize_u32 => visit_u32(0), "xs:unsignedInt");
+    trace_primitive!(deserialize_u64 => visit_u64(0), "xs:unsignedLong");
+    trace_primitive!(deserialize_u128 => visit_u128(0), "xs:nonNegativeInteger");
+    trace_primitive!(deserialize_f32 => visit_f32(0.0), "xs:float");
+    trace_primitive!(deserialize_f64 => visit_f64(0.0), "xs:double");
+    trace_primitive!(deserialize_char => visit_char('a'), "xs:string");
+    trace_primitive!(deserialize_str => visit_str(""), "xs:string");
+    trace_primitive!(deserialize_string => visit_string(String::new()), "xs:string");
+    trace_primitive!(deserialize_bytes => visit_bytes(&[]), "xs:string");
+    trace_primitive!(deserialize_byte_buf => visit_byte_buf(Vec::new()), "xs:string");
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        *self.shape = Shape::Any;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.tracer.minimal > 0 {
+            *self.shape = Shape::Option(Box::new(Shape::Unknown));
+            return visitor.visit_none();
+        }
+        let mut inner = Shape::Unknown;
+        let value = visitor.visit_some(TraceDeserializer::new(self.tracer, &mut inner))?;
+        *self.shape = Shape::Option(Box::new(inner));
+        Ok(value)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        *self.shape = Shape::Unit;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        if name == RAW_XML_TOKEN {
+            *self.shape = Shape::Raw;
+            return visitor.visit_string(String::new());
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut element = Shape::Unknown;
+        let len = if self.tracer.minimal > 0 { 0 } else { 1 };
+        let value = visitor.visit_seq(TraceSeq {
+            tracer: self.tracer,
+            shapes: std::slice::from_mut(&mut element),
+            len,
+            index: 0,
+        })?;
+        *self.shape = Shape::Seq(Box::new(element));
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        let mut elements = vec![Shape::Unknown; len];
+        let value = visitor.visit_seq(TraceSeq {
+            tracer: self.tracer,
+            shapes: &mut elements,
+            len,
+            index: 0,
+        })?;
+        *self.shape = Shape::Tuple(elements);
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut shapes = [Shape::Unknown, Shape::Unknown];
+        let len = if self.tracer.minimal > 0 { 0 } else { 1 };
+        let value = visitor.visit_map(TraceMap {
+            tracer: self.tracer,
+            shapes: &mut shapes,
+            len,
+        })?;
+        let [_, value_shape] = shapes;
+        *self.shape = Shape::Map(Box::new(value_shape));
+        Ok(value)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        *self.shape = Shape::Named(name);
+        let minimal = self.tracer.is_minimal(name);
+        let mut shapes = vec![Shape::Unknown; fields.len()];
+
+        self.tracer.enter(name)?;
+        self.tracer.minimal += minimal as usize;
+        let value = visitor.visit_map(TraceStruct {
+            tracer: self.tracer,
+            fields,
+            shapes: &mut shapes,
+            index: 0,
+        });
+        self.tracer.minimal -= minimal as usize;
+        self.tracer.stack.pop();
+
+        if !minimal {
+            self.tracer
+                .set_type_shape(name, TypeShape::Struct(field_shapes(fields, shapes)));
+        }
+        value
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        *self.shape = Shape::Named(name);
+        let minimal = self.tracer.is_minimal(name);
+        let index = self.tracer.choose_variant(name, variants);
+        let mut variant = None;
+
+        self.tracer.enter(name)?;
+        self.tracer.minimal += minimal as usize;
+        let value = visitor.visit_enum(TraceEnum {
+            tracer: self.tracer,
+            name: variants.get(index).copied().unwrap_or_default(),
+            shape: &mut variant,
+        });
+        self.tracer.minimal -= minimal as usize;
+        self.tracer.stack.pop();
+
+        if !minimal {
+            if let Some(TypeShape::Enum(traced)) = self.tracer.type_shape_mut(name) {
+                traced[index].1 = variant;
+            }
+        }
+        value
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_str("")
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+}
+
+struct TraceSeq<'a> {
+    tracer: &'a mut Tracer,
+    shapes: &'a mut [Shape],
+    len: usize,
+    index: usize,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for TraceSeq<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>> {
+        if self.index == self.len {
+            return Ok(None);
+        }
+        let shape = &mut self.shapes[self.index.min(self.shapes.len() - 1)];
+        self.index += 1;
+        seed.deserialize(TraceDeserializer::new(self.tracer, shape))
+            .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.index)
+    }
+}
+
+/// Traces a map with a single entry, recording the shapes of its key and value.
+struct TraceMap<'a> {
+    tracer: &'a mut Tracer,
+    shapes: &'a mut [Shape; 2],
+    len: usize,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for TraceMap<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        seed.deserialize(TraceDeserializer::new(self.tracer, &mut self.shapes[0]))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        seed.deserialize(TraceDeserializer::new(self.tracer, &mut self.shapes[1]))
+    }
+}
+
+/// Whether a field is the alias used to deserialize an attribute, as in
+/// `#[serde(rename = "@id", alias = "id")]`.
+fn is_attribute_alias(fields: &[&str], field: &str) -> bool {
+    fields
+        .iter()
+        .any(|other| other.strip_prefix('@') == Some(field))
+}
+
+/// Fields of a struct along with their shapes, leaving out the aliases of attributes.
+fn field_shapes(fields: &'static [&'static str], shapes: Vec<Shape>) -> Vec<(&'static str, Shape)> {
+    fields
+        .iter()
+        .copied()
+        .zip(shapes)
+        .filter(|(field, _)| !is_attribute_alias(fields, field))
+        .collect()
+}
+
+/// Traces the fields of a struct or struct variant, in order.
+struct TraceStruct<'a> {
+    tracer: &'a mut Tracer,
+    fields: &'static [&'static str],
+    shapes: &'a mut [Shape],
+    index: usize,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for TraceStruct<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        while self
+            .fields
+            .get(self.index)
+            .is_some_and(|field| is_attribute_alias(self.fields, field))
+        {
+            self.index += 1;
+        }
+        match self.fields.get(self.index) {
+            Some(field) => seed.deserialize(field.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let shape = &mut self.shapes[self.index];
+        self.index += 1;
+        seed.deserialize(TraceDeserializer::new(self.tracer, shape))
+    }
+}
+
+struct TraceEnum<'a> {
+    tracer: &'a mut Tracer,
+    name: &'static str,
+    shape: &'a mut Option<VariantShape>,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for TraceEnum<'a> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+        let variant = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(self.name))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for TraceEnum<'a> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        *self.shape = Some(VariantShape::Unit);
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        let mut inner = Shape::Unknown;
+        let value = seed.deserialize(TraceDeserializer::new(self.tracer, &mut inner))?;
+        *self.shape = Some(VariantShape::Newtype(inner));
+        Ok(value)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        let mut elements = vec![Shape::Unknown; len];
+        let value = visitor.visit_seq(TraceSeq {
+            tracer: self.tracer,
+            shapes: &mut elements,
+            len,
+            index: 0,
+        })?;
+        *self.shape = Some(VariantShape::Tuple(elements));
+        Ok(value)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let mut shapes = vec![Shape::Unknown; fields.len()];
+        let value = visitor.visit_map(TraceStruct {
+            tracer: self.tracer,
+            fields,
+            shapes: &mut shapes,
+            index: 0,
+        })?;
+        *self.shape = Some(VariantShape::Struct(field_shapes(fields, shapes)));
+        Ok(value)
+    }
+}
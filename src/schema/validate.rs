@@ -0,0 +1,537 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use xml::attribute::OwnedAttribute;
+use xml::reader::XmlEvent;
+
+use super::model::{ComplexType, ElementDecl, Facets, Particle, SimpleType, Term, TypeRef};
+use super::XsdSchema;
+use crate::error::{Error, Result};
+
+/// Checks the events read from the document against an `XsdSchema`, as they are read.
+pub struct Validator {
+    schema: XsdSchema,
+    stack: Vec<Frame>,
+}
+
+/// An open element of the document.
+struct Frame {
+    path: String,
+    content: Content,
+    /// Names of the child elements read so far.
+    children: Vec<String>,
+    counts: HashMap<String, usize>,
+    text: String,
+}
+
+enum Content {
+    Complex(ComplexType),
+    Simple(TypeRef),
+    /// Not validated, for `xs:any` and untyped elements.
+    Skip,
+}
+
+impl Validator {
+    pub fn new(schema: XsdSchema) -> Self {
+        Validator {
+            schema,
+            stack: Vec::new(),
+        }
+    }
+
+    pub fn check(&mut self, event: &XmlEvent) -> Result<()> {
+        match event {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => self.start_element(&name.local_name, attributes),
+            XmlEvent::EndElement { .. } => self.end_element(),
+            XmlEvent::Characters(text) | XmlEvent::CData(text) => self.text(text),
+            _ => Ok(()),
+        }
+    }
+
+    fn start_element(&mut self, name: &str, attributes: &[OwnedAttribute]) -> Result<()> {
+        let (path, declaration) = match self.stack.last_mut() {
+            None => {
+                let path = format!("/{}", name);
+                match self.schema.elements.get(name) {
+                    Some(declaration) => (path, Some(declaration.type_ref.clone())),
+                    None => return Err(violation(path, "element is not declared in the schema")),
+                }
+            }
+            Some(parent) => {
+                let count = parent.counts.entry(name.to_string()).or_insert(0);
+                *count += 1;
+                let path = match *count {
+                    1 => format!("{}/{}", parent.path, name),
+                    count => format!("{}/{}[{}]", parent.path, name, count),
+                };
+                parent.children.push(name.to_string());
+                match &parent.content {
+                    Content::Complex(complex) => match &complex.content {
+                        Some(particle) => match find_element(&self.schema, particle, name) {
+                            Found::Element(declaration) => (path, Some(declaration.type_ref)),
+                            Found::Any => (path, None),
+                            Found::None => return Err(violation(path, "unexpected element")),
+                        },
+                        None => return Err(violation(path, "unexpected element")),
+                    },
+                    Content::Simple(_) => {
+                        return Err(violation(path, "unexpected element in simple content"))
+                    }
+                    Content::Skip => (path, None),
+                }
+            }
+        };
+
+        let content = match declaration {
+            Some(type_ref) => self.content(&path, type_ref)?,
+            None => Content::Skip,
+        };
+        if let Content::Complex(complex) = &content {
+            self.check_attributes(&path, complex, attributes)?;
+        } else if let Content::Simple(_) = content {
+            if let Some(attribute) = attributes.iter().find(|a| !is_reserved(a)) {
+                return Err(violation(
+                    format!("{}/@{}", path, attribute.name.local_name),
+                    "unexpected attribute",
+                ));
+            }
+        }
+
+        self.stack.push(Frame {
+            path,
+            content,
+            children: Vec::new(),
+            counts: HashMap::new(),
+            text: String::new(),
+        });
+        Ok(())
+    }
+
+    fn content(&self, path: &str, type_ref: TypeRef) -> Result<Content> {
+        match type_ref {
+            TypeRef::Complex(complex) => {
+                Ok(Content::Complex(self.schema.resolve_complex(&complex)?))
+            }
+            TypeRef::Named(name) => match self.schema.complex_types.get(&name) {
+                Some(complex) => Ok(Content::Complex(self.schema.resolve_complex(complex)?)),
+                None if self.schema.simple_types.contains_key(&name) => {
+                    Ok(Content::Simple(TypeRef::Named(name)))
+                }
+                None => Err(violation(
+                    path.to_string(),
+                    format!("type '{}' is not declared in the schema", name),
+                )),
+            },
+            TypeRef::Builtin(name) if name == "anyType" => Ok(Content::Skip),
+            TypeRef::AnyType => Ok(Content::Skip),
+            simple => Ok(Content::Simple(simple)),
+        }
+    }
+
+    fn check_attributes(
+        &self,
+        path: &str,
+        complex: &ComplexType,
+        attributes: &[OwnedAttribute],
+    ) -> Result<()> {
+        for attribute in attributes.iter().filter(|a| !is_reserved(a)) {
+            let name = &attribute.name.local_name;
+            let attribute_path = format!("{}/@{}", path, name);
+            match complex.attributes.iter().find(|decl| decl.name == *name) {
+                Some(decl) => check_simple(&self.schema, &decl.type_ref, &attribute.value)
+                    .map_err(|message| violation(attribute_path, message))?,
+                None if complex.any_attribute => {}
+                None => return Err(violation(attribute_path, "unexpected attribute")),
+            }
+        }
+        for decl in complex.attributes.iter().filter(|decl| decl.required) {
+            if !attributes.iter().any(|a| a.name.local_name == decl.name) {
+                return Err(violation(
+                    format!("{}/@{}", path, decl.name),
+                    "missing required attribute",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, text: &str) -> Result<()> {
+        match self.stack.last_mut() {
+            Some(frame) => {
+                if let Content::Complex(complex) = &frame.content {
+                    if complex.simple_content.is_none() && !complex.mixed && !text.trim().is_empty()
+                    {
+                        return Err(violation(frame.path.clone(), "unexpected text content"));
+                    }
+                }
+                frame.text.push_str(text);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn end_element(&mut self) -> Result<()> {
+        let frame = match self.stack.pop() {
+            Some(frame) => frame,
+            None => return Ok(()),
+        };
+        match &frame.content {
+            Content::Simple(type_ref) => check_simple(&self.schema, type_ref, &frame.text)
+                .map_err(|message| violation(frame.path, message)),
+            Content::Complex(complex) => {
+                if let Some(type_ref) = &complex.simple_content {
+                    return check_simple(&self.schema, type_ref, &frame.text)
+                        .map_err(|message| violation(frame.path, message));
+                }
+                let children = &frame.children;
+                let ends = match &complex.content {
+                    Some(particle) => particle_ends(&self.schema, particle, children, 0),
+                    None => vec![0],
+                };
+                if ends.contains(&children.len()) {
+                    return Ok(());
+                }
+                // Report the first element that could not be matched, if any
+                let matched = ends.into_iter().max().unwrap_or(0);
+                let message = match children.get(matched) {
+                    Some(child) => format!("unexpected element <{}>", child),
+                    None => match children.last() {
+                        Some(last) => format!("missing elements after <{}>", last),
+                        None => "missing child elements".to_string(),
+                    },
+                };
+                Err(violation(frame.path, message))
+            }
+            Content::Skip => Ok(()),
+        }
+    }
+}
+
+fn violation<M: Into<String>>(path: String, message: M) -> Error {
+    Error::Validation {
+        path,
+        message: message.into(),
+    }
+}
+
+/// Namespace declarations and `xsi:` or `xml:` attributes are not declared in schemas.
+fn is_reserved(attribute: &OwnedAttribute) -> bool {
+    match attribute.name.prefix.as_deref() {
+        Some("xmlns") | Some("xml") | Some("xsi") => true,
+        _ => attribute.name.local_name == "xmlns",
+    }
+}
+
+enum Found {
+    Element(ElementDecl),
+    Any,
+    None,
+}
+
+/// Finds the declaration of a child element in a content model. Elements with the same name
+/// in a content model must have the same type, so the first one is as good as any.
+fn find_element(schema: &XsdSchema, particle: &Particle, name: &str) -> Found {
+    match &particle.term {
+        Term::Element(decl) if decl.name == name => Found::Element(decl.clone()),
+        Term::ElementRef(reference) if reference == name => match schema.elements.get(name) {
+            Some(decl) => Found::Element(decl.clone()),
+            None => Found::None,
+        },
+        Term::Element(_) | Term::ElementRef(_) => Found::None,
+        Term::Any => Found::Any,
+        Term::Sequence(particles) | Term::Choice(particles) | Term::All(particles) => {
+            let mut found = Found::None;
+            for particle in particles {
+                match find_element(schema, particle, name) {
+                    Found::Element(decl) => return Found::Element(decl),
+                    Found::Any => found = Found::Any,
+                    Found::None => {}
+                }
+            }
+            found
+        }
+    }
+}
+
+/// Positions in `children` at which a match of the particle starting at `start` can end.
+fn particle_ends(
+    schema: &XsdSchema,
+    particle: &Particle,
+    children: &[String],
+    start: usize,
+) -> Vec<usize> {
+    let mut ends = Vec::new();
+    let mut current = vec![start];
+    let mut count = 0;
+    loop {
+        if count >= particle.min {
+            ends.extend(current.iter().copied());
+        }
+        if particle.max.is_some_and(|max| count >= max) || current.is_empty() {
+            break;
+        }
+        let mut next: Vec<usize> = current
+            .iter()
+            .flat_map(|&position| term_ends(schema, &particle.term, children, position))
+            .collect();
+        next.sort_unstable();
+        next.dedup();
+        if count >= particle.min {
+            next.retain(|position| !ends.contains(position));
+        }
+        current = next;
+        count += 1;
+    }
+    ends.sort_unstable();
+    ends.dedup();
+    ends
+}
+
+fn term_ends(schema: &XsdSchema, term: &Term, children: &[String], start: usize) -> Vec<usize> {
+    let child = children.get(start);
+    match term {
+        Term::Element(decl) => match child {
+            Some(child) if *child == decl.name => vec![start + 1],
+            _ => Vec::new(),
+        },
+        Term::ElementRef(name) => match child {
+            Some(child) if child == name => vec![start + 1],
+            _ => Vec::new(),
+        },
+        Term::Any => match child {
+            Some(_) => vec![start + 1],
+            None => Vec::new(),
+        },
+        Term::Sequence(particles) => particles.iter().fold(vec![start], |positions, particle| {
+            let mut ends: Vec<usize> = positions
+                .into_iter()
+                .flat_map(|position| particle_ends(schema, particle, children, position))
+                .collect();
+            ends.sort_unstable();
+            ends.dedup();
+            ends
+        }),
+        Term::Choice(particles) => {
+            let mut ends: Vec<usize> = particles
+                .iter()
+                .flat_map(|particle| particle_ends(schema, particle, children, start))
+                .collect();
+            ends.sort_unstable();
+            ends.dedup();
+            ends
+        }
+        Term::All(particles) => {
+            // Each particle of an `xs:all` appears at most once, in any order
+            let mut used = vec![false; particles.len()];
+            let mut position = start;
+            while position < children.len() {
+                let index = particles.iter().enumerate().position(|(index, particle)| {
+                    !used[index]
+                        && term_ends(schema, &particle.term, children, position)
+                            .contains(&(position + 1))
+                });
+                match index {
+                    Some(index) => used[index] = true,
+                    None => break,
+                }
+                position += 1;
+            }
+            let complete = particles
+                .iter()
+                .zip(used)
+                .all(|(particle, used)| used || particle.min == 0);
+            if complete {
+                vec![position]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Checks a value against a simple type, returning a description of the violation.
+fn check_simple(
+    schema: &XsdSchema,
+    type_ref: &TypeRef,
+    value: &str,
+) -> std::result::Result<(), String> {
+    match type_ref {
+        TypeRef::Builtin(name) => check_builtin(name, value),
+        TypeRef::Named(name) => match schema.simple_types.get(name) {
+            Some(simple) => check_simple_type(schema, simple, value),
+            None => Err(format!(
+                "type '{}' is not a simple type of the schema",
+                name
+            )),
+        },
+        TypeRef::Simple(simple) => check_simple_type(schema, simple, value),
+        TypeRef::Complex(_) => Err("expected a simple type".to_string()),
+        TypeRef::AnyType => Ok(()),
+    }
+}
+
+fn check_simple_type(
+    schema: &XsdSchema,
+    simple: &SimpleType,
+    value: &str,
+) -> std::result::Result<(), String> {
+    match simple {
+        SimpleType::Restriction { base, facets } => {
+            check_simple(schema, base, value)?;
+            check_facets(facets, value)
+        }
+        SimpleType::List(item) => value
+            .split_whitespace()
+            .try_for_each(|item_value| check_simple(schema, item, item_value)),
+        SimpleType::Union(members) => {
+            if members
+                .iter()
+                .any(|member| check_simple(schema, member, value).is_ok())
+            {
+                Ok(())
+            } else {
+                Err(format!(
+                    "'{}' does not match any member of the union",
+                    value
+                ))
+            }
+        }
+    }
+}
+
+fn check_facets(facets: &Facets, value: &str) -> std::result::Result<(), String> {
+    if !facets.enumeration.is_empty() && !facets.enumeration.iter().any(|allowed| allowed == value)
+    {
+        return Err(format!(
+            "'{}' is not one of {}",
+            value,
+            facets.enumeration.join(", ")
+        ));
+    }
+    if !facets.patterns.is_empty()
+        && !facets
+            .patterns
+            .iter()
+            .any(|pattern| pattern.is_match(value))
+    {
+        return Err(format!(
+            "'{}' does not match the pattern '{}'",
+            value,
+            facets.patterns[0].source()
+        ));
+    }
+
+    let length = value.chars().count();
+    let length_checks = [
+        (
+            facets.length,
+            length == facets.length.unwrap_or(length),
+            "exactly",
+        ),
+        (
+            facets.min_length,
+            length >= facets.min_length.unwrap_or(0),
+            "at least",
+        ),
+        (
+            facets.max_length,
+            length <= facets.max_length.unwrap_or(length),
+            "at most",
+        ),
+    ];
+    for (limit, ok, bound) in length_checks.iter() {
+        if let (Some(limit), false) = (limit, ok) {
+            return Err(format!(
+                "'{}' must be {} {} characters long",
+                value, bound, limit
+            ));
+        }
+    }
+
+    // Each bound along with the orderings of the value relative to it that satisfy it
+    let bound_checks = [
+        (
+            &facets.min_inclusive,
+            "at least",
+            [Ordering::Greater, Ordering::Equal],
+        ),
+        (
+            &facets.max_inclusive,
+            "at most",
+            [Ordering::Less, Ordering::Equal],
+        ),
+        (&facets.min_exclusive, "more than", [Ordering::Greater; 2]),
+        (&facets.max_exclusive, "less than", [Ordering::Less; 2]),
+    ];
+    for (limit, bound, allowed) in bound_checks.iter() {
+        if let Some(limit) = limit {
+            match compare(value, limit) {
+                Some(ordering) if allowed.contains(&ordering) => {}
+                _ => return Err(format!("'{}' must be {} {}", value, bound, limit)),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Compares numbers by value, and other values such as dates by their text.
+fn compare(value: &str, limit: &str) -> Option<Ordering> {
+    match (value.trim().parse::<f64>(), limit.trim().parse::<f64>()) {
+        (Ok(value), Ok(limit)) => value.partial_cmp(&limit),
+        (Err(_), Err(_)) => Some(value.trim().cmp(limit.trim())),
+        _ => None,
+    }
+}
+
+fn check_builtin(name: &str, value: &str) -> std::result::Result<(), String> {
+    let value = value.trim();
+    let integer_range = match name {
+        "byte" => Some((i8::MIN as i128, i8::MAX as i128)),
+        "short" => Some((i16::MIN as i128, i16::MAX as i128)),
+        "int" => Some((i32::MIN as i128, i32::MAX as i128)),
+        "long" => Some((i64::MIN as i128, i64::MAX as i128)),
+        "unsignedByte" => Some((0, u8::MAX as i128)),
+        "unsignedShort" => Some((0, u16::MAX as i128)),
+        "unsignedInt" => Some((0, u32::MAX as i128)),
+        "unsignedLong" => Some((0, u64::MAX as i128)),
+        "integer" => Some((i128::MIN, i128::MAX)),
+        "nonNegativeInteger" => Some((0, i128::MAX)),
+        "positiveInteger" => Some((1, i128::MAX)),
+        "nonPositiveInteger" => Some((i128::MIN, 0)),
+        "negativeInteger" => Some((i128::MIN, -1)),
+        _ => None,
+    };
+    let valid = match (name, integer_range) {
+        (_, Some((min, max))) => value
+            .trim_start_matches('+')
+            .parse::<i128>()
+            .is_ok_and(|number| min <= number && number <= max),
+        ("boolean", _) => matches!(value, "true" | "false" | "1" | "0"),
+        ("decimal", _) => {
+            let digits = value.trim_start_matches(['+', '-'].as_ref());
+            let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+            !(integer.is_empty() && fraction.is_empty())
+                && digits.len() + 1 >= value.len()
+                && integer
+                    .chars()
+                    .chain(fraction.chars())
+                    .all(|c| c.is_ascii_digit())
+        }
+        ("float", _) | ("double", _) => {
+            matches!(value, "INF" | "-INF" | "NaN")
+                || (value.parse::<f64>().is_ok()
+                    && value
+                        .chars()
+                        .all(|c| c.is_ascii_digit() || "+-.eE".contains(c)))
+        }
+        _ => true,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a valid xs:{}", value, name))
+    }
+}
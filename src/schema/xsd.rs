@@ -0,0 +1,342 @@
+use xml::writer::XmlEvent;
+
+use super::{Schema, Shape, TypeShape, VariantShape, XmlOut};
+use crate::error::{Error, Result};
+
+const XSD_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+
+pub fn write_xsd(schema: &Schema) -> Result<String> {
+    let mut xsd = XsdWriter {
+        schema,
+        out: XmlOut::new(),
+    };
+    xsd.write()?;
+    xsd.out.finish()
+}
+
+struct XsdWriter<'a> {
+    schema: &'a Schema,
+    out: XmlOut,
+}
+
+/// Strips `Option` and sequences from a shape, returning the shape of the items along with
+/// their `minOccurs` and `maxOccurs`, if not 1.
+fn occurs(mut shape: &Shape) -> (&Shape, Option<&'static str>, Option<&'static str>) {
+    let (mut min, mut max) = (None, None);
+    loop {
+        match shape {
+            Shape::Option(inner) => {
+                min = Some("0");
+                shape = inner;
+            }
+            Shape::Seq(inner) => {
+                min = Some("0");
+                max = Some("unbounded");
+                shape = inner;
+            }
+            _ => return (shape, min, max),
+        }
+    }
+}
+
+/// Shape of the items of an optional sequence.
+fn seq_item(shape: &Shape) -> &Shape {
+    match shape {
+        Shape::Option(inner) => seq_item(inner),
+        Shape::Seq(item) => item,
+        _ => shape,
+    }
+}
+
+impl<'a> XsdWriter<'a> {
+    fn write(&mut self) -> Result<()> {
+        self.out.writer.write(
+            XmlEvent::start_element("xs:schema")
+                .ns("xs", XSD_NAMESPACE)
+                .attr("elementFormDefault", "qualified"),
+        )?;
+
+        match &self.schema.root {
+            Shape::Named(name) => match self.schema.type_shape(name) {
+                Some(TypeShape::Struct(_)) => {
+                    self.out
+                        .empty("xs:element", &[("name", name), ("type", name)])?;
+                }
+                // An enum at the root is written as the element of one of its variants
+                Some(TypeShape::Enum(variants)) => {
+                    for (variant, shape) in variants {
+                        self.variant_element(variant, shape.as_ref())?;
+                    }
+                }
+                None => {}
+            },
+            _ => {
+                return Err(Error::UnsupportedOperation {
+                    operation: "schema for a root that is not a struct or an enum".to_string(),
+                })
+            }
+        }
+
+        for (name, shape) in &self.schema.types {
+            match shape {
+                TypeShape::Struct(fields) => {
+                    self.out
+                        .start("xs:complexType", &self.mixed(name, fields))?;
+                    self.struct_content(fields)?;
+                    self.out.end()?;
+                }
+                TypeShape::Enum(variants) if self.schema.is_simple_enum(name) => {
+                    self.out.start("xs:simpleType", &[("name", name)])?;
+                    self.out.start("xs:restriction", &[("base", "xs:string")])?;
+                    for (variant, _) in variants {
+                        self.out.empty("xs:enumeration", &[("value", variant)])?;
+                    }
+                    self.out.end()?;
+                    self.out.end()?;
+                }
+                TypeShape::Enum(variants) => {
+                    self.out.start("xs:complexType", &[("name", name)])?;
+                    self.out.start("xs:choice", &[])?;
+                    for (variant, shape) in variants {
+                        self.variant_element(variant, shape.as_ref())?;
+                    }
+                    self.out.end()?;
+                    self.out.end()?;
+                }
+            }
+        }
+
+        self.out.end()
+    }
+
+    /// Attributes of the `xs:complexType` of a struct, which has mixed content if its `$value`
+    /// holds several values written as text.
+    fn mixed(
+        &self,
+        name: &'a str,
+        fields: &[(&'static str, Shape)],
+    ) -> Vec<(&'static str, &'a str)> {
+        let mut attrs = Vec::new();
+        if !name.is_empty() {
+            attrs.push(("name", name));
+        }
+        let value = fields.iter().find(|(field, _)| *field == "$value");
+        if let Some((_, Shape::Seq(item))) = value {
+            if self.is_text(item) {
+                attrs.push(("mixed", "true"));
+            }
+        }
+        attrs
+    }
+
+    /// Whether values of a shape are written as text.
+    fn is_text(&self, shape: &Shape) -> bool {
+        match shape {
+            Shape::Primitive(_) => true,
+            Shape::Option(inner) => self.is_text(inner),
+            Shape::Named(name) => self.schema.is_simple_enum(name),
+            _ => false,
+        }
+    }
+
+    /// XSD type of values written as text.
+    fn text_type(shape: &Shape) -> &str {
+        match shape {
+            Shape::Primitive(xsd_type) => xsd_type,
+            Shape::Option(inner) => Self::text_type(inner),
+            Shape::Named(name) => name,
+            _ => "xs:string",
+        }
+    }
+
+    fn struct_content(&mut self, fields: &[(&'static str, Shape)]) -> Result<()> {
+        let value = fields
+            .iter()
+            .find(|(field, _)| *field == "$value")
+            .map(|(_, shape)| shape);
+
+        match value {
+            Some(value) if self.is_text(value) => {
+                self.out.start("xs:simpleContent", &[])?;
+                self.out
+                    .start("xs:extension", &[("base", Self::text_type(value))])?;
+                self.attributes(fields)?;
+                self.out.end()?;
+                return self.out.end();
+            }
+            _ => {}
+        }
+
+        let elements: Vec<_> = fields
+            .iter()
+            .filter(|(field, _)| !field.starts_with('@') && !field.starts_with('$'))
+            .collect();
+        let unknown = fields.iter().any(|(field, _)| *field == "$unknown");
+        let value = value.filter(|value| !matches!(value, Shape::Seq(item) if self.is_text(item)));
+
+        if !elements.is_empty() || unknown || value.is_some() {
+            self.out.start("xs:sequence", &[])?;
+            for (field, shape) in elements {
+                self.element(field, shape)?;
+            }
+            if let Some(value) = value {
+                self.value_content(value)?;
+            }
+            if unknown {
+                self.out.empty(
+                    "xs:any",
+                    &[
+                        ("processContents", "lax"),
+                        ("minOccurs", "0"),
+                        ("maxOccurs", "unbounded"),
+                    ],
+                )?;
+            }
+            self.out.end()?;
+        }
+
+        self.attributes(fields)
+    }
+
+    fn attributes(&mut self, fields: &[(&'static str, Shape)]) -> Result<()> {
+        for (field, shape) in fields {
+            match field.strip_prefix('@') {
+                Some("*") => {}
+                Some(name) => {
+                    let required = if let Shape::Option(_) = shape {
+                        "optional"
+                    } else {
+                        "required"
+                    };
+                    self.out.empty(
+                        "xs:attribute",
+                        &[
+                            ("name", name),
+                            ("type", Self::text_type(shape)),
+                            ("use", required),
+                        ],
+                    )?;
+                }
+                None => {}
+            }
+        }
+        if fields.iter().any(|(field, _)| *field == "@*") {
+            self.out
+                .empty("xs:anyAttribute", &[("processContents", "lax")])?;
+        }
+        Ok(())
+    }
+
+    /// Content of a `$value` field holding elements: the variants of an enum, possibly repeated.
+    fn value_content(&mut self, value: &Shape) -> Result<()> {
+        let (item, min, max) = occurs(value);
+        let mut attrs = Vec::new();
+        attrs.extend(min.map(|min| ("minOccurs", min)));
+        attrs.extend(max.map(|max| ("maxOccurs", max)));
+
+        match item {
+            Shape::Named(name) => match self.schema.type_shape(name) {
+                Some(TypeShape::Enum(variants)) => {
+                    self.out.start("xs:choice", &attrs)?;
+                    for (variant, shape) in variants {
+                        self.variant_element(variant, shape.as_ref())?;
+                    }
+                    self.out.end()
+                }
+                _ => self.any(&attrs),
+            },
+            _ => self.any(&attrs),
+        }
+    }
+
+    fn any(&mut self, attrs: &[(&str, &str)]) -> Result<()> {
+        let mut attrs = attrs.to_vec();
+        attrs.insert(0, ("processContents", "lax"));
+        self.out.empty("xs:any", &attrs)
+    }
+
+    /// An element for a field or a newtype variant.
+    fn element(&mut self, name: &str, shape: &Shape) -> Result<()> {
+        // A field named with a path holds the items inside its wrapper element, which is
+        // omitted for `None` and empty sequences
+        if let Some((wrapper, items)) = name.split_once('/') {
+            self.out
+                .start("xs:element", &[("name", wrapper), ("minOccurs", "0")])?;
+            self.out.start("xs:complexType", &[])?;
+            self.out.start("xs:sequence", &[])?;
+            self.items(items, shape)?;
+            self.out.end()?;
+            self.out.end()?;
+            return self.out.end();
+        }
+
+        let (item, min, max) = occurs(shape);
+        let mut attrs = vec![("name", name)];
+        match item {
+            Shape::Primitive(xsd_type) => attrs.push(("type", xsd_type)),
+            Shape::Named(type_name) => attrs.push(("type", type_name)),
+            _ => {}
+        }
+        attrs.extend(min.map(|min| ("minOccurs", min)));
+        attrs.extend(max.map(|max| ("maxOccurs", max)));
+
+        match item {
+            Shape::Unit => {
+                self.out.start("xs:element", &attrs)?;
+                self.out.empty("xs:complexType", &[])?;
+                self.out.end()
+            }
+            Shape::Map(_) => {
+                self.out.start("xs:element", &attrs)?;
+                self.out.start("xs:complexType", &[])?;
+                self.out.start("xs:sequence", &[])?;
+                self.any(&[("minOccurs", "0"), ("maxOccurs", "unbounded")])?;
+                self.out.end()?;
+                self.out.end()?;
+                self.out.end()
+            }
+            _ => self.out.empty("xs:element", &attrs),
+        }
+    }
+
+    /// Elements for the items of a sequence inside a wrapper, named with the rest of the path.
+    /// Each further name is the element of an item of the outer sequence, holding the items of
+    /// the inner one.
+    fn items(&mut self, path: &str, shape: &Shape) -> Result<()> {
+        let (item, items) = match path.split_once('/') {
+            Some(names) => names,
+            None => return self.element(path, shape),
+        };
+        self.out.start(
+            "xs:element",
+            &[
+                ("name", item),
+                ("minOccurs", "0"),
+                ("maxOccurs", "unbounded"),
+            ],
+        )?;
+        self.out.start("xs:complexType", &[])?;
+        self.out.start("xs:sequence", &[])?;
+        self.items(items, seq_item(shape))?;
+        self.out.end()?;
+        self.out.end()?;
+        self.out.end()
+    }
+
+    fn variant_element(&mut self, name: &str, shape: Option<&VariantShape>) -> Result<()> {
+        match shape {
+            // Comments and processing instructions are not elements
+            _ if name == "$comment" || name == "$pi" => Ok(()),
+            Some(VariantShape::Newtype(shape)) => self.element(name, shape),
+            Some(VariantShape::Struct(fields)) => {
+                self.out.start("xs:element", &[("name", name)])?;
+                self.out.start("xs:complexType", &self.mixed("", fields))?;
+                self.struct_content(fields)?;
+                self.out.end()?;
+                self.out.end()
+            }
+            Some(VariantShape::Unit) => self.element(name, &Shape::Unit),
+            Some(VariantShape::Tuple(_)) | None => self.out.empty("xs:element", &[("name", name)]),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::io::Write;
+
+use serde::ser::{Impossible, Serialize};
+
+use super::{
+    plain::{to_plain_attr, to_plain_string},
+    Serializer,
+};
+use crate::error::{Error, Result};
+
+/// Adds the entries of a map, such as the one held by a `@*` field, as attributes of the current
+/// tag. Keys and values must serialize to plain strings.
+pub struct AttrMapSerializer<'ser, W: 'ser + Write> {
+    ser: &'ser mut Serializer<W>,
+    next_key: Option<String>,
+}
+
+impl<'ser, W: 'ser + Write> AttrMapSerializer<'ser, W> {
+    pub fn new(ser: &'ser mut Serializer<W>) -> Self {
+        AttrMapSerializer {
+            ser,
+            next_key: None,
+        }
+    }
+}
+
+fn unsupported<T>(operation: &str) -> Result<T> {
+    Err(Error::UnsupportedOperation {
+        operation: format!("{} as attributes", operation),
+    })
+}
+
+impl<'ser, W: 'ser + Write> serde::ser::Serializer for &mut AttrMapSerializer<'ser, W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = Self;
+    type SerializeStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok> {
+        unsupported("bool")
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<Self::Ok> {
+        unsupported("i8")
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<Self::Ok> {
+        unsupported("i16")
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<Self::Ok> {
+        unsupported("i32")
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<Self::Ok> {
+        unsupported("i64")
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<Self::Ok> {
+        unsupported("u8")
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<Self::Ok> {
+        unsupported("u16")
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<Self::Ok> {
+        unsupported("u32")
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<Self::Ok> {
+        unsupported("u64")
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok> {
+        unsupported("f32")
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok> {
+        unsupported("f64")
+    }
+
+    fn serialize_char(self, _v: char) -> Result<Self::Ok> {
+        unsupported("char")
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<Self::Ok> {
+        unsupported("str")
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok> {
+        unsupported("bytes")
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok> {
+        unsupported("unit variant")
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        unsupported("newtype variant")
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        unsupported("sequence")
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        unsupported("tuple")
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        unsupported("tuple struct")
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        unsupported("tuple variant")
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        unsupported("struct")
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        unsupported("struct variant")
+    }
+}
+
+impl<'ser, W: 'ser + Write> serde::ser::SerializeMap for &mut AttrMapSerializer<'ser, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.next_key = Some(to_plain_string(key, self.ser.value_format)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = self.next_key.take().ok_or(Error::Custom {
+            field: "attribute value without a name".to_string(),
+        })?;
+        match to_plain_attr(value, self.ser.value_format)? {
+            Some(value) => self.ser.add_attr(&key, value),
+            None => Ok(()),
+        }
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
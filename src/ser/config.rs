@@ -0,0 +1,212 @@
+use std::borrow::Cow;
+
+use xml::writer::EmitterConfig;
+
+use super::plain::ValueFormat;
+use crate::layout::{BoolFormat, EmptySequences, ItemNames, MapLayout, NoneValues};
+
+/// Options for the output of a `Serializer`. The defaults give compact output with an XML
+/// declaration, self-closing empty elements and attributes in declaration order.
+///
+/// ```rust
+/// # use serde::Serialize;
+/// # use serde_xml_rs::ser::{to_string_with_config, AttributeOrder, EmptyElements};
+/// # use serde_xml_rs::SerializerConfig;
+/// #[derive(Serialize)]
+/// struct Item {
+///     #[serde(rename = "@name")]
+///     name: String,
+///     #[serde(rename = "@id")]
+///     id: u32,
+///     note: (),
+/// }
+///
+/// # fn main() {
+/// let config = SerializerConfig::new()
+///     .indent("\t")
+///     .line_separator("\r\n")
+///     .empty_elements(EmptyElements::Expanded)
+///     .attribute_order(AttributeOrder::Sorted)
+///     .xml_declaration(false);
+/// let item = Item { name: "pear".to_string(), id: 7, note: () };
+/// assert_eq!(
+///     to_string_with_config(&item, &config).unwrap(),
+///     "<Item id=\"7\" name=\"pear\">\r\n\t<note></note>\r\n</Item>"
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SerializerConfig {
+    indent: Option<Cow<'static, str>>,
+    line_separator: Cow<'static, str>,
+    empty_elements: EmptyElements,
+    pub(super) attribute_order: AttributeOrder,
+    pub(super) xml_declaration: bool,
+    escaping: Escaping,
+    pub(super) map_layout: MapLayout,
+    pub(super) item_names: ItemNames,
+    pub(super) none_values: NoneValues,
+    pub(super) empty_sequences: EmptySequences,
+    float_format: FloatFormat,
+    bool_format: BoolFormat,
+}
+
+/// How elements without content are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptyElements {
+    /// `<note />`
+    #[default]
+    SelfClosing,
+    /// `<note/>`
+    SelfClosingCompact,
+    /// `<note></note>`
+    Expanded,
+}
+
+/// The order in which the attributes of an element are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AttributeOrder {
+    /// The order in which the fields are declared, or the entries of a map are serialized.
+    #[default]
+    Declaration,
+    /// Sorted by name.
+    Sorted,
+}
+
+/// How special characters in text and attribute values are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Escaping {
+    /// `<`, `>`, `&` and quotes are written as entities.
+    #[default]
+    Standard,
+    /// Text and attribute values are written as is, for values that are already escaped. The
+    /// output is not well-formed if they aren't.
+    Raw,
+}
+
+/// How floating point numbers are written, in elements and attributes alike. Infinities and NaN
+/// are written as `INF`, `-INF` and `NaN`, as in `xs:double`, whatever the format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloatFormat {
+    /// The shortest decimal representation that reads back to the same value: `100`, `0.25`.
+    #[default]
+    Decimal,
+    /// The canonical representation of `xs:double`, in exponent notation: `1.0E2`, `2.5E-1`.
+    Canonical,
+}
+
+impl Default for SerializerConfig {
+    fn default() -> Self {
+        SerializerConfig {
+            indent: None,
+            line_separator: Cow::Borrowed("\n"),
+            empty_elements: EmptyElements::default(),
+            attribute_order: AttributeOrder::default(),
+            xml_declaration: true,
+            escaping: Escaping::default(),
+            map_layout: MapLayout::default(),
+            item_names: ItemNames::default(),
+            none_values: NoneValues::default(),
+            empty_sequences: EmptySequences::default(),
+            float_format: FloatFormat::default(),
+            bool_format: BoolFormat::default(),
+        }
+    }
+}
+
+impl SerializerConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Indents nested elements with the given string, putting each element on its own line. No
+    /// indentation by default.
+    pub fn indent<S: Into<Cow<'static, str>>>(mut self, indent: S) -> Self {
+        self.indent = Some(indent.into());
+        self
+    }
+
+    /// The string ending the lines of indented output. Defaults to `"\n"`.
+    pub fn line_separator<S: Into<Cow<'static, str>>>(mut self, separator: S) -> Self {
+        self.line_separator = separator.into();
+        self
+    }
+
+    pub fn empty_elements(mut self, set: EmptyElements) -> Self {
+        self.empty_elements = set;
+        self
+    }
+
+    pub fn attribute_order(mut self, set: AttributeOrder) -> Self {
+        self.attribute_order = set;
+        self
+    }
+
+    /// Configures whether the XML declaration is written. Enabled by default.
+    pub fn xml_declaration(mut self, set: bool) -> Self {
+        self.xml_declaration = set;
+        self
+    }
+
+    pub fn escaping(mut self, set: Escaping) -> Self {
+        self.escaping = set;
+        self
+    }
+
+    /// How the entries of maps are written. Keys are element names by default.
+    pub fn map_layout(mut self, set: MapLayout) -> Self {
+        self.map_layout = set;
+        self
+    }
+
+    /// How the items of sequence fields are written. Each item is named after the field by
+    /// default.
+    pub fn item_names(mut self, set: ItemNames) -> Self {
+        self.item_names = set;
+        self
+    }
+
+    /// How `None` fields and sequence items are written. They are omitted by default.
+    pub fn none_values(mut self, set: NoneValues) -> Self {
+        self.none_values = set;
+        self
+    }
+
+    /// How empty sequence fields are written. By default, only the wrapper element of their
+    /// items is written, if they have one.
+    pub fn empty_sequences(mut self, set: EmptySequences) -> Self {
+        self.empty_sequences = set;
+        self
+    }
+
+    pub fn float_format(mut self, set: FloatFormat) -> Self {
+        self.float_format = set;
+        self
+    }
+
+    pub fn bool_format(mut self, set: BoolFormat) -> Self {
+        self.bool_format = set;
+        self
+    }
+
+    pub(super) fn value_format(&self) -> ValueFormat {
+        ValueFormat {
+            float_format: self.float_format,
+            bool_format: self.bool_format,
+        }
+    }
+
+    pub(super) fn emitter_config(&self) -> EmitterConfig {
+        let mut config = EmitterConfig::new();
+        config.perform_indent = self.indent.is_some();
+        if let Some(indent) = &self.indent {
+            config.indent_string = indent.clone();
+        }
+        config.line_separator = self.line_separator.clone();
+        config.normalize_empty_elements = self.empty_elements != EmptyElements::Expanded;
+        config.pad_self_closing = self.empty_elements == EmptyElements::SelfClosing;
+        config.perform_escaping = self.escaping == Escaping::Standard;
+        config.write_document_declaration = self.xml_declaration;
+        config
+    }
+}
@@ -1,23 +1,42 @@
-use super::{plain::to_plain_string, Serializer};
+use super::{
+    attrs::AttrMapSerializer,
+    misc_kind,
+    plain::{to_plain_attr, to_plain_string},
+    raw::{Anchors, RawKind, RawXmlSerializer},
+    Serializer,
+};
 use crate::error::{Error, Result};
+use crate::layout::{is_xml_name, MapLayout};
 use log::debug;
 use serde::ser::Serialize;
 use std::io::Write;
 
 pub struct MapSerializer<'ser, W: 'ser + Write> {
     ser: &'ser mut Serializer<W>,
-    must_close_tag: bool,
+    /// Whether the entries are written inside the current tag, which is closed at the end.
+    in_tag: bool,
+    next_key: Option<String>,
+    /// Whether the next key is the name of a struct field rather than a map key.
+    next_key_is_field: bool,
 }
 
 impl<'ser, W: 'ser + Write> MapSerializer<'ser, W> {
-    pub fn new(ser: &'ser mut Serializer<W>, must_close_tag: bool) -> Self {
+    pub fn new(ser: &'ser mut Serializer<W>, in_tag: bool) -> Self {
         MapSerializer {
             ser,
-            must_close_tag,
+            in_tag,
+            next_key: None,
+            next_key_is_field: false,
         }
     }
 }
 
+/// Whether a key is the name of a struct field. serde serializes structs with flattened fields
+/// as maps, keyed by their field names as `str`, while map keys are owned or referenced values.
+fn is_field_name<T: ?Sized>() -> bool {
+    std::any::type_name::<T>() == "str"
+}
+
 impl<'ser, W: Write> serde::ser::SerializeMap for MapSerializer<'ser, W> {
     type Ok = ();
     type Error = Error;
@@ -26,29 +45,113 @@ impl<'ser, W: Write> serde::ser::SerializeMap for MapSerializer<'ser, W> {
     where
         T: ?Sized + Serialize,
     {
-        self.ser.open_tag(&to_plain_string(key)?)?;
+        self.next_key = Some(to_plain_string(key, self.ser.value_format)?);
+        self.next_key_is_field = is_field_name::<T>();
         Ok(())
     }
 
+    /// Entries with special names are handled as struct fields are, which is also how the fields
+    /// of flattened structs are serialized. Other entries follow the map layout.
     fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut *self.ser)?;
-        Ok(())
+        let key = self.next_key.take().ok_or(Error::Custom {
+            field: "map value without a key".to_string(),
+        })?;
+        if serialize_special_field(self.ser, &key, value, None)? {
+            return Ok(());
+        }
+        // The fields of structs with flattened fields can't be told apart from entries laid out
+        // otherwise when reading them back
+        if self.next_key_is_field && self.ser.map_layout != MapLayout::ElementNames {
+            return Err(Error::UnsupportedOperation {
+                operation: "serializing flattened fields with a map layout".to_string(),
+            });
+        }
+        self.ser.build_start_tag()?;
+        match self.ser.map_layout.clone() {
+            MapLayout::ElementNames => {
+                if !is_xml_name(&key) {
+                    return Err(Error::InvalidName { name: key });
+                }
+                self.ser.open_tag(&key)?;
+            }
+            MapLayout::KeyAttribute { element, attribute } => {
+                self.ser.open_tag(&element)?;
+                self.ser.add_attr(&attribute, key.clone())?;
+            }
+            MapLayout::KeyValueElements {
+                element,
+                key: key_name,
+                value: value_name,
+            } => {
+                self.ser.open_tag(&element)?;
+                self.ser.build_start_tag()?;
+                self.ser.open_tag(&key_name)?;
+                serde::ser::Serializer::serialize_str(&mut *self.ser, &key)?;
+                self.ser.open_tag(&value_name)?;
+                value.serialize(&mut *self.ser)?;
+                return self.ser.end_tag();
+            }
+        }
+        debug!("entry {}", key);
+        value.serialize(&mut *self.ser)
     }
 
     fn end(self) -> Result<()> {
-        if self.must_close_tag {
+        if self.in_tag {
+            self.ser.build_start_tag()?;
             self.ser.end_tag()?;
         }
         Ok(())
     }
 }
 
+/// Serializes a struct field or map entry with a special name: an attribute, `@*`, `$value`,
+/// `$unknown`, `$comment` or `$pi`. Returns `false` without doing anything for other names.
+/// `Anchored` items are held back in `anchors` if given, or else written in place.
+fn serialize_special_field<W, T>(
+    ser: &mut Serializer<W>,
+    key: &str,
+    value: &T,
+    anchors: Option<&mut Anchors>,
+) -> Result<bool>
+where
+    W: Write,
+    T: ?Sized + Serialize,
+{
+    if key == "@*" {
+        debug!("extra attributes");
+        value.serialize(&mut AttrMapSerializer::new(ser))?;
+    } else if key == "$unknown" || misc_kind(key).is_some() {
+        ser.build_start_tag()?;
+        debug!("{}", key);
+        let kind = misc_kind(key).unwrap_or(RawKind::Xml);
+        let mut raw = RawXmlSerializer::with_kind(ser, kind);
+        if let Some(anchors) = anchors {
+            raw = raw.with_anchors(anchors);
+        }
+        value.serialize(&mut raw)?;
+    } else if let Some(name) = key.strip_prefix('@') {
+        debug!("attribute {}", name);
+        if let Some(value) = to_plain_attr(value, ser.value_format)? {
+            ser.add_attr(name, value)?;
+        }
+    } else if key == "$value" {
+        ser.build_start_tag()?;
+        debug!("body");
+        value.serialize(&mut *ser)?;
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
 pub struct StructSerializer<'ser, W: 'ser + Write> {
     ser: &'ser mut Serializer<W>,
     must_close_tag: bool,
+    anchors: Anchors,
 }
 
 impl<'ser, W: 'ser + Write> StructSerializer<'ser, W> {
@@ -56,6 +159,7 @@ impl<'ser, W: 'ser + Write> StructSerializer<'ser, W> {
         StructSerializer {
             ser,
             must_close_tag,
+            anchors: Anchors::default(),
         }
     }
 
@@ -63,26 +167,21 @@ impl<'ser, W: 'ser + Write> StructSerializer<'ser, W> {
     where
         T: ?Sized + Serialize,
     {
-        if key.starts_with("@") {
-            debug!("attribute {}", key);
-            self.ser.add_attr(&key[1..], to_plain_string(value)?)
-        } else if key == "$value" {
-            self.ser.build_start_tag()?;
-            debug!("body");
-            value.serialize(&mut *self.ser)?;
-            Ok(())
-        } else {
-            self.ser.build_start_tag()?;
-            self.ser.open_tag(key)?;
-            debug!("field {}", key);
-            value.serialize(&mut *self.ser)?;
-            debug!("end field");
-            Ok(())
+        if serialize_special_field(self.ser, key, value, Some(&mut self.anchors))? {
+            return Ok(());
         }
+        self.ser.build_start_tag()?;
+        self.anchors.before_field(self.ser, key)?;
+        self.ser.open_field_tag(key)?;
+        debug!("field {}", key);
+        value.serialize(&mut *self.ser)?;
+        debug!("end field");
+        Ok(())
     }
 
-    fn after_fields(self) -> Result<()> {
+    fn after_fields(mut self) -> Result<()> {
         self.ser.build_start_tag()?;
+        self.anchors.after_fields(self.ser)?;
         self.ser.end_tag()?;
         if self.must_close_tag {
             self.ser.end_tag()?;
@@ -1,19 +1,40 @@
+mod attrs;
+mod config;
 mod map;
 mod plain;
+mod raw;
+mod root;
 mod seq;
 mod tuple;
+mod writer;
 
+pub use self::config::{AttributeOrder, EmptyElements, Escaping, FloatFormat, SerializerConfig};
+pub use self::writer::XmlWriter;
 use self::{
     map::{MapSerializer, StructSerializer},
+    plain::{to_plain_string, ValueFormat},
+    raw::{RawKind, RawXmlSerializer},
+    root::RootSerializer,
     seq::SeqSeralizer,
     tuple::TupleSerializer,
 };
+use crate::cdata::CDATA_TOKEN;
+use crate::document::{Prolog, XmlDeclaration};
+use crate::encoding::{Encoding, EncodingWriter};
 use crate::error::{Error, Result};
+use crate::items::item_names_for;
+use crate::layout::{EmptySequences, ItemNames, MapLayout, NoneValues};
+use crate::raw::RAW_XML_TOKEN;
+use crate::root::{Root, ROOT_TOKEN};
 use log::debug;
 use serde::ser::Serialize;
-use std::{collections::HashMap, io::Write};
+use std::io::Write;
+use xml::reader::{self, EventReader};
 use xml::writer::{EmitterConfig, EventWriter, XmlEvent};
 
+/// Namespace of the `xsi:nil` attribute.
+pub(crate) const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
 /// A convenience method for serializing some object to a buffer.
 ///
 /// # Examples
@@ -31,7 +52,7 @@ use xml::writer::{EmitterConfig, EventWriter, XmlEvent};
 /// let mut buffer = Vec::new();
 /// let joe = Person {name: "Joe".to_string(), age: 42};
 ///
-/// to_writer(&mut buffer, &joe).unwrap();
+/// to_writer(&mut buffer, &joe, false).unwrap();
 ///
 /// let serialized = String::from_utf8(buffer).unwrap();
 /// println!("{}", serialized);
@@ -42,6 +63,23 @@ pub fn to_writer<W: Write, S: Serialize>(writer: W, value: &S, is_pretty: bool)
     value.serialize(&mut ser)
 }
 
+/// Serializes a value to a buffer with the given output options.
+pub fn to_writer_with_config<W: Write, S: Serialize>(
+    writer: W,
+    value: &S,
+    config: &SerializerConfig,
+) -> Result<()> {
+    let mut ser = Serializer::with_config(writer, config);
+    value.serialize(&mut ser)
+}
+
+/// Serializes a value to a string with the given output options. See `SerializerConfig`.
+pub fn to_string_with_config<S: Serialize>(value: &S, config: &SerializerConfig) -> Result<String> {
+    let mut writer = Vec::with_capacity(128);
+    to_writer_with_config(&mut writer, value, config)?;
+    Ok(String::from_utf8(writer)?)
+}
+
 /// A convenience method for serializing some object to a string.
 ///
 /// # Examples
@@ -82,48 +120,380 @@ pub fn to_string_pretty<S: Serialize>(value: &S) -> Result<String> {
     Ok(string)
 }
 
+/// Serializes a value with the given root element name and attributes, in place of the tag named
+/// after its type. See `Root`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use serde::Serialize;
+/// # use serde_xml_rs::ser::to_string_with_root;
+/// #[derive(Serialize)]
+/// struct Channel {
+///     id: u32,
+/// }
+///
+/// # fn main() {
+/// let serialized = to_string_with_root("ImageChannel", &[("version", "2.0")], &Channel { id: 1 });
+/// assert_eq!(
+///     serialized.unwrap(),
+///     r#"<?xml version="1.0" encoding="UTF-8"?><ImageChannel version="2.0"><id>1</id></ImageChannel>"#
+/// );
+/// # }
+/// ```
+pub fn to_string_with_root<S: Serialize>(
+    name: &str,
+    attributes: &[(&str, &str)],
+    value: &S,
+) -> Result<String> {
+    let root = attributes
+        .iter()
+        .fold(Root::new(name, value), |root, (name, value)| {
+            root.attr(*name, *value)
+        });
+    to_string(&root)
+}
+
+/// Whether a field or variant name is one of the special names under which comments and
+/// processing instructions are written, and if so which.
+fn misc_kind(name: &str) -> Option<RawKind> {
+    match name {
+        "$comment" => Some(RawKind::Comment),
+        "$pi" => Some(RawKind::ProcessingInstruction),
+        _ => None,
+    }
+}
+
 /// An XML `Serializer`.
 pub struct Serializer<W>
 where
     W: Write,
 {
-    writer: EventWriter<W>,
+    writer: EventWriter<EncodingWriter<W>>,
+    /// Configuration `writer` was created with, so that it can be recreated with other options
+    /// before anything is written.
+    config: EmitterConfig,
+    /// Prolog to write in place of the default XML declaration, until it has been written.
+    prolog: Option<Prolog>,
+    xml_declaration: bool,
+    attribute_order: AttributeOrder,
+    map_layout: MapLayout,
+    item_names: ItemNames,
+    none_values: NoneValues,
+    empty_sequences: EmptySequences,
+    value_format: ValueFormat,
+    /// Whether the last event written is a start tag.
+    in_empty_tag: bool,
     root: bool,
+    /// Name and attributes of the root tag set by a `Root`, until the root tag is opened.
+    root_name: Option<String>,
+    root_attrs: Vec<(String, String)>,
     current_tag: String,
-    current_tag_attrs: Option<HashMap<&'static str, String>>,
+    /// Whether the current tag is the `item` tag of an item of a sequence at the root, which
+    /// structs and enum variants replace with their own name.
+    item_placeholder: bool,
+    /// Whether a unit variant is written as an element named after the variant, rather than as
+    /// text. Set for the items of a sequence written directly inside an element, such as a
+    /// `$value` field.
+    variant_as_tag: bool,
+    /// Names of the items of the sequence about to be written in the current tag, and of the
+    /// items of sequences nested in them, from a field named with a path such as `Items/Item`.
+    item_path: Vec<String>,
+    /// Names of the items of the sequence about to be written in the current tag, if chosen for
+    /// the field with `items`.
+    field_item_names: Option<ItemNames>,
+    /// Attributes of the current tag, in the order they were added.
+    current_tag_attrs: Option<Vec<(String, String)>>,
 }
 
 impl<W> Serializer<W>
 where
     W: Write,
 {
-    fn new_from_writer(writer: EventWriter<W>) -> Self {
+    /// Creates a serializer with the given output options.
+    pub fn with_config(writer: W, config: &SerializerConfig) -> Self {
+        let emitter_config = config.emitter_config();
         Self {
-            writer,
+            writer: emitter_config
+                .clone()
+                .create_writer(EncodingWriter::new(writer)),
+            config: emitter_config,
+            prolog: None,
+            xml_declaration: config.xml_declaration,
+            attribute_order: config.attribute_order,
+            map_layout: config.map_layout.clone(),
+            item_names: config.item_names.clone(),
+            none_values: config.none_values,
+            empty_sequences: config.empty_sequences,
+            value_format: config.value_format(),
+            in_empty_tag: false,
             root: true,
+            root_name: None,
+            root_attrs: Vec::new(),
             current_tag: "".into(),
+            item_placeholder: false,
+            variant_as_tag: false,
+            item_path: Vec::new(),
+            field_item_names: None,
             current_tag_attrs: None,
         }
     }
 
     pub fn new(writer: W, is_pretty: bool) -> Self {
-        Self::new_from_writer(
-            EmitterConfig::new()
-                .perform_indent(is_pretty)
-                .create_writer(writer),
-        )
+        let config = SerializerConfig::new();
+        if is_pretty {
+            Self::with_config(writer, &config.indent("  "))
+        } else {
+            Self::with_config(writer, &config)
+        }
+    }
+
+    /// Configures the name of the root element. A struct is written under this name instead of
+    /// its own, and any other value is wrapped in it: the items of a sequence become its
+    /// children, named after their type for structs and enum variants and `item` otherwise.
+    /// `Deserializer::root_name` reads the same layout back. See also `Root`.
+    ///
+    /// Without a root name, only structs and enum variants can be written at the root; other
+    /// values fail with `Error::UnsupportedOperation`.
+    ///
+    /// ```rust
+    /// # use serde::Serialize;
+    /// # use serde_xml_rs::Serializer;
+    /// # fn main() {
+    /// let mut buffer = Vec::new();
+    /// let mut ser = Serializer::new(&mut buffer, false).root_name("ids");
+    /// vec![1, 2].serialize(&mut ser).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buffer).unwrap(),
+    ///     r#"<?xml version="1.0" encoding="UTF-8"?><ids><item>1</item><item>2</item></ids>"#
+    /// );
+    /// # }
+    /// ```
+    pub fn root_name(mut self, name: &str) -> Self {
+        self.root_name = Some(name.to_string());
+        self
+    }
+
+    /// Configures what is written before the root element: the XML declaration, which is omitted
+    /// if `None`, a DOCTYPE and processing instructions. Must be set before anything is
+    /// serialized.
+    ///
+    /// ```rust
+    /// # use serde::Serialize;
+    /// # use serde_xml_rs::{Prolog, Serializer, XmlDeclaration};
+    /// #[derive(Serialize)]
+    /// struct Page {
+    ///     title: String,
+    /// }
+    ///
+    /// # fn main() {
+    /// let prolog = Prolog {
+    ///     declaration: Some(XmlDeclaration {
+    ///         standalone: Some(true),
+    ///         ..XmlDeclaration::default()
+    ///     }),
+    ///     doctype: Some("Page SYSTEM \"page.dtd\"".to_string()),
+    ///     processing_instructions: vec!["xml-stylesheet href=\"page.xsl\"".to_string()],
+    /// };
+    /// let mut buffer = Vec::new();
+    /// let mut ser = Serializer::new(&mut buffer, false).prolog(prolog);
+    /// Page { title: "Home".to_string() }.serialize(&mut ser).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buffer).unwrap(),
+    ///     r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><!DOCTYPE Page SYSTEM "page.dtd"><?xml-stylesheet href="page.xsl"?><Page><title>Home</title></Page>"#
+    /// );
+    /// # }
+    /// ```
+    pub fn prolog(self, prolog: Prolog) -> Self {
+        let config = self.config.write_document_declaration(false);
+        let mut sink = self.writer.into_inner();
+        let declared = prolog
+            .declaration
+            .as_ref()
+            .and_then(|d| d.encoding.as_deref());
+        if let Some(encoding) = declared.and_then(Encoding::for_label) {
+            sink.set_encoding(encoding);
+        }
+        Self {
+            writer: config.clone().create_writer(sink),
+            config,
+            prolog: Some(prolog),
+            ..self
+        }
+    }
+
+    /// Configures the output encoding, which is also declared in the XML declaration. Defaults to
+    /// UTF-8. Characters that the encoding can't represent are written as character references in
+    /// text and attribute values, and reported as `Error::UnencodableCharacter` anywhere else,
+    /// such as in names, comments and CDATA sections.
+    /// Must be set before anything is serialized.
+    ///
+    /// ```rust
+    /// # use serde::Serialize;
+    /// # use serde_xml_rs::{Encoding, Serializer};
+    /// #[derive(Serialize)]
+    /// struct Item {
+    ///     name: String,
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut buffer = Vec::new();
+    /// let mut ser = Serializer::new(&mut buffer, false).encoding(Encoding::Latin1);
+    /// Item { name: "Café".to_string() }.serialize(&mut ser).unwrap();
+    /// assert_eq!(
+    ///     buffer,
+    ///     b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><Item><name>Caf\xe9</name></Item>"
+    /// );
+    /// # }
+    /// ```
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        let mut prolog = self.prolog.take().unwrap_or_else(|| Prolog {
+            declaration: Some(XmlDeclaration::default()).filter(|_| self.xml_declaration),
+            ..Prolog::default()
+        });
+        if let Some(declaration) = &mut prolog.declaration {
+            declaration.encoding = Some(encoding.name().to_string());
+        }
+        let mut ser = self.prolog(prolog);
+        ser.writer.inner_mut().set_encoding(encoding);
+        ser
     }
 
     fn next(&mut self, event: XmlEvent) -> Result<()> {
+        self.write_prolog()?;
+        self.check_encodable(&event)?;
+        self.in_empty_tag = matches!(event, XmlEvent::StartElement { .. });
         self.writer.write(event)?;
         Ok(())
     }
 
+    /// Checks that the output encoding can represent the parts of an event where a character
+    /// reference can't stand in for a character: anything but text and attribute values.
+    fn check_encodable(&mut self, event: &XmlEvent) -> Result<()> {
+        let encoding = self.writer.inner_mut().encoding();
+        let check_name = |name: &xml::name::Name| {
+            if let Some(prefix) = name.prefix {
+                encoding.check_encodable(prefix)?;
+            }
+            encoding.check_encodable(name.local_name)
+        };
+        match event {
+            XmlEvent::StartElement {
+                name,
+                attributes,
+                namespace,
+            } => {
+                check_name(name)?;
+                for attribute in attributes.iter() {
+                    check_name(&attribute.name)?;
+                }
+                for prefix in namespace.0.keys() {
+                    encoding.check_encodable(prefix)?;
+                }
+                Ok(())
+            }
+            XmlEvent::EndElement { name: Some(name) } => check_name(name),
+            XmlEvent::Comment(text) | XmlEvent::CData(text) => encoding.check_encodable(text),
+            XmlEvent::ProcessingInstruction { name, data } => {
+                encoding.check_encodable(name)?;
+                encoding.check_encodable(data.unwrap_or_default())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes the prolog set with `prolog`, if it hasn't been written yet.
+    fn write_prolog(&mut self) -> Result<()> {
+        let prolog = match self.prolog.take() {
+            Some(prolog) => prolog,
+            None => return Ok(()),
+        };
+        if let Some(declaration) = &prolog.declaration {
+            let encoding = match &declaration.encoding {
+                Some(label) if Encoding::for_label(label).is_none() => {
+                    return Err(Error::UnsupportedEncoding {
+                        encoding: label.clone(),
+                    })
+                }
+                Some(label) => label.as_str(),
+                None => self.writer.inner_mut().encoding().name(),
+            };
+            self.writer.write(XmlEvent::StartDocument {
+                version: declaration.version,
+                encoding: Some(encoding),
+                standalone: declaration.standalone,
+            })?;
+        }
+        if let Some(doctype) = &prolog.doctype {
+            // The writer has no event for the DOCTYPE
+            let sink = self.writer.inner_mut();
+            sink.encoding().check_encodable(doctype)?;
+            if self.config.perform_indent && prolog.declaration.is_some() {
+                sink.write_all(b"\n")?;
+            }
+            write!(sink, "<!DOCTYPE {}>", doctype)?;
+        }
+        for pi in &prolog.processing_instructions {
+            self.processing_instruction(pi)?;
+        }
+        Ok(())
+    }
+
     fn characters(&mut self, s: &str) -> Result<()> {
         self.next(XmlEvent::characters(s))
     }
 
+    /// Writes text as CDATA sections. A section cannot contain `]]>`, so the text is split
+    /// between its `]]` and `>` into consecutive sections.
+    fn cdata(&mut self, s: &str) -> Result<()> {
+        let mut rest = s;
+        while let Some(end) = rest.find("]]>") {
+            self.next(XmlEvent::cdata(&rest[..end + 2]))?;
+            rest = &rest[end + 2..];
+        }
+        self.next(XmlEvent::cdata(rest))
+    }
+
+    /// Writes an XML fragment as is, by replaying its events. Namespaces declared in the fragment
+    /// are only re-declared if they are not already in scope.
+    fn raw(&mut self, xml: &str) -> Result<()> {
+        let mut reader = EventReader::new(xml.as_bytes());
+        loop {
+            match reader.next()? {
+                reader::XmlEvent::StartDocument { .. } => {}
+                reader::XmlEvent::EndDocument => break,
+                event => {
+                    if let Some(event) = event.as_writer_event() {
+                        self.next(event)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn comment(&mut self, text: &str) -> Result<()> {
+        self.next(XmlEvent::comment(text))
+    }
+
+    /// Writes a processing instruction given as its target, followed by its data if any.
+    fn processing_instruction(&mut self, pi: &str) -> Result<()> {
+        match pi.split_once(char::is_whitespace) {
+            Some((target, data)) => self.next(XmlEvent::processing_instruction(
+                target,
+                Some(data.trim_start()),
+            )),
+            None => self.next(XmlEvent::processing_instruction(pi, None)),
+        }
+    }
+
     fn start_document(&mut self) -> Result<()> {
+        if self.prolog.is_some() {
+            return self.write_prolog();
+        }
+        if !self.xml_declaration {
+            return Ok(());
+        }
         self.next(XmlEvent::StartDocument {
             encoding: Default::default(),
             standalone: Default::default(),
@@ -132,7 +502,7 @@ where
     }
 
     fn open_root_tag(&mut self, name: &'static str) -> Result<()> {
-        if self.root {
+        if self.root && !self.open_named_root()? {
             self.root = false;
             self.start_document()?;
             self.open_tag(name)?;
@@ -140,36 +510,93 @@ where
         Ok(())
     }
 
+    /// Opens the root tag with the name and attributes set by `root_name` or a `Root`, if the
+    /// root tag hasn't been opened yet and a name is set.
+    fn open_named_root(&mut self) -> Result<bool> {
+        if !self.root {
+            return Ok(false);
+        }
+        let name = match self.root_name.take() {
+            Some(name) => name,
+            None => return Ok(false),
+        };
+        self.root = false;
+        self.start_document()?;
+        self.open_tag(&name)?;
+        self.current_tag_attrs = Some(std::mem::take(&mut self.root_attrs));
+        Ok(true)
+    }
+
+    /// Fails for a value that can only be written at the root inside a root tag named by
+    /// `root_name`, if no name is set: text isn't a document, and the items of a sequence would
+    /// each be a root element of their own.
+    fn check_root_name(&self, value: &str) -> Result<()> {
+        if self.root && self.root_name.is_none() {
+            return Err(Error::UnsupportedOperation {
+                operation: format!("serializing {} at the root without a root_name", value),
+            });
+        }
+        Ok(())
+    }
+
+    /// Opens the `item` tag of an item of a sequence at the root.
+    fn open_item_placeholder(&mut self) -> Result<()> {
+        self.open_tag("item")?;
+        self.item_placeholder = true;
+        Ok(())
+    }
+
+    /// Drops the `item` tag if it is the current tag, for a value that is named on its own.
+    fn replace_item_placeholder(&mut self) -> Result<()> {
+        if self.item_placeholder {
+            self.abandon_tag()?;
+        }
+        Ok(())
+    }
+
     fn open_tag(&mut self, tag_name: &str) -> Result<()> {
+        self.item_placeholder = false;
+        self.variant_as_tag = false;
+        self.item_path.clear();
+        self.field_item_names = None;
         self.current_tag = tag_name.into();
-        self.current_tag_attrs = Some(HashMap::new());
+        self.current_tag_attrs = Some(Vec::new());
         Ok(())
     }
 
-    fn reopen_tag(&mut self) -> Result<()> {
-        self.current_tag_attrs = Some(HashMap::new());
+    /// Opens the tag of a struct field. A path such as `Items/Item` opens the
+    /// wrapper element, and names the items of the sequence inside it.
+    fn open_field_tag(&mut self, key: &str) -> Result<()> {
+        let mut names = key.split('/');
+        self.open_tag(names.next().unwrap_or_default())?;
+        self.item_path = names.map(str::to_string).collect();
         Ok(())
     }
 
     fn abandon_tag(&mut self) -> Result<()> {
+        self.item_placeholder = false;
         self.current_tag = "".into();
         self.current_tag_attrs = None;
         Ok(())
     }
 
-    fn add_attr(&mut self, name: &'static str, value: String) -> Result<()> {
+    fn add_attr(&mut self, name: &str, value: String) -> Result<()> {
         self.current_tag_attrs
             .as_mut()
             .ok_or(Error::Custom {
                 field: format!("Cannot add attribute {}", name),
             })
-            .map(|attrs| {
-                attrs.insert(name, value);
+            .map(|attrs| match attrs.iter_mut().find(|(n, _)| n == name) {
+                Some(attr) => attr.1 = value,
+                None => attrs.push((name.to_string(), value)),
             })
     }
 
     fn build_start_tag(&mut self) -> Result<bool> {
+        // A value other than a struct at the root is wrapped in the named root tag
+        self.open_named_root()?;
         if let Some(attrs) = self.current_tag_attrs.take() {
+            self.item_placeholder = false;
             self.start_tag(&self.current_tag(), attrs)?;
             Ok(true)
         } else {
@@ -177,17 +604,31 @@ where
         }
     }
 
-    fn start_tag(&mut self, tag_name: &str, attrs: HashMap<&str, String>) -> Result<()> {
-        let element = attrs
-            .iter()
-            .fold(XmlEvent::start_element(tag_name), |b, (&name, value)| {
-                b.attr(name, value)
-            });
+    /// Writes a start tag. Attributes named `xmlns` or `xmlns:prefix` are written as namespace
+    /// declarations.
+    fn start_tag(&mut self, tag_name: &str, mut attrs: Vec<(String, String)>) -> Result<()> {
+        if self.attribute_order == AttributeOrder::Sorted {
+            attrs.sort();
+        }
+        let element =
+            attrs.iter().fold(
+                XmlEvent::start_element(tag_name),
+                |b, (name, value)| match name.strip_prefix("xmlns") {
+                    Some("") => b.default_ns(value.as_str()),
+                    Some(prefixed) if prefixed.starts_with(':') => b.ns(&prefixed[1..], value),
+                    _ => b.attr(name.as_str(), value),
+                },
+            );
 
         self.next(element.into())
     }
 
     fn end_tag(&mut self) -> Result<()> {
+        // Without any content, the writer puts the end tag of an expanded empty element on a line
+        // of its own when indenting
+        if self.in_empty_tag && !self.config.normalize_empty_elements {
+            self.characters("")?;
+        }
         self.next(XmlEvent::end_element().into())
     }
 
@@ -209,7 +650,8 @@ impl<'ser, W: Write> serde::ser::Serializer for &'ser mut Serializer<W> {
     type SerializeStructVariant = StructSerializer<'ser, W>;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
-        self.serialize_str(&v.to_string())
+        let formatted = self.value_format.bool_format.format(v);
+        self.serialize_str(formatted)
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
@@ -241,6 +683,7 @@ impl<'ser, W: Write> serde::ser::Serializer for &'ser mut Serializer<W> {
     }
 
     fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
+        self.check_root_name("a number")?;
         let must_close_tag = self.build_start_tag()?;
         self.characters(&v.to_string())?;
         if must_close_tag {
@@ -254,7 +697,8 @@ impl<'ser, W: Write> serde::ser::Serializer for &'ser mut Serializer<W> {
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
-        self.serialize_str(&v.to_string())
+        let formatted = self.value_format.format_f64(v);
+        self.serialize_str(&formatted)
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok> {
@@ -262,6 +706,7 @@ impl<'ser, W: Write> serde::ser::Serializer for &'ser mut Serializer<W> {
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        self.check_root_name("text")?;
         let must_close_tag = self.build_start_tag()?;
         self.characters(v)?;
         if must_close_tag {
@@ -276,13 +721,25 @@ impl<'ser, W: Write> serde::ser::Serializer for &'ser mut Serializer<W> {
 
     fn serialize_none(self) -> Result<Self::Ok> {
         debug!("None");
-        self.abandon_tag()?;
-        Ok(())
+        if self.root {
+            return self.serialize_unit();
+        }
+        match self.none_values {
+            NoneValues::Omit => self.abandon_tag(),
+            NoneValues::EmptyElement => self.serialize_unit(),
+            NoneValues::XsiNil => {
+                if self.current_tag_attrs.is_some() {
+                    self.add_attr("xmlns:xsi", XSI_NAMESPACE.to_string())?;
+                    self.add_attr("xsi:nil", "true".to_string())?;
+                }
+                self.serialize_unit()
+            }
+        }
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         debug!("Some");
         value.serialize(self)
@@ -290,6 +747,7 @@ impl<'ser, W: Write> serde::ser::Serializer for &'ser mut Serializer<W> {
 
     fn serialize_unit(self) -> Result<Self::Ok> {
         debug!("Unit");
+        self.check_root_name("a unit")?;
         let must_close_tag = self.build_start_tag()?;
         if must_close_tag {
             self.end_tag()?;
@@ -299,6 +757,9 @@ impl<'ser, W: Write> serde::ser::Serializer for &'ser mut Serializer<W> {
 
     fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok> {
         debug!("Unit struct {}", name);
+        if self.root && self.root_name.is_none() {
+            self.open_root_tag(name)?;
+        }
         self.serialize_unit()
     }
 
@@ -309,19 +770,58 @@ impl<'ser, W: Write> serde::ser::Serializer for &'ser mut Serializer<W> {
         variant: &'static str,
     ) -> Result<Self::Ok> {
         debug!("Unit variant {}::{}", name, variant);
+        if self.item_placeholder || self.variant_as_tag {
+            self.open_tag(variant)?;
+            return self.serialize_unit();
+        }
         self.serialize_str(variant)?;
         Ok(())
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(self, name: &'static str, value: &T) -> Result<Self::Ok>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
+        if name == RAW_XML_TOKEN {
+            debug!("Raw XML");
+            if self.root {
+                self.root = false;
+                self.start_document()?;
+            }
+            // The captured element replaces the one that was about to be written
+            self.abandon_tag()?;
+            return value.serialize(&mut RawXmlSerializer::new(self));
+        }
+        if name == ROOT_TOKEN {
+            if !self.root {
+                return Err(Error::UnsupportedOperation {
+                    operation: "serializing a Root below the root element".to_string(),
+                });
+            }
+            debug!("Root");
+            return value.serialize(&mut RootSerializer::new(self));
+        }
+        if let Some(item_names) = item_names_for(name) {
+            debug!("Item names {:?}", item_names);
+            self.field_item_names = Some(item_names);
+            return value.serialize(self);
+        }
+        if name == CDATA_TOKEN {
+            debug!("CDATA");
+            self.check_root_name("CDATA")?;
+            let text = to_plain_string(value, self.value_format)?;
+            let must_close_tag = self.build_start_tag()?;
+            self.cdata(&text)?;
+            if must_close_tag {
+                self.end_tag()?;
+            }
+            return Ok(());
+        }
         debug!("Newtype struct {}", name);
         value.serialize(self)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         name: &'static str,
         _variant_index: u32,
@@ -329,11 +829,21 @@ impl<'ser, W: Write> serde::ser::Serializer for &'ser mut Serializer<W> {
         value: &T,
     ) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
+        self.replace_item_placeholder()?;
         let must_close_tag = self.build_start_tag()?;
 
         debug!("Newtype variant {}::{}", name, variant);
+        if let Some(kind) = misc_kind(variant) {
+            value.serialize(&mut RawXmlSerializer::with_kind(self, kind))?;
+            if must_close_tag {
+                self.end_tag()?;
+            }
+            return Ok(());
+        }
+        // The variant tag is the root element
+        self.root = false;
         self.open_tag(variant)?;
         value.serialize(&mut *self)?;
 
@@ -345,11 +855,22 @@ impl<'ser, W: Write> serde::ser::Serializer for &'ser mut Serializer<W> {
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
         debug!("Sequence");
+        if self.root && self.root_name.is_some() {
+            self.build_start_tag()?;
+            return Ok(SeqSeralizer::at_root(self));
+        }
+        self.check_root_name("a sequence")?;
+        if !self.item_path.is_empty() {
+            let mut item_path = std::mem::take(&mut self.item_path);
+            let item = item_path.remove(0);
+            return Ok(SeqSeralizer::wrapped(self, item, item_path));
+        }
         Ok(SeqSeralizer::new(self))
     }
 
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
         debug!("Tuple");
+        self.check_root_name("a tuple")?;
         let must_close_tag = self.build_start_tag()?;
         Ok(TupleSerializer::new(self, must_close_tag))
     }
@@ -360,6 +881,7 @@ impl<'ser, W: Write> serde::ser::Serializer for &'ser mut Serializer<W> {
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
         debug!("Tuple struct {}", name);
+        self.check_root_name("a tuple struct")?;
         let must_close_tag = self.build_start_tag()?;
         Ok(TupleSerializer::new(self, must_close_tag))
     }
@@ -372,20 +894,27 @@ impl<'ser, W: Write> serde::ser::Serializer for &'ser mut Serializer<W> {
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
         debug!("Tuple variant {}::{}", name, variant);
+        self.replace_item_placeholder()?;
         let must_close_tag = self.build_start_tag()?;
-        self.start_tag(variant, HashMap::new())?;
+        self.root = false;
+        self.start_tag(variant, Vec::new())?;
         Ok(TupleSerializer::new(self, must_close_tag))
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        let must_close_tag = self.build_start_tag()?;
-        Ok(MapSerializer::new(self, must_close_tag))
+        // serde gives no name for a struct with flattened fields, which is serialized as a map
+        self.check_root_name("a map or a struct with flattened fields")?;
+        // The entries go inside the current tag, which is left open so that `@` entries can add
+        // attributes to it
+        self.open_named_root()?;
+        let in_tag = self.current_tag_attrs.is_some();
+        Ok(MapSerializer::new(self, in_tag))
     }
 
     fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
         if self.root {
             self.open_root_tag(name)?;
-        } else {
+        } else if self.current_tag_attrs.is_none() || self.item_placeholder {
             self.open_tag(name)?;
         }
 
@@ -400,6 +929,7 @@ impl<'ser, W: Write> serde::ser::Serializer for &'ser mut Serializer<W> {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant> {
+        self.replace_item_placeholder()?;
         self.open_root_tag(name)?;
 
         debug!("Struct variant {}", variant);
@@ -460,4 +990,172 @@ mod tests {
         let got = String::from_utf8(buffer).unwrap();
         assert_eq!(got, should_be);
     }
+
+    #[derive(Debug, Serialize)]
+    struct Camera {
+        #[serde(rename = "@zone")]
+        zone: String,
+        #[serde(rename = "@id")]
+        id: u32,
+        #[serde(rename = "@active")]
+        active: bool,
+        label: String,
+        presets: (),
+    }
+
+    #[test]
+    fn serializer_config() {
+        let camera = Camera {
+            zone: "lobby".to_string(),
+            id: 3,
+            active: true,
+            label: "A &amp; B".to_string(),
+            presets: (),
+        };
+
+        // Attributes are written in declaration order, every time
+        for _ in 0..10 {
+            assert_eq!(
+                to_string_with_config(&camera, &SerializerConfig::new().xml_declaration(false))
+                    .unwrap(),
+                r#"<Camera zone="lobby" id="3" active="true"><label>A &amp;amp; B</label><presets /></Camera>"#
+            );
+        }
+
+        let config = SerializerConfig::new()
+            .indent("    ")
+            .empty_elements(EmptyElements::SelfClosingCompact)
+            .attribute_order(AttributeOrder::Sorted)
+            .escaping(Escaping::Raw);
+        assert_eq!(
+            to_string_with_config(&camera, &config).unwrap(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Camera active=\"true\" id=\"3\" zone=\"lobby\">\n    <label>A &amp; B</label>\n    <presets/>\n</Camera>"
+        );
+    }
+
+    #[test]
+    fn flattened_attributes_and_value() {
+        #[derive(Serialize)]
+        struct Common {
+            #[serde(rename = "@id")]
+            id: u32,
+            #[serde(rename = "@lang")]
+            lang: &'static str,
+        }
+
+        #[derive(Serialize)]
+        struct Note {
+            #[serde(flatten)]
+            common: Common,
+            #[serde(rename = "$value")]
+            text: &'static str,
+        }
+
+        #[derive(Serialize)]
+        struct Message {
+            note: Note,
+        }
+
+        let message = Message {
+            note: Note {
+                common: Common { id: 1, lang: "en" },
+                text: "Hello",
+            },
+        };
+        let config = SerializerConfig::new().xml_declaration(false);
+        assert_eq!(
+            to_string_with_config(&message, &config).unwrap(),
+            r#"<Message><note id="1" lang="en">Hello</note></Message>"#
+        );
+
+        // At the root, the struct needs a root name, since serde doesn't give its own
+        assert!(matches!(
+            to_string_with_config(&message.note, &config),
+            Err(Error::UnsupportedOperation { .. })
+        ));
+        let mut buffer = Vec::new();
+        {
+            let mut ser = Serializer::with_config(&mut buffer, &config).root_name("note");
+            message.note.serialize(&mut ser).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            r#"<note id="1" lang="en">Hello</note>"#
+        );
+
+        let mut attributes = std::collections::BTreeMap::new();
+        attributes.insert("@href", "/home");
+        attributes.insert("@rel", "start");
+        let mut buffer = Vec::new();
+        {
+            let mut ser = Serializer::with_config(&mut buffer, &config).root_name("link");
+            attributes.serialize(&mut ser).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            r#"<link href="/home" rel="start" />"#
+        );
+        assert!(matches!(
+            to_string_with_config(&attributes, &config),
+            Err(Error::UnsupportedOperation { .. })
+        ));
+    }
+
+    #[test]
+    fn xml_writer_streams_and_flushes() {
+        #[derive(Default)]
+        struct Sink {
+            written: Vec<u8>,
+            flushes: usize,
+        }
+
+        impl Write for Sink {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.written.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.flushes += 1;
+                Ok(())
+            }
+        }
+
+        #[derive(Serialize)]
+        enum Status {
+            Active,
+        }
+
+        let mut writer = XmlWriter::new(Sink::default()).flush_interval(2);
+        writer.begin_element("cameras", &[]).unwrap();
+        for id in 0..5 {
+            writer
+                .write(&Camera {
+                    zone: "lobby".to_string(),
+                    id,
+                    active: true,
+                    label: String::new(),
+                    presets: (),
+                })
+                .unwrap();
+        }
+        writer.write(&Status::Active).unwrap();
+        assert!(writer.end_element().is_ok());
+        assert!(writer.end_element().is_err());
+        assert!(writer.text("after the root").is_err());
+        assert!(matches!(
+            writer.begin_element("cameras", &[]),
+            Err(Error::UnsupportedOperation { .. })
+        ));
+        assert!(matches!(
+            writer.write(&Status::Active),
+            Err(Error::UnsupportedOperation { .. })
+        ));
+
+        let sink = writer.finish().unwrap();
+        assert_eq!(sink.flushes, 4);
+        let xml = String::from_utf8(sink.written).unwrap();
+        assert_eq!(xml.matches("<Camera ").count(), 5);
+        assert!(xml.ends_with("<Active /></cameras>"));
+    }
 }
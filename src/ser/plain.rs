@@ -2,26 +2,79 @@ use std::io::Write;
 
 use serde::ser::{Impossible, Serialize};
 
+use super::config::FloatFormat;
 use crate::error::{Error, Result};
+use crate::layout::BoolFormat;
+
+/// How scalar values are written as text, the same way in element content and in attribute
+/// values.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValueFormat {
+    pub float_format: FloatFormat,
+    pub bool_format: BoolFormat,
+}
+
+impl ValueFormat {
+    /// Writes a float in the lexical space of `xs:double`, where infinities are `INF` and `-INF`.
+    pub fn format_f64(&self, v: f64) -> String {
+        if v.is_nan() {
+            return "NaN".to_string();
+        }
+        if v.is_infinite() {
+            return if v > 0.0 { "INF" } else { "-INF" }.to_string();
+        }
+        match self.float_format {
+            FloatFormat::Decimal => v.to_string(),
+            FloatFormat::Canonical => {
+                // One digit before the point and at least one after it, as in `1.0E2`
+                let formatted = format!("{:E}", v);
+                match formatted.split_once('E') {
+                    Some((mantissa, exponent)) if !mantissa.contains('.') => {
+                        format!("{}.0E{}", mantissa, exponent)
+                    }
+                    _ => formatted,
+                }
+            }
+        }
+    }
+}
 
-pub fn to_plain_string<T>(value: &T) -> Result<String>
+pub fn to_plain_string<T>(value: &T, format: ValueFormat) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    Ok(to_plain_attr(value, format)?.unwrap_or_default())
+}
+
+/// Serializes the value of an attribute, which is left out if `None`.
+pub fn to_plain_attr<T>(value: &T, format: ValueFormat) -> Result<Option<String>>
 where
     T: ?Sized + Serialize,
 {
     let mut writer = Vec::with_capacity(128);
-    value.serialize(&mut PlainStringSerializer::new(&mut writer))?;
+    let mut ser = PlainStringSerializer::new(&mut writer, format);
+    value.serialize(&mut ser)?;
+    if ser.is_none {
+        return Ok(None);
+    }
 
     let string = String::from_utf8(writer)?;
-    Ok(string)
+    Ok(Some(string))
 }
 
 struct PlainStringSerializer<W: Write> {
     writer: W,
+    format: ValueFormat,
+    is_none: bool,
 }
 
 impl<W: Write> PlainStringSerializer<W> {
-    fn new(writer: W) -> Self {
-        PlainStringSerializer { writer }
+    fn new(writer: W, format: ValueFormat) -> Self {
+        PlainStringSerializer {
+            writer,
+            format,
+            is_none: false,
+        }
     }
 
     fn characters(&mut self, s: &str) -> Result<()> {
@@ -43,7 +96,8 @@ impl<'ser, W: 'ser + Write> serde::ser::Serializer for &'ser mut PlainStringSeri
     type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
-        self.characters(&v.to_string())
+        let formatted = self.format.bool_format.format(v);
+        self.characters(formatted)
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
@@ -83,7 +137,8 @@ impl<'ser, W: 'ser + Write> serde::ser::Serializer for &'ser mut PlainStringSeri
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
-        self.characters(&v.to_string())
+        let formatted = self.format.format_f64(v);
+        self.characters(&formatted)
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok> {
@@ -99,14 +154,15 @@ impl<'ser, W: 'ser + Write> serde::ser::Serializer for &'ser mut PlainStringSeri
     }
 
     fn serialize_none(self) -> Result<Self::Ok> {
-        unimplemented!()
+        self.is_none = true;
+        Ok(())
     }
 
-    fn serialize_some<T: ?Sized>(self, _value: &T) -> Result<Self::Ok>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        unimplemented!()
+        value.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok> {
@@ -121,23 +177,19 @@ impl<'ser, W: 'ser + Write> serde::ser::Serializer for &'ser mut PlainStringSeri
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<Self::Ok> {
-        unimplemented!()
+        self.characters(variant)
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
-        self,
-        _name: &'static str,
-        _value: &T,
-    ) -> Result<Self::Ok>
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        unimplemented!()
+        value.serialize(self)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -145,7 +197,7 @@ impl<'ser, W: 'ser + Write> serde::ser::Serializer for &'ser mut PlainStringSeri
         _value: &T,
     ) -> Result<Self::Ok>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unimplemented!()
     }
@@ -0,0 +1,331 @@
+use std::io::Write;
+
+use serde::ser::{Impossible, Serialize};
+
+use super::plain::to_plain_string;
+use super::Serializer;
+use crate::anchored::ANCHORED_TOKEN;
+use crate::error::{Error, Result};
+
+/// What the strings given to a `RawXmlSerializer` hold.
+#[derive(Clone, Copy)]
+pub enum RawKind {
+    /// XML fragments, such as the elements captured into a `$unknown` field.
+    Xml,
+    /// The text of comments.
+    Comment,
+    /// The target of processing instructions, followed by their data if any.
+    ProcessingInstruction,
+}
+
+/// Writes XML fragments, comments or processing instructions held as strings verbatim into the
+/// output. Accepts a single string, an optional one, or a sequence of them, any of which can be
+/// wrapped in `Anchored`.
+pub struct RawXmlSerializer<'ser, W: 'ser + Write> {
+    ser: &'ser mut Serializer<W>,
+    kind: RawKind,
+    /// Where the nodes of `Anchored` items are held back, when written among the fields of a
+    /// struct.
+    anchors: Option<&'ser mut Anchors>,
+    /// Whether the parts of an `Anchored` item are expected.
+    in_anchored: bool,
+    /// Field the `Anchored` item being written is anchored to, once read.
+    before: Option<String>,
+}
+
+impl<'ser, W: 'ser + Write> RawXmlSerializer<'ser, W> {
+    pub fn new(ser: &'ser mut Serializer<W>) -> Self {
+        Self::with_kind(ser, RawKind::Xml)
+    }
+
+    pub fn with_kind(ser: &'ser mut Serializer<W>, kind: RawKind) -> Self {
+        RawXmlSerializer {
+            ser,
+            kind,
+            anchors: None,
+            in_anchored: false,
+            before: None,
+        }
+    }
+
+    /// Holds back the nodes of `Anchored` items until the fields they are anchored to.
+    pub fn with_anchors(mut self, anchors: &'ser mut Anchors) -> Self {
+        self.anchors = Some(anchors);
+        self
+    }
+}
+
+/// The nodes of `Anchored` items collected into the special fields of a struct, held back until
+/// the fields they are anchored to are written.
+#[derive(Default)]
+pub struct Anchors {
+    /// Fields written so far.
+    written: Vec<&'static str>,
+    /// Nodes to write before a field not written yet, or after all fields if its name is empty.
+    pending: Vec<(String, RawKind, String)>,
+}
+
+impl Anchors {
+    /// Writes the nodes anchored to the field about to be written, and records it as written.
+    pub fn before_field<W: Write>(
+        &mut self,
+        ser: &mut Serializer<W>,
+        field: &'static str,
+    ) -> Result<()> {
+        let (anchored, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|(before, _, _)| before == field);
+        self.pending = pending;
+        write_all(ser, anchored)?;
+        self.written.push(field);
+        Ok(())
+    }
+
+    /// Writes the nodes that are still held back, after all fields.
+    pub fn after_fields<W: Write>(&mut self, ser: &mut Serializer<W>) -> Result<()> {
+        write_all(ser, std::mem::take(&mut self.pending))
+    }
+}
+
+fn write_all<W: Write>(
+    ser: &mut Serializer<W>,
+    nodes: Vec<(String, RawKind, String)>,
+) -> Result<()> {
+    for (_, kind, text) in nodes {
+        write_node(ser, kind, &text)?;
+    }
+    Ok(())
+}
+
+fn write_node<W: Write>(ser: &mut Serializer<W>, kind: RawKind, text: &str) -> Result<()> {
+    match kind {
+        RawKind::Xml => ser.raw(text),
+        RawKind::Comment => ser.comment(text),
+        RawKind::ProcessingInstruction => ser.processing_instruction(text),
+    }
+}
+
+fn unsupported<T>(operation: &str) -> Result<T> {
+    Err(Error::UnsupportedOperation {
+        operation: format!("{} as raw XML", operation),
+    })
+}
+
+impl<'ser, W: 'ser + Write> serde::ser::Serializer for &mut RawXmlSerializer<'ser, W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = Impossible<Self::Ok, Self::Error>;
+    type SerializeStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok> {
+        unsupported("bool")
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<Self::Ok> {
+        unsupported("i8")
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<Self::Ok> {
+        unsupported("i16")
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<Self::Ok> {
+        unsupported("i32")
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<Self::Ok> {
+        unsupported("i64")
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<Self::Ok> {
+        unsupported("u8")
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<Self::Ok> {
+        unsupported("u16")
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<Self::Ok> {
+        unsupported("u32")
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<Self::Ok> {
+        unsupported("u64")
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok> {
+        unsupported("f32")
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok> {
+        unsupported("f64")
+    }
+
+    fn serialize_char(self, _v: char) -> Result<Self::Ok> {
+        unsupported("char")
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        match (self.before.as_deref(), self.anchors.as_deref_mut()) {
+            (Some(before), Some(anchors))
+                if before.is_empty() || !anchors.written.contains(&before) =>
+            {
+                anchors
+                    .pending
+                    .push((before.to_string(), self.kind, v.to_string()));
+                Ok(())
+            }
+            _ => write_node(self.ser, self.kind, v),
+        }
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok> {
+        unsupported("bytes")
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok> {
+        unsupported("unit variant")
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        if name == ANCHORED_TOKEN {
+            self.in_anchored = true;
+            let result = value.serialize(&mut *self);
+            self.in_anchored = false;
+            self.before = None;
+            return result;
+        }
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        unsupported("newtype variant")
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        if !self.in_anchored {
+            return unsupported("tuple");
+        }
+        self.in_anchored = false;
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        unsupported("tuple struct")
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        unsupported("tuple variant")
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        unsupported("map")
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        unsupported("struct")
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        unsupported("struct variant")
+    }
+}
+
+impl<'ser, W: 'ser + Write> serde::ser::SerializeSeq for &mut RawXmlSerializer<'ser, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The field an `Anchored` item is anchored to, followed by its value.
+impl<'ser, W: 'ser + Write> serde::ser::SerializeTuple for &mut RawXmlSerializer<'ser, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if self.before.is_none() {
+            self.before = Some(to_plain_string(value, self.ser.value_format)?);
+            return Ok(());
+        }
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+use std::io::Write;
+
+use serde::ser::{Impossible, Serialize};
+
+use super::{plain::to_plain_string, Serializer};
+use crate::error::{Error, Result};
+
+/// Reads the parts of a `Root`, sets the name and attributes of the root tag and serializes the
+/// value under it.
+pub struct RootSerializer<'ser, W: 'ser + Write> {
+    ser: &'ser mut Serializer<W>,
+    next_key: Option<String>,
+}
+
+impl<'ser, W: 'ser + Write> RootSerializer<'ser, W> {
+    pub fn new(ser: &'ser mut Serializer<W>) -> Self {
+        RootSerializer {
+            ser,
+            next_key: None,
+        }
+    }
+}
+
+fn unexpected<T>() -> Result<T> {
+    Err(Error::Custom {
+        field: "Root must be serialized as a map of its parts".to_string(),
+    })
+}
+
+impl<'ser, W: 'ser + Write> serde::ser::Serializer for &mut RootSerializer<'ser, W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = Self;
+    type SerializeStruct = Impossible<Self::Ok, Self::Error>;
+    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_char(self, _v: char) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        unexpected()
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok> {
+        unexpected()
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, _value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        unexpected()
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        unexpected()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        unexpected()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        unexpected()
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        unexpected()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        unexpected()
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        unexpected()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        unexpected()
+    }
+}
+
+impl<'ser, W: 'ser + Write> serde::ser::SerializeMap for &mut RootSerializer<'ser, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.next_key = Some(to_plain_string(key, self.ser.value_format)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = self.next_key.take().unwrap_or_default();
+        if key == "$name" {
+            self.ser.root_name = Some(to_plain_string(value, self.ser.value_format)?);
+        } else if let Some(name) = key.strip_prefix('@') {
+            self.ser.root_attrs.push((
+                name.to_string(),
+                to_plain_string(value, self.ser.value_format)?,
+            ));
+        } else if key == "$value" {
+            value.serialize(&mut *self.ser)?;
+        } else {
+            return unexpected();
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
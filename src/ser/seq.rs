@@ -1,15 +1,75 @@
 use super::Serializer;
 use crate::error::{Error, Result};
+use crate::layout::{EmptySequences, ItemNames};
 use serde::ser::Serialize;
 use std::io::Write;
 
+/// How the items of a sequence are named.
+enum Items {
+    /// `item` tags, which structs and enum variants replace with their own name.
+    Placeholder,
+    /// Elements with the given name, along with the names of the items of sequences nested in
+    /// them.
+    Named(String, Vec<String>),
+    /// Items named on their own, for a sequence written directly inside an element, such as a
+    /// `$value` field.
+    Unnamed,
+}
+
 pub struct SeqSeralizer<'ser, W: 'ser + Write> {
     ser: &'ser mut Serializer<W>,
+    items: Items,
+    /// Whether the current tag wraps the items, and is only written along with the first item.
+    wrapper_pending: bool,
+    /// Whether the items are written inside an element opened for the sequence, the root tag or
+    /// a wrapper element, which is closed at the end.
+    must_close_tag: bool,
+    /// Whether no item has been written yet.
+    empty: bool,
 }
 
 impl<'ser, W: 'ser + Write> SeqSeralizer<'ser, W> {
+    /// Items named as configured inside the current tag, if any, such as the field holding the
+    /// sequence.
     pub fn new(ser: &'ser mut Serializer<W>) -> Self {
-        SeqSeralizer { ser }
+        let item_names = match ser.field_item_names.take() {
+            Some(item_names) => item_names,
+            None => ser.item_names.clone(),
+        };
+        let (items, wrapper_pending) = match ser.current_tag_attrs {
+            None => (Items::Unnamed, false),
+            Some(_) => match item_names {
+                ItemNames::FieldName => (Items::Named(ser.current_tag(), Vec::new()), false),
+                ItemNames::TypeName => (Items::Placeholder, true),
+                ItemNames::Element(name) => (Items::Named(name.into_owned(), Vec::new()), true),
+            },
+        };
+        Self::with_items(ser, items, wrapper_pending)
+    }
+
+    /// Items of a sequence at the root, written as the children of the root tag.
+    pub fn at_root(ser: &'ser mut Serializer<W>) -> Self {
+        SeqSeralizer {
+            must_close_tag: true,
+            ..Self::with_items(ser, Items::Placeholder, false)
+        }
+    }
+
+    /// Items with the given name inside the current tag, the wrapper element of a field named
+    /// with a path.
+    pub fn wrapped(ser: &'ser mut Serializer<W>, item: String, item_path: Vec<String>) -> Self {
+        let wrapper_pending = ser.current_tag_attrs.is_some();
+        Self::with_items(ser, Items::Named(item, item_path), wrapper_pending)
+    }
+
+    fn with_items(ser: &'ser mut Serializer<W>, items: Items, wrapper_pending: bool) -> Self {
+        SeqSeralizer {
+            ser,
+            items,
+            wrapper_pending,
+            must_close_tag: false,
+            empty: true,
+        }
     }
 }
 
@@ -21,17 +81,49 @@ impl<'ser, W: 'ser + Write> serde::ser::SerializeSeq for SeqSeralizer<'ser, W> {
     where
         T: ?Sized + Serialize,
     {
-        let must_close_tag = self.ser.build_start_tag()?;
-        value.serialize(&mut *self.ser)?;
-        if must_close_tag {
-            self.ser.end_tag()?;
-            self.ser.reopen_tag()?;
+        self.empty = false;
+        if self.wrapper_pending {
+            self.wrapper_pending = false;
+            self.must_close_tag = self.ser.build_start_tag()?;
+        }
+        match &self.items {
+            Items::Placeholder => {
+                self.ser.open_item_placeholder()?;
+                value.serialize(&mut *self.ser)
+            }
+            Items::Named(item, item_path) => {
+                // The first item of a field takes the tag already opened for the field
+                if self.ser.current_tag_attrs.is_none() {
+                    self.ser.open_tag(item)?;
+                }
+                self.ser.item_path = item_path.clone();
+                value.serialize(&mut *self.ser)
+            }
+            Items::Unnamed => {
+                // Enum items are written as elements named after their variant
+                self.ser.variant_as_tag = true;
+                let result = value.serialize(&mut *self.ser);
+                self.ser.variant_as_tag = false;
+                result
+            }
         }
-        Ok(())
     }
 
     fn end(self) -> Result<()> {
+        // The tag of a field or wrapper that is still open holds an empty sequence
+        let write_empty = match self.ser.empty_sequences {
+            EmptySequences::EmptyWrapper => self.wrapper_pending,
+            EmptySequences::Omit => false,
+            EmptySequences::EmptyElement => true,
+        };
+        if self.empty && self.ser.current_tag_attrs.is_some() && write_empty {
+            self.ser.build_start_tag()?;
+            self.ser.end_tag()?;
+        }
         self.ser.abandon_tag()?;
+        if self.must_close_tag {
+            self.ser.end_tag()?;
+        }
         Ok(())
     }
 }
@@ -0,0 +1,148 @@
+use std::io::Write;
+
+use serde::ser::Serialize;
+
+use super::{Serializer, SerializerConfig};
+use crate::error::{Error, Result};
+
+/// Writes a document piece by piece, for documents too large to be built in memory first:
+/// elements are begun and ended explicitly, and any serializable value can be written inside
+/// them. Output goes straight to the underlying writer, which is flushed every
+/// `flush_interval` values.
+///
+/// ```rust
+/// # use serde::Serialize;
+/// # use serde_xml_rs::XmlWriter;
+/// #[derive(Serialize)]
+/// struct Record {
+///     id: u32,
+/// }
+///
+/// # fn main() {
+/// let mut writer = XmlWriter::new(Vec::new());
+/// writer.begin_element("export", &[("version", "2")]).unwrap();
+/// for id in 1..=2 {
+///     writer.write(&Record { id }).unwrap();
+/// }
+/// writer.begin_element("note", &[]).unwrap();
+/// writer.text("complete").unwrap();
+/// writer.end_element().unwrap();
+///
+/// let xml = String::from_utf8(writer.finish().unwrap()).unwrap();
+/// assert_eq!(
+///     xml,
+///     r#"<?xml version="1.0" encoding="UTF-8"?><export version="2"><Record><id>1</id></Record><Record><id>2</id></Record><note>complete</note></export>"#
+/// );
+/// # }
+/// ```
+pub struct XmlWriter<W: Write> {
+    ser: Serializer<W>,
+    /// Names of the elements begun and not ended yet, innermost last.
+    open: Vec<String>,
+    flush_interval: usize,
+    /// Values written since the last flush.
+    unflushed: usize,
+}
+
+impl<W: Write> XmlWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_config(writer, &SerializerConfig::new())
+    }
+
+    pub fn with_config(writer: W, config: &SerializerConfig) -> Self {
+        XmlWriter {
+            ser: Serializer::with_config(writer, config),
+            open: Vec::new(),
+            flush_interval: 1000,
+            unflushed: 0,
+        }
+    }
+
+    /// Flushes the underlying writer after every `interval` values written with `write`. Every
+    /// 1000 values by default; 0 leaves flushing to `finish`.
+    pub fn flush_interval(mut self, interval: usize) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Writes the start tag of an element. The first element written is the root element, and
+    /// the XML declaration is written before it. Once the root element has ended, no other
+    /// element can be begun.
+    pub fn begin_element(&mut self, name: &str, attributes: &[(&str, &str)]) -> Result<()> {
+        self.check_not_after_root()?;
+        if self.ser.root {
+            self.ser.root = false;
+            self.ser.start_document()?;
+        }
+        let attributes = attributes
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        self.ser.start_tag(name, attributes)?;
+        self.open.push(name.to_string());
+        Ok(())
+    }
+
+    /// Serializes a value inside the current element. Structs are written as elements named after
+    /// their type, enums as elements named after their variant and other values as text. Without
+    /// a current element, the value is written as the root element, as `to_writer` would.
+    pub fn write<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.check_not_after_root()?;
+        self.ser.variant_as_tag = !self.open.is_empty();
+        let result = value.serialize(&mut self.ser);
+        self.ser.variant_as_tag = false;
+        result?;
+
+        self.unflushed += 1;
+        if self.flush_interval > 0 && self.unflushed >= self.flush_interval {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes text inside the current element.
+    pub fn text(&mut self, text: &str) -> Result<()> {
+        if self.open.is_empty() {
+            return Err(Error::UnsupportedOperation {
+                operation: "writing text outside of an element".to_string(),
+            });
+        }
+        self.ser.characters(text)
+    }
+
+    /// Writes the end tag of the current element.
+    pub fn end_element(&mut self) -> Result<()> {
+        if self.open.pop().is_none() {
+            return Err(Error::UnsupportedOperation {
+                operation: "ending an element that wasn't begun".to_string(),
+            });
+        }
+        self.ser.end_tag()
+    }
+
+    /// Fails if the root element has already ended, since a document has a single root.
+    fn check_not_after_root(&self) -> Result<()> {
+        if !self.ser.root && self.open.is_empty() {
+            return Err(Error::UnsupportedOperation {
+                operation: "writing a second root element".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<()> {
+        self.unflushed = 0;
+        self.ser.writer.inner_mut().flush()?;
+        Ok(())
+    }
+
+    /// Ends the elements still open, flushes and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        while !self.open.is_empty() {
+            self.end_element()?;
+        }
+        self.flush()?;
+        Ok(self.ser.writer.into_inner().into_inner())
+    }
+}
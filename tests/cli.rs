@@ -0,0 +1,131 @@
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn serde_xml(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_serde-xml"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    assert!(output.status.success(), "{:?}", output);
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+const ORDER: &str = r#"<?xml version="1.0"?>
+<order id="7">
+    <line sku="A">apple</line>
+    <line sku="B">pear</line>
+    <note/>
+</order>"#;
+
+#[test]
+fn json_round_trip() {
+    let json = serde_xml(&["to-json"], ORDER);
+    let value: serde_json::Value = serde_json::from_str(stdout(&json)).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "order": {
+                "@id": "7",
+                "line": [
+                    { "@sku": "A", "$value": "apple" },
+                    { "@sku": "B", "$value": "pear" },
+                ],
+                "note": "",
+            }
+        })
+    );
+
+    let xml = serde_xml(&["from-json"], stdout(&json));
+    let minified = serde_xml(&["minify"], stdout(&xml));
+    let canonical = serde_xml(&["canonicalize"], stdout(&minified));
+    assert_eq!(
+        stdout(&canonical),
+        r#"<order id="7"><line sku="A">apple</line><line sku="B">pear</line><note></note></order>"#
+    );
+}
+
+#[test]
+fn canonicalize_without_comments() {
+    let output = serde_xml(
+        &["canonicalize"],
+        "<!-- c --><?style a?><a><!-- inner --><b/></a><!-- after --><?end?>",
+    );
+    assert_eq!(stdout(&output), "<?style a?>\n<a><b></b></a>\n<?end?>");
+
+    let output = serde_xml(&["canonicalize"], "<!-- c --><a/><!-- after -->");
+    assert_eq!(stdout(&output), "<a></a>");
+}
+
+#[test]
+fn extract_path() {
+    let output = serde_xml(&["extract", "/order/line[2]/@sku"], ORDER);
+    assert_eq!(stdout(&output), "B\n");
+
+    let output = serde_xml(&["extract", "/order/line"], ORDER);
+    assert_eq!(
+        stdout(&output),
+        "<line sku=\"A\">apple</line>\n<line sku=\"B\">pear</line>\n"
+    );
+
+    let output = serde_xml(&["extract", "/order/missing"], ORDER);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn check_against_schema() {
+    let schema = std::env::temp_dir().join(format!("serde-xml-cli-{}.xsd", std::process::id()));
+    std::fs::write(
+        &schema,
+        r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+            <xs:element name="order">
+                <xs:complexType>
+                    <xs:sequence>
+                        <xs:element name="line" maxOccurs="unbounded">
+                            <xs:complexType>
+                                <xs:simpleContent>
+                                    <xs:extension base="xs:string">
+                                        <xs:attribute name="sku" type="xs:string" use="required"/>
+                                    </xs:extension>
+                                </xs:simpleContent>
+                            </xs:complexType>
+                        </xs:element>
+                    </xs:sequence>
+                    <xs:attribute name="id" type="xs:int"/>
+                </xs:complexType>
+            </xs:element>
+        </xs:schema>"#,
+    )
+    .unwrap();
+    let schema = schema.to_str().unwrap();
+
+    let valid = serde_xml(
+        &["check", schema],
+        r#"<order id="7"><line sku="A"/></order>"#,
+    );
+    assert_eq!(stdout(&valid), "valid\n");
+
+    let invalid = serde_xml(&["check", schema], ORDER);
+    assert_eq!(invalid.status.code(), Some(1));
+    let message = String::from_utf8_lossy(&invalid.stderr);
+    assert!(message.contains("/order/note"), "{}", message);
+
+    let malformed = serde_xml(&["check", schema], "<order>\n  <line sku=\"A\"></order>");
+    assert_eq!(malformed.status.code(), Some(1));
+    let message = String::from_utf8_lossy(&malformed.stderr);
+    assert!(message.contains("2:"), "{}", message);
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "cfg")]
+pub struct Cfg {
+    #[serde(rename = "@id", alias = "id")]
+    pub id: u32,
+    #[serde(rename = "$value", default)]
+    pub value: Vec<CfgContent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CfgContent {
+    #[serde(rename = "mode")]
+    Mode(String),
+    #[serde(rename = "a")]
+    A(i32),
+    #[serde(rename = "b")]
+    B(String),
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "module")]
+pub struct Module {
+    #[serde(rename = "self")]
+    pub self_: Self_,
+    #[serde(rename = "crate")]
+    pub crate_: String,
+    pub r#type: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Self_ {
+    pub z: i64,
+}
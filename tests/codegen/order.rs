@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "order")]
+pub struct Order {
+    #[serde(rename = "@id", alias = "id")]
+    pub id: i64,
+    #[serde(rename = "@rush", alias = "rush")]
+    pub rush: Option<bool>,
+    #[serde(default)]
+    pub line: Vec<String>,
+    pub quantity: Option<i64>,
+    pub note: Option<String>,
+}
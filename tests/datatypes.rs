@@ -29,6 +29,8 @@ mod de {
     #[case::u64("<bla> 1234 </bla>", 1234u64)]
     #[case::bool("<bla>true</bla>", true)]
     #[case::bool("<bla>false</bla>", false)]
+    #[case::bool("<bla>0</bla>", false)]
+    #[case::bool("<bla>TRUE</bla>", true)]
     #[case::unit("<bla/>", ())]
     #[case::f64("<bla>3.0</bla>", 3.0f64)]
     #[case::f64("<bla>3.1</bla>", 3.1f64)]
@@ -38,6 +40,9 @@ mod de {
     #[case::f64("<bla>0.4e15</bla>", 0.4e15f64)]
     #[case::f64_precision_troubles("<bla>0.4e-01</bla>", 0.4e-01f64)]
     #[case::f64("<bla> 0.4e-01 </bla>", 0.4e-01f64)]
+    #[case::f64_infinity("<bla>INF</bla>", f64::INFINITY)]
+    #[case::f64_infinity("<bla>-INF</bla>", f64::NEG_INFINITY)]
+    #[case::f32_infinity("<bla>INF</bla>", f32::INFINITY)]
     #[case::option("<bla/>", Some("".to_string()))]
     #[case::option("<bla></bla>", Some("".to_string()))]
     #[case::option("<bla> </bla>", Some("".to_string()))]
@@ -70,6 +75,8 @@ mod de {
     #[case(r#"<bla foo="false"/>"#, DummyAttribute { foo: false })]
     #[case(r#"<bla foo="1"/>"#, DummyAttribute { foo: true })]
     #[case(r#"<bla foo="0"/>"#, DummyAttribute { foo: false })]
+    #[case(r#"<bla foo="-INF"/>"#, DummyAttribute { foo: f64::NEG_INFINITY })]
+    #[case(r#"<bla foo="1.5E2"/>"#, DummyAttribute { foo: 150f32 })]
     fn attribute_ok<T, 'de>(_logger: (), #[case] document: &str, #[case] expected: T)
     where
         T: Deserialize<'de> + Debug + PartialEq,
@@ -82,7 +89,7 @@ mod de {
 mod ser {
     use super::*;
     use serde::{self, Serialize};
-    use serde_xml_rs::to_string;
+    use serde_xml_rs::{to_string, BoolFormat};
 
     #[derive(Serialize, Debug)]
     #[serde(rename = "bla")]
@@ -112,6 +119,9 @@ mod ser {
     #[case::f64("<bla>40000</bla>", 0.4e5f64)]
     #[case::f64("<bla>400000000000000</bla>", 0.4e15f64)]
     #[case::f64_precision_troubles("<bla>0.04</bla>", 0.4e-01f64)]
+    #[case::f64_infinity("<bla>INF</bla>", f64::INFINITY)]
+    #[case::f64_infinity("<bla>-INF</bla>", f64::NEG_INFINITY)]
+    #[case::f64_nan("<bla>NaN</bla>", f64::NAN)]
     #[case::option("<bla></bla>", Some("".to_string()))]
     #[case::option("<bla>42</bla>", Some("42".to_string()))]
     fn element_ok<T>(_logger: (), #[case] expected: &str, #[case] value: T)
@@ -132,10 +142,23 @@ mod ser {
         value: T,
     }
 
+    #[derive(Serialize, Debug)]
+    enum Color {
+        Red,
+    }
+
+    #[derive(Serialize, Debug)]
+    struct Meters(u32);
+
     #[rstest]
     #[case::string(r#"<bla value="" />"#, "".to_string())]
     #[case::bool(r#"<bla value="true" />"#, true)]
     #[case::bool(r#"<bla value="false" />"#, false)]
+    #[case::option(r#"<bla value="42" />"#, Some(42))]
+    #[case::option(r#"<bla />"#, None::<i32>)]
+    #[case::unit_variant(r#"<bla value="Red" />"#, Color::Red)]
+    #[case::newtype_struct(r#"<bla value="7" />"#, Meters(7))]
+    #[case::f64_infinity(r#"<bla value="INF" />"#, f64::INFINITY)]
     fn attribute_ok<T>(_logger: (), #[case] expected: &str, #[case] value: T)
     where
         T: Serialize + Debug,
@@ -146,4 +169,83 @@ mod ser {
             format!(r#"<?xml version="1.0" encoding="UTF-8"?>{}"#, expected)
         );
     }
+
+    #[rstest]
+    #[case("<bla>1.0E2</bla>", 100f64)]
+    #[case("<bla>2.5E-1</bla>", 0.25f64)]
+    #[case("<bla>-1.234E3</bla>", -1234f64)]
+    #[case("<bla>0.0E0</bla>", 0f64)]
+    #[case("<bla>-INF</bla>", f64::NEG_INFINITY)]
+    #[case("<bla>1.5E0</bla>", 1.5f32)]
+    fn canonical_float<T>(_logger: (), #[case] expected: &str, #[case] value: T)
+    where
+        T: Serialize + Debug,
+    {
+        use serde_xml_rs::ser::{to_string_with_config, FloatFormat};
+        use serde_xml_rs::SerializerConfig;
+
+        let config = SerializerConfig::new()
+            .xml_declaration(false)
+            .float_format(FloatFormat::Canonical);
+        let actual = to_string_with_config(&Dummy { value }, &config).unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case(BoolFormat::TrueFalse, r#"<bla value="true"><flag>false</flag></bla>"#)]
+    #[case(BoolFormat::OneZero, r#"<bla value="1"><flag>0</flag></bla>"#)]
+    #[case(BoolFormat::UpperCase, r#"<bla value="TRUE"><flag>FALSE</flag></bla>"#)]
+    #[case(
+        BoolFormat::Custom("on", "off"),
+        r#"<bla value="on"><flag>off</flag></bla>"#
+    )]
+    #[case(BoolFormat::Custom("0", "1"), r#"<bla value="0"><flag>1</flag></bla>"#)]
+    fn bool_format(_logger: (), #[case] format: BoolFormat, #[case] expected: &str) {
+        use serde::Deserialize;
+        use serde_xml_rs::ser::to_string_with_config;
+        use serde_xml_rs::{Deserializer, SerializerConfig};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        #[serde(rename = "bla")]
+        struct Flags {
+            #[serde(rename = "@value", alias = "value")]
+            value: bool,
+            flag: bool,
+        }
+
+        let flags = Flags {
+            value: true,
+            flag: false,
+        };
+        let config = SerializerConfig::new()
+            .xml_declaration(false)
+            .bool_format(format);
+        let actual = to_string_with_config(&flags, &config).unwrap();
+        assert_eq!(actual, expected);
+
+        let mut de = Deserializer::new_from_reader(actual.as_bytes()).bool_format(format);
+        assert_eq!(Flags::deserialize(&mut de).unwrap(), flags);
+    }
+
+    #[rstest]
+    fn attribute_map_skips_none(_logger: ()) {
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize, Debug)]
+        #[serde(rename = "bla")]
+        struct DummyAttributes {
+            #[serde(rename = "@*")]
+            attributes: BTreeMap<String, Option<String>>,
+        }
+
+        let attributes = BTreeMap::from([
+            ("set".to_string(), Some("yes".to_string())),
+            ("unset".to_string(), None),
+        ]);
+        let actual = to_string(&DummyAttributes { attributes }).unwrap();
+        assert_eq!(
+            actual,
+            r#"<?xml version="1.0" encoding="UTF-8"?><bla set="yes" />"#
+        );
+    }
 }
@@ -21,11 +21,8 @@ fn simple_struct_from_attributes_should_fail() {
 
     let item: Result<Item, _> = from_str(s);
     match item {
-        Ok(_) => assert!(false),
-        Err(e) => {
-            info!("simple_struct_from_attributes_should_fail(): {}", e);
-            assert!(true)
-        }
+        Ok(_) => panic!("expected a syntax error"),
+        Err(e) => info!("simple_struct_from_attributes_should_fail(): {}", e),
     }
 }
 
@@ -40,10 +37,7 @@ fn multiple_roots_attributes_should_fail() {
 
     let item: Result<Vec<Item>, _> = from_str(s);
     match item {
-        Ok(_) => assert!(false),
-        Err(e) => {
-            info!("multiple_roots_attributes_should_fail(): {}", e);
-            assert!(true)
-        }
+        Ok(_) => panic!("expected a syntax error"),
+        Err(e) => info!("multiple_roots_attributes_should_fail(): {}", e),
     }
 }
@@ -4,10 +4,13 @@ use common::init_logger;
 use log::info;
 use serde::ser::*;
 use serde::{Deserialize, Serialize, Serializer};
-use serde_xml_rs::{from_str, to_string};
+use serde_xml_rs::{from_str, to_string, Deserializer, Error};
+#[allow(unused_imports)]
 use std::fmt::Display;
 
+#[allow(dead_code)]
 const NIGHT_TO_DAY_FILTER_TIME: u32 = 5;
+#[allow(dead_code)]
 const NIGHT_TO_DAY_FILTER_LEVEL: u32 = 4;
 const NIGHT_TO_DAY_FILTER_LEVEL_PTZ: u32 = 2;
 
@@ -925,9 +928,7 @@ pub struct FocusData {
 
 impl From<FocusValue> for FocusData {
     fn from(value: FocusValue) -> Self {
-        Self {
-            focus: value as i32,
-        }
+        Self { focus: value }
     }
 }
 
@@ -1050,8 +1051,8 @@ impl SyncSignalOutput {
 
     pub fn is_set(&self) -> bool {
         self.output_status == OutputStatus::Pulse
-            && self.video_flash_enable == false
-            && self.detect_brightness_enable == false
+            && !self.video_flash_enable
+            && !self.detect_brightness_enable
     }
 
     pub fn is_unset(&self) -> bool {
@@ -1059,11 +1060,12 @@ impl SyncSignalOutput {
     }
 }
 
-#[derive(Debug, Deserialize, PartialEq, Serialize, Clone, Copy)]
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize, Clone, Copy)]
 pub enum FirmwareVerison {
     #[serde(rename = "V5.7.3")]
     V573,
     #[serde(rename = "V5.0.2")]
+    #[default]
     V502,
     #[serde(rename = "V5.1.4")]
     V514,
@@ -1073,12 +1075,6 @@ pub enum FirmwareVerison {
     V55800,
 }
 
-impl Default for FirmwareVerison {
-    fn default() -> Self {
-        FirmwareVerison::V502
-    }
-}
-
 #[derive(Debug, Default)]
 pub struct SPSettings {
     pub enabled: bool,
@@ -1258,10 +1254,7 @@ impl From<IrcutFilterTypes> for bool {
     fn from(ift: IrcutFilterTypes) -> Self {
         use IrcutFilterTypes::*;
 
-        match ift {
-            Night => true,
-            _ => false,
-        }
+        matches!(ift, Night)
     }
 }
 
@@ -1491,6 +1484,7 @@ pub struct NTPServer {
 }
 
 #[test]
+#[allow(clippy::assertions_on_constants)]
 fn simple_hik() {
     init_logger();
 
@@ -1502,3 +1496,19 @@ fn simple_hik() {
 
     assert!(false);
 }
+
+#[test]
+fn strict_root_hik() {
+    init_logger();
+
+    let s = r##"<?xml version="1.0" encoding="UTF-8"?><ImageChannel version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><id>1</id><enabled>true</enabled><videoInputID>1</videoInputID><ImageFlip version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><enabled>false</enabled></ImageFlip><IrcutFilter version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><IrcutFilterType>day</IrcutFilterType><nightToDayFilterLevel>2</nightToDayFilterLevel></IrcutFilter><Exposure version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><ExposureType>auto</ExposureType><OverexposeSuppress><enabled>false</enabled></OverexposeSuppress></Exposure><powerLineFrequency version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><powerLineFrequencyMode>50hz</powerLineFrequencyMode></powerLineFrequency><PTZ version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><enabled>true</enabled></PTZ><FocusConfiguration version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><focusStyle>SEMIAUTOMATIC</focusStyle><focusLimited>600</focusLimited></FocusConfiguration><LensInitialization version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><enabled>false</enabled></LensInitialization><DSS version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><enabled>false</enabled><DSSLevel>*2</DSSLevel></DSS><IrLight version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><mode>auto</mode></IrLight><ZoomLimit version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><ZoomLimitRatio>32</ZoomLimitRatio></ZoomLimit><Iris version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><IrisLevel>160</IrisLevel><maxIrisLevelLimit>100</maxIrisLevelLimit><minIrisLevelLimit>0</minIrisLevelLimit></Iris><CaptureMode version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><mode>close</mode></CaptureMode><ImageFreeze version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><enabled>false</enabled></ImageFreeze><proportionalpan version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><enabled>true</enabled></proportionalpan><LaserLight version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><mode>manual</mode><brightnessLevel>0</brightnessLevel><laserangle>0</laserangle></LaserLight><WDR version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><mode>close</mode><WDRLevel>50</WDRLevel></WDR><BLC version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><enabled>false</enabled></BLC><NoiseReduce version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><mode>general</mode><GeneralMode><generalLevel>50</generalLevel></GeneralMode></NoiseReduce><WhiteBalance version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><WhiteBalanceStyle>auto</WhiteBalanceStyle><WhiteBalanceRed>50</WhiteBalanceRed><WhiteBalanceBlue>50</WhiteBalanceBlue></WhiteBalance><Sharpness version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><SharpnessLevel>50</SharpnessLevel></Sharpness><Gain version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><GainLevel>0</GainLevel><GainLimit>94</GainLimit></Gain><Shutter version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><ShutterLevel>1/25</ShutterLevel><maxShutterLevelLimit>1/25</maxShutterLevelLimit><minShutterLevelLimit>1/30000</minShutterLevelLimit></Shutter><Color version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><brightnessLevel>50</brightnessLevel><contrastLevel>50</contrastLevel><saturationLevel>50</saturationLevel></Color><Dehaze version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><DehazeMode>close</DehazeMode></Dehaze><HLC version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><enabled>false</enabled><HLCLevel>0</HLCLevel></HLC><EIS version="2.0" xmlns="http://www.hikvision.com/ver20/XMLSchema"><enabled>false</enabled></EIS></ImageChannel>"##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).strict_root(true);
+    match StreamingChannel::deserialize(&mut de) {
+        Err(Error::UnexpectedRoot { expected, found }) => {
+            assert_eq!(expected, "StreamingChannel");
+            assert_eq!(found, "ImageChannel");
+        }
+        other => panic!("expected a root mismatch, got {:?}", other),
+    }
+}
@@ -8,6 +8,7 @@ struct Item {
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 enum Node {
     Boolean(bool),
     Identifier { value: String, index: u32 },
@@ -90,3 +91,969 @@ fn whitespace_preserving_config() {
     let reserialized_item = to_string(&item).unwrap();
     assert_eq!(reserialized_item, serialized_should_be);
 }
+
+#[test]
+fn round_trip_unknown_fields() {
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Channel {
+        #[serde(rename = "@*")]
+        extra_attributes: BTreeMap<String, String>,
+        name: String,
+        #[serde(rename = "$unknown")]
+        unknown: Vec<String>,
+    }
+
+    let src = r#"<?xml version="1.0" encoding="UTF-8"?><Channel mode="auto"><name>Front door</name><Codec type="h264"><profile>main</profile></Codec><bitrate>4096</bitrate></Channel>"#;
+
+    let channel: Channel = from_str(src).unwrap();
+    assert_eq!(channel.name, "Front door");
+    assert_eq!(
+        channel.extra_attributes.get("mode").map(String::as_str),
+        Some("auto")
+    );
+    assert_eq!(
+        channel.unknown,
+        vec![
+            r#"<Codec type="h264"><profile>main</profile></Codec>"#.to_string(),
+            "<bitrate>4096</bitrate>".to_string(),
+        ]
+    );
+
+    let reserialized = to_string(&channel).unwrap();
+    assert_eq!(reserialized, src);
+}
+
+#[test]
+fn round_trip_unknown_elements_in_place() {
+    use serde_xml_rs::Anchored;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Channel {
+        #[serde(rename = "@*")]
+        extra_attributes: BTreeMap<String, String>,
+        #[serde(rename = "$unknown", default)]
+        unknown: Vec<Anchored<String>>,
+        name: String,
+        enabled: bool,
+    }
+
+    let src = r#"<?xml version="1.0" encoding="UTF-8"?><Channel xmlns:v="urn:vendor" mode="auto"><name>Front door</name><v:Codec>h264</v:Codec><enabled>true</enabled></Channel>"#;
+
+    let channel: Channel = from_str(src).unwrap();
+    assert_eq!(channel.extra_attributes["xmlns:v"], "urn:vendor");
+    assert_eq!(
+        channel.unknown,
+        vec![Anchored::new(
+            r#"<v:Codec xmlns:v="urn:vendor">h264</v:Codec>"#.to_string(),
+            Some("enabled".to_string())
+        )]
+    );
+    assert_eq!(to_string(&channel).unwrap(), src);
+
+    let without_unknown: Channel =
+        from_str(r#"<Channel><name>Front door</name><enabled>true</enabled></Channel>"#).unwrap();
+    assert!(without_unknown.unknown.is_empty());
+}
+
+#[test]
+fn round_trip_unknown_elements_order() {
+    use serde_xml_rs::Anchored;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Channel {
+        #[serde(rename = "$unknown", default)]
+        unknown: Vec<Anchored<String>>,
+        a: u32,
+        b: u32,
+    }
+
+    let src = r#"<?xml version="1.0" encoding="UTF-8"?><Channel><w>0</w><a>1</a><x>2</x><y>3</y><b>4</b><z>5</z></Channel>"#;
+
+    let channel: Channel = from_str(src).unwrap();
+    let anchors: Vec<_> = channel
+        .unknown
+        .iter()
+        .map(|item| item.before.as_deref())
+        .collect();
+    assert_eq!(anchors, vec![Some("a"), Some("b"), Some("b"), None]);
+    assert_eq!(to_string(&channel).unwrap(), src);
+
+    // Plain strings are written where the `$unknown` field is declared
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Grouped {
+        a: u32,
+        b: u32,
+        #[serde(rename = "$unknown", default)]
+        unknown: Vec<String>,
+    }
+
+    let grouped: Grouped = from_str(src).unwrap();
+    let should_be = r#"<?xml version="1.0" encoding="UTF-8"?><Grouped><a>1</a><b>4</b><w>0</w><x>2</x><y>3</y><z>5</z></Grouped>"#;
+    assert_eq!(to_string(&grouped).unwrap(), should_be);
+}
+
+#[test]
+fn round_trip_raw_xml() {
+    use serde_xml_rs::{Lazy, RawXml};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Details {
+        size: u64,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Report {
+        id: u32,
+        extension: Option<RawXml>,
+        details: Lazy<Details>,
+        #[serde(rename = "$unknown")]
+        unknown: Vec<RawXml>,
+    }
+
+    let src = r#"<?xml version="1.0" encoding="UTF-8"?><Report xmlns:v="urn:vendor"><id>1</id><extension v:kind="a"><v:item>1</v:item><v:item>2</v:item></extension><details><size>42</size></details><v:note>checked</v:note></Report>"#;
+
+    let report: Report = from_str(src).unwrap();
+    assert_eq!(
+        report.extension.as_ref().map(RawXml::as_str),
+        Some(
+            r#"<extension xmlns:v="urn:vendor" v:kind="a"><v:item>1</v:item><v:item>2</v:item></extension>"#
+        )
+    );
+    assert_eq!(report.details.get().unwrap(), Details { size: 42 });
+    assert_eq!(
+        report.unknown,
+        vec![RawXml::new(
+            r#"<v:note xmlns:v="urn:vendor">checked</v:note>"#
+        )]
+    );
+
+    let should_be = r#"<?xml version="1.0" encoding="UTF-8"?><Report><id>1</id><extension xmlns:v="urn:vendor" v:kind="a"><v:item>1</v:item><v:item>2</v:item></extension><details><size>42</size></details><v:note xmlns:v="urn:vendor">checked</v:note></Report>"#;
+    assert_eq!(to_string(&report).unwrap(), should_be);
+
+    let root: RawXml = from_str(src).unwrap();
+    let reparsed: Report = root.parse().unwrap();
+    assert_eq!(reparsed, report);
+}
+
+fn comment_preserving_deserializer(src: &str) -> serde_xml_rs::Deserializer<&[u8]> {
+    let config = ParserConfig::new()
+        .trim_whitespace(true)
+        .ignore_comments(false);
+    let event_reader = EventReader::new_with_config(src.as_bytes(), config);
+    serde_xml_rs::Deserializer::new(event_reader).preserve_comments_and_pis(true)
+}
+
+#[test]
+fn round_trip_comments_and_pis_as_fields() {
+    use serde_xml_rs::Anchored;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Server {
+        #[serde(rename = "$comment", default)]
+        comments: Vec<Anchored<String>>,
+        #[serde(rename = "$pi")]
+        pi: Option<Anchored<String>>,
+        host: String,
+        port: u16,
+    }
+
+    let src = r#"<?xml version="1.0" encoding="UTF-8"?><Server><!-- do not change --><?reload delay="5"?><host>example.com</host><!-- staging --><port>8080</port><!-- end --></Server>"#;
+
+    let server = Server::deserialize(&mut comment_preserving_deserializer(src)).unwrap();
+    assert_eq!(
+        server,
+        Server {
+            comments: vec![
+                Anchored::new(" do not change ".to_string(), Some("host".to_string())),
+                Anchored::new(" staging ".to_string(), Some("port".to_string())),
+                Anchored::new(" end ".to_string(), None),
+            ],
+            pi: Some(Anchored::new(
+                r#"reload delay="5""#.to_string(),
+                Some("host".to_string())
+            )),
+            host: "example.com".to_string(),
+            port: 8080,
+        }
+    );
+    assert_eq!(to_string(&server).unwrap(), src);
+
+    // Plain strings are written back where the fields are declared
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Grouped {
+        #[serde(rename = "$comment", default)]
+        comments: Vec<String>,
+        #[serde(rename = "$pi")]
+        pi: Option<String>,
+        host: String,
+        port: u16,
+    }
+
+    let grouped = Grouped::deserialize(&mut comment_preserving_deserializer(src)).unwrap();
+    assert_eq!(grouped.pi.as_deref(), Some(r#"reload delay="5""#));
+    let should_be = r#"<?xml version="1.0" encoding="UTF-8"?><Grouped><!-- do not change --><!-- staging --><!-- end --><?reload delay="5"?><host>example.com</host><port>8080</port></Grouped>"#;
+    assert_eq!(to_string(&grouped).unwrap(), should_be);
+
+    // Skipped by default
+    let server: Server = from_str(src).unwrap();
+    assert!(server.comments.is_empty());
+    assert_eq!(server.pi, None);
+}
+
+#[test]
+fn round_trip_comments_from_reader() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Server {
+        #[serde(rename = "$comment", default)]
+        comments: Vec<String>,
+        host: String,
+    }
+
+    let src = r#"<?xml version="1.0" encoding="UTF-8"?><Server><!-- staging --><host>example.com</host></Server>"#;
+
+    let mut de =
+        serde_xml_rs::Deserializer::new_from_reader(src.as_bytes()).preserve_comments_and_pis(true);
+    let server = Server::deserialize(&mut de).unwrap();
+    assert_eq!(server.comments, vec![" staging ".to_string()]);
+    assert_eq!(to_string(&server).unwrap(), src);
+
+    // Text around a skipped comment is read as a whole
+    let src = r#"<Server><!-- staging --><host>example<!-- internal -->.com</host></Server>"#;
+    let server: Server = from_str(src).unwrap();
+    assert_eq!(server.host, "example.com");
+    assert!(server.comments.is_empty());
+}
+
+#[test]
+fn round_trip_comments_in_place() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    enum Entry {
+        #[serde(rename = "$comment")]
+        Comment(String),
+        #[serde(rename = "$pi")]
+        Pi(String),
+        #[serde(rename = "user")]
+        User(String),
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Users {
+        #[serde(rename = "$value")]
+        entries: Vec<Entry>,
+    }
+
+    let src = r#"<?xml version="1.0" encoding="UTF-8"?><Users><!-- admins --><user>root</user><?audit?><!-- guests --><user>nobody</user></Users>"#;
+
+    let users = Users::deserialize(&mut comment_preserving_deserializer(src)).unwrap();
+    assert_eq!(
+        users.entries,
+        vec![
+            Entry::Comment(" admins ".to_string()),
+            Entry::User("root".to_string()),
+            Entry::Pi("audit".to_string()),
+            Entry::Comment(" guests ".to_string()),
+            Entry::User("nobody".to_string()),
+        ]
+    );
+    assert_eq!(to_string(&users).unwrap(), src);
+}
+
+#[test]
+fn round_trip_document_prolog() {
+    use serde_xml_rs::{Document, Prolog, XmlDeclaration, XmlVersion};
+
+    let src = r#"<?xml version="1.1" encoding="UTF-8" standalone="no"?><?xml-stylesheet type="text/xsl" href="item.xsl"?><?generator?><Item><name>Banana</name><source>Store</source></Item>"#;
+
+    let document: Document<Item> = src.parse().unwrap();
+    assert_eq!(
+        document.prolog,
+        Prolog {
+            declaration: Some(XmlDeclaration {
+                version: XmlVersion::Version11,
+                encoding: Some("UTF-8".to_string()),
+                standalone: Some(false),
+            }),
+            doctype: None,
+            processing_instructions: vec![
+                r#"xml-stylesheet type="text/xsl" href="item.xsl""#.to_string(),
+                "generator".to_string(),
+            ],
+        }
+    );
+    assert_eq!(document.root.name, "Banana");
+    assert_eq!(document.to_string().unwrap(), src);
+
+    let without_declaration = Document {
+        prolog: Prolog {
+            declaration: None,
+            doctype: Some("Item SYSTEM \"item.dtd\"".to_string()),
+            processing_instructions: vec![],
+        },
+        root: document.root,
+    };
+    assert_eq!(
+        without_declaration.to_string().unwrap(),
+        r#"<!DOCTYPE Item SYSTEM "item.dtd"><Item><name>Banana</name><source>Store</source></Item>"#
+    );
+
+    // Told apart from a declaration with default values
+    let undeclared = r#"<Item><name>Banana</name><source>Store</source></Item>"#;
+    let document: Document<Item> = undeclared.parse().unwrap();
+    assert_eq!(document.prolog.declaration, None);
+    assert_eq!(document.to_string().unwrap(), undeclared);
+
+    let document: Document<Item> = format!("<?xml version='1.0'?>{}", undeclared)
+        .parse()
+        .unwrap();
+    assert_eq!(document.prolog.declaration, Some(XmlDeclaration::default()));
+
+    let with_doctype = r#"<?xml version="1.0" encoding="UTF-8"?><!-- catalog --><!DOCTYPE Item SYSTEM "item.dtd" [<!ELEMENT name (#PCDATA)>]><Item><name>Banana</name><source>Store</source></Item>"#;
+    let document: Document<Item> = with_doctype.parse().unwrap();
+    assert_eq!(
+        document.prolog.doctype.as_deref(),
+        Some(r#"Item SYSTEM "item.dtd" [<!ELEMENT name (#PCDATA)>]"#)
+    );
+    assert_eq!(
+        document.to_string().unwrap(),
+        r#"<?xml version="1.0" encoding="UTF-8"?><!DOCTYPE Item SYSTEM "item.dtd" [<!ELEMENT name (#PCDATA)>]><Item><name>Banana</name><source>Store</source></Item>"#
+    );
+}
+
+#[test]
+fn round_trip_legacy_encodings() {
+    use serde_xml_rs::{Document, Encoding, Serializer};
+
+    let latin1 = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><Item><name>Cr\xe8me br\xfbl\xe9e</name><source>Caf\xe9</source></Item>";
+    let document: Document<Item> = Document::from_reader(&latin1[..]).unwrap();
+    assert_eq!(document.root.name, "Crème brûlée");
+    let mut buffer = Vec::new();
+    document.to_writer(&mut buffer, false).unwrap();
+    assert_eq!(buffer, latin1.to_vec());
+
+    let cp1251 = b"<?xml version=\"1.0\" encoding=\"windows-1251\"?><Item><name>\xcf\xf0\xe8\xe2\xe5\xf2</name><source>\xab\xcc\xe8\xf0\xbb</source></Item>";
+    let item: Item = serde_xml_rs::from_reader(&cp1251[..]).unwrap();
+    assert_eq!(item.name, "Привет");
+    assert_eq!(item.source, "«Мир»");
+
+    // UTF-16 is detected from the byte order mark
+    let mut utf16 = Vec::new();
+    let mut ser = Serializer::new(&mut utf16, false).encoding(Encoding::Utf16Le);
+    item.serialize(&mut ser).unwrap();
+    assert_eq!(&utf16[..2], &[0xFF, 0xFE]);
+    let decoded = String::from_utf16(
+        &utf16[2..]
+            .chunks(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect::<Vec<_>>(),
+    )
+    .unwrap();
+    assert_eq!(
+        decoded,
+        r#"<?xml version="1.0" encoding="UTF-16"?><Item><name>Привет</name><source>«Мир»</source></Item>"#
+    );
+    let reparsed: Item = serde_xml_rs::from_reader(utf16.as_slice()).unwrap();
+    assert_eq!(reparsed, item);
+
+    // Characters missing from the output encoding become character references
+    let mut latin1 = Vec::new();
+    let mut ser = Serializer::new(&mut latin1, false).encoding(Encoding::Latin1);
+    item.serialize(&mut ser).unwrap();
+    let reparsed: Item = serde_xml_rs::from_reader(latin1.as_slice()).unwrap();
+    assert_eq!(reparsed, item);
+}
+
+#[test]
+fn decoded_strings_and_unsupported_encodings() {
+    use serde_xml_rs::{Encoding, Error, Serializer};
+    use std::collections::BTreeMap;
+
+    // A string is already decoded, whatever its declaration says
+    let item: Item = from_str(r#"<?xml version="1.0" encoding="windows-1251"?><Item><name>Привет</name><source>«Мир»</source></Item>"#).unwrap();
+    assert_eq!(item.name, "Привет");
+    let item: Item = from_str(r#"<?xml version="1.0" encoding="UTF-16"?><Item><name>Привет</name><source>«Мир»</source></Item>"#).unwrap();
+    assert_eq!(item.source, "«Мир»");
+
+    // Unsupported encodings are reported, even for a document that would read the same as UTF-8
+    let shift_jis = b"<?xml version=\"1.0\" encoding=\"Shift_JIS\"?><Item><name>Banana</name><source>Store</source></Item>";
+    match serde_xml_rs::from_reader::<_, Item>(&shift_jis[..]) {
+        Err(Error::UnsupportedEncoding { encoding }) => assert_eq!(encoding, "Shift_JIS"),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // ASCII has no upper half
+    let ascii = b"<?xml version=\"1.0\" encoding=\"US-ASCII\"?><Item><name>Caf\xe9</name><source>Store</source></Item>";
+    assert!(serde_xml_rs::from_reader::<_, Item>(&ascii[..]).is_err());
+    let mut buffer = Vec::new();
+    let mut ser = Serializer::new(&mut buffer, false).encoding(Encoding::Ascii);
+    Item {
+        name: "Café".to_string(),
+        source: "Store".to_string(),
+    }
+    .serialize(&mut ser)
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        r#"<?xml version="1.0" encoding="US-ASCII"?><Item><name>Caf&#xE9;</name><source>Store</source></Item>"#
+    );
+
+    // Character references are only written in text and attribute values
+    #[derive(Serialize)]
+    struct Note {
+        #[serde(rename = "$comment")]
+        comment: String,
+    }
+    let mut ser = Serializer::new(Vec::new(), false).encoding(Encoding::Latin1);
+    let result = Note {
+        comment: "Привет".to_string(),
+    }
+    .serialize(&mut ser);
+    assert!(matches!(
+        result,
+        Err(Error::UnencodableCharacter {
+            character: 'П', ..
+        })
+    ));
+
+    let names = BTreeMap::from([("имя".to_string(), "Banana".to_string())]);
+    let mut ser = Serializer::new(Vec::new(), false)
+        .encoding(Encoding::Latin1)
+        .root_name("names");
+    assert!(matches!(
+        names.serialize(&mut ser),
+        Err(Error::UnencodableCharacter {
+            character: 'и', ..
+        })
+    ));
+}
+
+#[test]
+fn round_trip_root_name_and_attributes() {
+    use serde_xml_rs::{ser::to_string_with_root, Root};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Channel {
+        #[serde(rename = "@id", alias = "id")]
+        id: u32,
+        enabled: bool,
+    }
+
+    let channel = Channel {
+        id: 101,
+        enabled: true,
+    };
+    let root = Root::new("ImageChannel", &channel)
+        .attr("version", "2.0")
+        .attr("xmlns", "http://www.hikvision.com/ver20/XMLSchema");
+    let xml = to_string(&root).unwrap();
+    assert_eq!(
+        xml,
+        r#"<?xml version="1.0" encoding="UTF-8"?><ImageChannel xmlns="http://www.hikvision.com/ver20/XMLSchema" version="2.0" id="101"><enabled>true</enabled></ImageChannel>"#
+    );
+    let reparsed: Channel = from_str(&xml).unwrap();
+    assert_eq!(reparsed, channel);
+
+    let item = Item {
+        name: "Banana".to_string(),
+        source: "Store".to_string(),
+    };
+    assert_eq!(
+        to_string_with_root(
+            "fruit",
+            &[("xmlns:f", "urn:fruit"), ("f:ripe", "yes")],
+            &item
+        )
+        .unwrap(),
+        r#"<?xml version="1.0" encoding="UTF-8"?><fruit xmlns:f="urn:fruit" f:ripe="yes"><name>Banana</name><source>Store</source></fruit>"#
+    );
+}
+
+#[test]
+fn round_trip_non_struct_roots() {
+    use serde::de::DeserializeOwned;
+    use serde_xml_rs::{Deserializer, Serializer};
+    use std::collections::BTreeMap;
+    use std::fmt::Debug;
+
+    fn round_trip<T>(value: &T, expected: &str)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let mut buffer = Vec::new();
+        let mut ser = Serializer::new(&mut buffer, false).root_name("root");
+        value.serialize(&mut ser).unwrap();
+        let xml = String::from_utf8(buffer).unwrap();
+        assert_eq!(
+            xml,
+            format!(r#"<?xml version="1.0" encoding="UTF-8"?>{}"#, expected)
+        );
+
+        let mut de = Deserializer::new_from_reader(xml.as_bytes()).root_name("root");
+        assert_eq!(&T::deserialize(&mut de).unwrap(), value);
+    }
+
+    let items = vec![
+        Item {
+            name: "Banana".to_string(),
+            source: "Store".to_string(),
+        },
+        Item {
+            name: "Apple".to_string(),
+            source: "Orchard".to_string(),
+        },
+    ];
+    round_trip(
+        &items,
+        "<root><Item><name>Banana</name><source>Store</source></Item><Item><name>Apple</name><source>Orchard</source></Item></root>",
+    );
+    round_trip(
+        &vec![1, 2, 3],
+        "<root><item>1</item><item>2</item><item>3</item></root>",
+    );
+    round_trip(&Vec::<u32>::new(), "<root />");
+    round_trip(&42u32, "<root>42</root>");
+    round_trip(&"text".to_string(), "<root>text</root>");
+    round_trip(
+        &Node::Identifier {
+            value: "foo".to_string(),
+            index: 5,
+        },
+        "<root><Identifier><value>foo</value><index>5</index></Identifier></root>",
+    );
+    round_trip(&Node::Boolean(true), "<root><Boolean>true</Boolean></root>");
+    round_trip(
+        &vec![Node::Boolean(false), Node::EOF],
+        "<root><Boolean>false</Boolean><EOF /></root>",
+    );
+
+    let mut map = BTreeMap::new();
+    map.insert("a".to_string(), 1);
+    map.insert("b".to_string(), 2);
+    round_trip(&map, "<root><a>1</a><b>2</b></root>");
+
+    // Without a root name, text and sequences aren't a document with a single root element
+    assert!(matches!(
+        to_string(&5u32),
+        Err(serde_xml_rs::Error::UnsupportedOperation { .. })
+    ));
+    assert!(matches!(
+        to_string(&items),
+        Err(serde_xml_rs::Error::UnsupportedOperation { .. })
+    ));
+    assert!(matches!(
+        to_string(&map),
+        Err(serde_xml_rs::Error::UnsupportedOperation { .. })
+    ));
+
+    // A root element with another name is rejected
+    let mut de = Deserializer::new_from_reader("<other />".as_bytes()).root_name("root");
+    assert!(u32::deserialize(&mut de).is_err());
+}
+
+#[test]
+fn round_trip_cdata() {
+    use serde_xml_rs::{CData, Text};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Post {
+        body: CData<String>,
+        summary: Text,
+        footer: Text,
+    }
+
+    let post = Post {
+        body: CData("<p>a]]>b</p>".to_string()),
+        summary: Text::CData("1 < 2".to_string()),
+        footer: Text::Plain("a & b".to_string()),
+    };
+    let xml = to_string(&post).unwrap();
+    assert_eq!(
+        xml,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Post><body><![CDATA[<p>a]]]]><![CDATA[>b</p>]]></body><summary><![CDATA[1 < 2]]></summary><footer>a &amp; b</footer></Post>"
+    );
+    assert_eq!(from_str::<Post>(&xml).unwrap(), post);
+
+    // Text mixing CDATA sections and character data is read as plain text
+    let post: Post = from_str(
+        "<Post><body><![CDATA[x]]></body><summary>1<![CDATA[ < 2]]></summary><footer /></Post>",
+    )
+    .unwrap();
+    assert_eq!(post.summary, Text::Plain("1 < 2".to_string()));
+    assert_eq!(post.footer, Text::Plain(String::new()));
+}
+
+#[test]
+fn round_trip_value_sequence_of_variants() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Point {
+        #[serde(rename = "@x")]
+        x: i32,
+        #[serde(rename = "@y")]
+        y: i32,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Step {
+        Move(Point),
+        Line(Point),
+        Close,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Path {
+        #[serde(rename = "$value")]
+        steps: Vec<Step>,
+    }
+
+    let path = Path {
+        steps: vec![
+            Step::Move(Point { x: 1, y: 2 }),
+            Step::Line(Point { x: 3, y: 4 }),
+            Step::Close,
+        ],
+    };
+    let should_be = r#"<?xml version="1.0" encoding="UTF-8"?><Path><move x="1" y="2" /><line x="3" y="4" /><close /></Path>"#;
+    assert_eq!(to_string(&path).unwrap(), should_be);
+    assert_eq!(from_str::<Path>(should_be).unwrap(), path);
+}
+
+#[test]
+fn round_trip_map_layouts() {
+    use serde_xml_rs::ser::to_string_with_config;
+    use serde_xml_rs::{Deserializer, Error, MapLayout, SerializerConfig};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Labels {
+        names: BTreeMap<String, u32>,
+    }
+
+    let labels = Labels {
+        names: vec![("a b".to_string(), 1), ("90".to_string(), 2)]
+            .into_iter()
+            .collect(),
+    };
+    let round_trip = |layout: MapLayout, expected: &str| {
+        let config = SerializerConfig::new()
+            .xml_declaration(false)
+            .map_layout(layout.clone());
+        let xml = to_string_with_config(&labels, &config).unwrap();
+        assert_eq!(xml, expected);
+        let mut de = Deserializer::new_from_reader(xml.as_bytes()).map_layout(layout);
+        assert_eq!(Labels::deserialize(&mut de).unwrap(), labels);
+    };
+
+    round_trip(
+        MapLayout::key_attribute("entry", "key"),
+        r#"<Labels><names><entry key="90">2</entry><entry key="a b">1</entry></names></Labels>"#,
+    );
+    round_trip(
+        MapLayout::key_value_elements("entry", "key", "value"),
+        "<Labels><names><entry><key>90</key><value>2</value></entry><entry><key>a b</key><value>1</value></entry></names></Labels>",
+    );
+
+    // Keys are checked when they are element names
+    assert!(matches!(
+        to_string(&labels),
+        Err(Error::InvalidName { name }) if name == "90"
+    ));
+    // Paths only name the items of struct fields
+    let mut paths = BTreeMap::new();
+    paths.insert("Items/Item".to_string(), 1);
+    assert!(matches!(
+        to_string(&Labels { names: paths }),
+        Err(Error::InvalidName { name }) if name == "Items/Item"
+    ));
+
+    // Flattened fields go through the map layout too, so they need the default one
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Owner {
+        #[serde(rename = "@owner", alias = "owner")]
+        owner: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Channel {
+        #[serde(flatten)]
+        owner: Owner,
+        name: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Studio {
+        channel: Channel,
+    }
+
+    let studio = Studio {
+        channel: Channel {
+            owner: Owner {
+                owner: "ops".to_string(),
+            },
+            name: "Front".to_string(),
+        },
+    };
+    let config = SerializerConfig::new()
+        .xml_declaration(false)
+        .map_layout(MapLayout::key_attribute("entry", "key"));
+    assert!(matches!(
+        to_string_with_config(&studio, &config),
+        Err(Error::UnsupportedOperation { .. })
+    ));
+    let xml = to_string(&studio).unwrap();
+    assert_eq!(
+        xml,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Studio><channel owner=\"ops\"><name>Front</name></channel></Studio>"
+    );
+    assert_eq!(from_str::<Studio>(&xml).unwrap(), studio);
+
+    // Maps of unknown length, which are not flattened structs, follow the layout
+    struct Evens(Vec<(String, u32)>);
+
+    impl Serialize for Evens {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_map(
+                self.0
+                    .iter()
+                    .filter(|(_, value)| value % 2 == 0)
+                    .map(|(key, value)| (key, value)),
+            )
+        }
+    }
+
+    let evens = Evens(vec![("a b".to_string(), 1), ("90".to_string(), 2)]);
+    let mut buffer = Vec::new();
+    let mut ser = serde_xml_rs::Serializer::with_config(&mut buffer, &config).root_name("evens");
+    evens.serialize(&mut ser).unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        r#"<evens><entry key="90">2</entry></evens>"#
+    );
+}
+
+#[test]
+fn round_trip_wrapped_lists() {
+    use serde_xml_rs::ser::to_string_with_config;
+    use serde_xml_rs::{EmptySequences, SerializerConfig};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Cell {
+        #[serde(rename = "@span", alias = "span")]
+        span: u32,
+        #[serde(rename = "$value")]
+        text: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Table {
+        #[serde(rename = "Columns/Column")]
+        columns: Vec<String>,
+        #[serde(rename = "Rows/Row/Cell")]
+        rows: Vec<Vec<Cell>>,
+        #[serde(rename = "Notes/Note")]
+        notes: Option<Vec<String>>,
+        #[serde(rename = "Tags/Tag")]
+        tags: Option<Vec<String>>,
+    }
+
+    let cell = |span, text: &str| Cell {
+        span,
+        text: text.to_string(),
+    };
+    let table = Table {
+        columns: vec!["a".to_string(), "b".to_string()],
+        rows: vec![vec![cell(1, "a1"), cell(1, "b1")], vec![cell(2, "ab2")]],
+        notes: None,
+        tags: Some(vec![]),
+    };
+    let should_be = concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><Table>"#,
+        r#"<Columns><Column>a</Column><Column>b</Column></Columns>"#,
+        r#"<Rows><Row><Cell span="1">a1</Cell><Cell span="1">b1</Cell></Row>"#,
+        r#"<Row><Cell span="2">ab2</Cell></Row></Rows>"#,
+        r#"<Tags /></Table>"#,
+    );
+    assert_eq!(to_string(&table).unwrap(), should_be);
+    assert_eq!(from_str::<Table>(should_be).unwrap(), table);
+
+    // Omitted along with its wrapper, the empty list is read back as `None`
+    let config = SerializerConfig::new().empty_sequences(EmptySequences::Omit);
+    let xml = to_string_with_config(&table, &config).unwrap();
+    assert!(!xml.contains("Tags"));
+    assert_eq!(from_str::<Table>(&xml).unwrap().tags, None);
+}
+
+#[test]
+fn round_trip_item_names() {
+    use serde_xml_rs::ser::to_string_with_config;
+    use serde_xml_rs::{Deserializer, ItemNames, SerializerConfig};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Channel {
+        #[serde(rename = "@id", alias = "id")]
+        id: u32,
+        name: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Device {
+        channel: Vec<Channel>,
+        presets: Vec<Vec<u8>>,
+    }
+
+    let device = Device {
+        channel: vec![
+            Channel {
+                id: 1,
+                name: "Front".to_string(),
+            },
+            Channel {
+                id: 2,
+                name: "Back".to_string(),
+            },
+        ],
+        presets: vec![vec![1, 2], vec![3]],
+    };
+    let round_trip = |item_names: ItemNames, should_be: &str| {
+        let config = SerializerConfig::new()
+            .xml_declaration(false)
+            .item_names(item_names.clone());
+        let xml = to_string_with_config(&device, &config).unwrap();
+        assert_eq!(xml, should_be);
+        let mut de = Deserializer::new_from_reader(xml.as_bytes()).item_names(item_names);
+        assert_eq!(Device::deserialize(&mut de).unwrap(), device);
+    };
+
+    let channels = r#"<channel id="1"><name>Front</name></channel><channel id="2"><name>Back</name></channel>"#;
+    let config = SerializerConfig::new().xml_declaration(false);
+    assert_eq!(
+        to_string_with_config(&device, &config).unwrap(),
+        format!("<Device>{channels}<presets>1</presets><presets>2</presets><presets>3</presets></Device>")
+    );
+    round_trip(
+        ItemNames::element("item"),
+        &format!(
+            "<Device><channel>{}</channel><presets><item><item>1</item><item>2</item></item><item><item>3</item></item></presets></Device>",
+            channels.replace("channel", "item")
+        ),
+    );
+    assert_eq!(
+        to_string_with_config(&device, &config.item_names(ItemNames::TypeName)).unwrap(),
+        format!(
+            "<Device><channel>{}</channel><presets><item><item>1</item><item>2</item></item><item><item>3</item></item></presets></Device>",
+            channels.replace("channel", "Channel")
+        )
+    );
+
+    // Nested sequences aside, items named after their type are read back
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Lineup {
+        channel: Vec<Channel>,
+    }
+
+    let lineup = Lineup {
+        channel: device.channel,
+    };
+    let config = SerializerConfig::new()
+        .xml_declaration(false)
+        .item_names(ItemNames::TypeName);
+    let xml = to_string_with_config(&lineup, &config).unwrap();
+    assert_eq!(
+        xml,
+        format!(
+            "<Lineup><channel>{}</channel></Lineup>",
+            channels.replace("channel", "Channel")
+        )
+    );
+    let mut de = Deserializer::new_from_reader(xml.as_bytes()).item_names(ItemNames::TypeName);
+    assert_eq!(Lineup::deserialize(&mut de).unwrap(), lineup);
+
+    // A field can choose its own item names, whatever the configured ones
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Mixed {
+        #[serde(with = "serde_xml_rs::items::type_name")]
+        channels: Vec<Channel>,
+        #[serde(with = "serde_xml_rs::items::field_name")]
+        port: Option<Vec<u16>>,
+        #[serde(rename = "presets/preset")]
+        presets: Vec<u8>,
+    }
+
+    let mixed = Mixed {
+        channels: lineup.channel,
+        port: Some(vec![80, 443]),
+        presets: vec![1],
+    };
+    let should_be = format!(
+        "<Mixed><channels>{}</channels><port>80</port><port>443</port><presets><preset>1</preset></presets></Mixed>",
+        channels.replace("channel", "Channel")
+    );
+    for item_names in [ItemNames::FieldName, ItemNames::TypeName] {
+        let config = SerializerConfig::new()
+            .xml_declaration(false)
+            .item_names(item_names.clone());
+        let xml = to_string_with_config(&mixed, &config).unwrap();
+        assert_eq!(xml, should_be);
+        let mut de = Deserializer::new_from_reader(xml.as_bytes()).item_names(item_names);
+        assert_eq!(Mixed::deserialize(&mut de).unwrap(), mixed);
+    }
+}
+
+#[test]
+fn round_trip_none_and_empty_sequences() {
+    use serde_xml_rs::ser::to_string_with_config;
+    use serde_xml_rs::{Deserializer, EmptySequences, NoneValues, SerializerConfig};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Schedule {
+        start: Option<u32>,
+        #[serde(default)]
+        days: Vec<String>,
+        #[serde(default, rename = "Holidays/Holiday")]
+        holidays: Vec<String>,
+        slots: Vec<Option<u32>>,
+    }
+
+    let schedule = Schedule {
+        start: None,
+        days: vec![],
+        holidays: vec![],
+        slots: vec![Some(1), None, Some(3)],
+    };
+    let round_trip = |none_values, empty_sequences, should_be: &str| {
+        let config = SerializerConfig::new()
+            .xml_declaration(false)
+            .none_values(none_values)
+            .empty_sequences(empty_sequences);
+        let xml = to_string_with_config(&schedule, &config).unwrap();
+        assert_eq!(xml, should_be);
+        let mut de = Deserializer::new_from_reader(xml.as_bytes())
+            .none_values(none_values)
+            .empty_sequences(empty_sequences);
+        assert_eq!(Schedule::deserialize(&mut de).unwrap(), schedule);
+    };
+
+    round_trip(
+        NoneValues::EmptyElement,
+        EmptySequences::EmptyElement,
+        "<Schedule><start /><days /><Holidays /><slots>1</slots><slots /><slots>3</slots></Schedule>",
+    );
+    round_trip(
+        NoneValues::XsiNil,
+        EmptySequences::Omit,
+        concat!(
+            r#"<Schedule><start xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:nil="true" />"#,
+            r#"<slots>1</slots><slots xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:nil="true" />"#,
+            r#"<slots>3</slots></Schedule>"#,
+        ),
+    );
+
+    // The default policies drop the `None` item, and keep the empty wrapper
+    let config = SerializerConfig::new().xml_declaration(false);
+    assert_eq!(
+        to_string_with_config(&schedule, &config).unwrap(),
+        "<Schedule><Holidays /><slots>1</slots><slots>3</slots></Schedule>"
+    );
+}
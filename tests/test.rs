@@ -2,7 +2,7 @@ mod common;
 
 use common::init_logger;
 use serde::Deserialize;
-use serde_xml_rs::{from_str, Deserializer};
+use serde_xml_rs::{from_str, Deserializer, Error};
 
 #[derive(Debug, Deserialize, PartialEq)]
 struct Item {
@@ -378,3 +378,605 @@ fn nested_collection_repeated_elements() {
 
     assert_eq!(should_be, actual);
 }
+
+#[test]
+fn strict_root_accepts_renamed_struct() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename = "item")]
+    struct RenamedItem {
+        name: String,
+    }
+
+    init_logger();
+
+    let s = r##"<item name="hello" />"##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).strict_root(true);
+    let item = RenamedItem::deserialize(&mut de).unwrap();
+
+    assert_eq!(
+        item,
+        RenamedItem {
+            name: "hello".to_string()
+        }
+    );
+}
+
+#[test]
+fn strict_root_rejects_mismatched_root() {
+    init_logger();
+
+    let s = r##"
+        <project name="my_project">
+            <item name="hello1" source="world1.rs" />
+        </project>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).strict_root(true);
+    match Item::deserialize(&mut de) {
+        Err(Error::UnexpectedRoot { expected, found }) => {
+            assert_eq!(expected, "Item");
+            assert_eq!(found, "project");
+        }
+        other => panic!("expected a root mismatch, got {:?}", other),
+    }
+}
+
+#[test]
+fn strict_root_ignores_nested_elements() {
+    init_logger();
+
+    let s = r##"
+        <Project name="my_project">
+            <item name="hello1" source="world1.rs" />
+        </Project>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).strict_root(true);
+    let project = Project::deserialize(&mut de).unwrap();
+
+    assert_eq!(project.items.len(), 1);
+}
+
+#[test]
+fn peek_root_name_reports_namespace() {
+    init_logger();
+
+    let s = r##"<x:item xmlns:x="urn:items" name="hello" source="world.rs" />"##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes());
+    let root = de.peek_root_name().unwrap();
+    assert_eq!(root.local_name, "item");
+    assert_eq!(root.prefix.as_deref(), Some("x"));
+    assert_eq!(root.namespace.as_deref(), Some("urn:items"));
+
+    let item = Item::deserialize(&mut de).unwrap();
+    assert_eq!(item.name, "hello");
+}
+
+#[test]
+fn strict_mode_accepts_conforming_document() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Document {
+        id: String,
+        header: String,
+        section: Vec<String>,
+        footer: String,
+    }
+
+    init_logger();
+
+    let s = r##"
+        <document id="1">
+            <header>A header</header>
+            <section>First section</section>
+            <section>Second section</section>
+            <footer>The footer</footer>
+        </document>
+    "##;
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).strict_mode(true);
+    let document = Document::deserialize(&mut de).unwrap();
+
+    assert_eq!(document.section.len(), 2);
+}
+
+#[test]
+fn deny_unknown_attributes() {
+    init_logger();
+
+    let s = r##"<item name="hello" source="world.rs" version="2" />"##;
+
+    let item: Item = from_str(s).unwrap();
+    assert_eq!(item.name, "hello");
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).deny_unknown_attributes(true);
+    match Item::deserialize(&mut de) {
+        Err(Error::UnknownAttribute { name, element }) => {
+            assert_eq!(name, "version");
+            assert_eq!(element, "item");
+        }
+        other => panic!("expected an unknown attribute error, got {:?}", other),
+    }
+}
+
+#[test]
+fn deny_unexpected_text() {
+    init_logger();
+
+    let s = r##"<item name="hello">stray<source>world.rs</source></item>"##;
+
+    let item: Item = from_str(s).unwrap();
+    assert_eq!(item.source, "world.rs");
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).deny_unexpected_text(true);
+    match Item::deserialize(&mut de) {
+        Err(Error::UnexpectedText { element }) => assert_eq!(element, "item"),
+        other => panic!("expected an unexpected text error, got {:?}", other),
+    }
+}
+
+#[test]
+fn enforce_element_order() {
+    init_logger();
+
+    let s = r##"<item><source>world.rs</source><name>hello</name></item>"##;
+
+    let item: Item = from_str(s).unwrap();
+    assert_eq!(item.name, "hello");
+
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).enforce_element_order(true);
+    match Item::deserialize(&mut de) {
+        Err(Error::OutOfOrderElement { name, after }) => {
+            assert_eq!(name, "name");
+            assert_eq!(after, "source");
+        }
+        other => panic!("expected an out of order error, got {:?}", other),
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+enum Shade {
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Palette {
+    #[serde(rename = "@shade", alias = "shade")]
+    shade: Option<Shade>,
+    name: String,
+    color: Vec<Item>,
+}
+
+#[test]
+fn schema_and_sample_for_type() {
+    init_logger();
+
+    let schema = serde_xml_rs::schema_for::<Palette>().unwrap();
+
+    assert_eq!(
+        schema.to_xsd().unwrap(),
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" elementFormDefault="qualified">
+  <xs:element name="Palette" type="Palette" />
+  <xs:simpleType name="Shade">
+    <xs:restriction base="xs:string">
+      <xs:enumeration value="Light" />
+      <xs:enumeration value="Dark" />
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:complexType name="Item">
+    <xs:sequence>
+      <xs:element name="name" type="xs:string" />
+      <xs:element name="source" type="xs:string" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="Palette">
+    <xs:sequence>
+      <xs:element name="name" type="xs:string" />
+      <xs:element name="color" type="Item" minOccurs="0" maxOccurs="unbounded" />
+    </xs:sequence>
+    <xs:attribute name="shade" type="Shade" use="optional" />
+  </xs:complexType>
+</xs:schema>"#
+    );
+
+    let sample = schema.sample().unwrap();
+    assert_eq!(
+        sample,
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<Palette shade="Light">
+  <name>string</name>
+  <color>
+    <name>string</name>
+    <source>string</source>
+  </color>
+</Palette>"#
+    );
+
+    let palette: Palette = from_str(&sample).unwrap();
+    assert_eq!(
+        palette,
+        Palette {
+            shade: Some(Shade::Light),
+            name: "string".to_string(),
+            color: vec![Item {
+                name: "string".to_string(),
+                source: "string".to_string(),
+            }],
+        }
+    );
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Grid {
+    #[serde(rename = "Colors/Color")]
+    colors: Vec<Item>,
+    #[serde(rename = "Rows/Row/Cell")]
+    rows: Vec<Vec<u32>>,
+}
+
+#[test]
+fn schema_and_sample_for_path_fields() {
+    init_logger();
+
+    let schema = serde_xml_rs::schema_for::<Grid>().unwrap();
+
+    assert_eq!(
+        schema.to_xsd().unwrap(),
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" elementFormDefault="qualified">
+  <xs:element name="Grid" type="Grid" />
+  <xs:complexType name="Item">
+    <xs:sequence>
+      <xs:element name="name" type="xs:string" />
+      <xs:element name="source" type="xs:string" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="Grid">
+    <xs:sequence>
+      <xs:element name="Colors" minOccurs="0">
+        <xs:complexType>
+          <xs:sequence>
+            <xs:element name="Color" type="Item" minOccurs="0" maxOccurs="unbounded" />
+          </xs:sequence>
+        </xs:complexType>
+      </xs:element>
+      <xs:element name="Rows" minOccurs="0">
+        <xs:complexType>
+          <xs:sequence>
+            <xs:element name="Row" minOccurs="0" maxOccurs="unbounded">
+              <xs:complexType>
+                <xs:sequence>
+                  <xs:element name="Cell" type="xs:unsignedInt" minOccurs="0" maxOccurs="unbounded" />
+                </xs:sequence>
+              </xs:complexType>
+            </xs:element>
+          </xs:sequence>
+        </xs:complexType>
+      </xs:element>
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>"#
+    );
+
+    let sample = schema.sample().unwrap();
+    assert_eq!(
+        sample,
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<Grid>
+  <Colors>
+    <Color>
+      <name>string</name>
+      <source>string</source>
+    </Color>
+  </Colors>
+  <Rows>
+    <Row>
+      <Cell>0</Cell>
+    </Row>
+  </Rows>
+</Grid>"#
+    );
+
+    let grid: Grid = from_str(&sample).unwrap();
+    assert_eq!(
+        grid,
+        Grid {
+            colors: vec![Item {
+                name: "string".to_string(),
+                source: "string".to_string(),
+            }],
+            rows: vec![vec![0]],
+        }
+    );
+}
+
+const ORDER_XSD: &str = r##"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+        <xs:element name="order" type="Order" />
+        <xs:complexType name="Order">
+            <xs:sequence>
+                <xs:element name="sku" type="Sku" maxOccurs="unbounded" />
+                <xs:element name="quantity" minOccurs="0">
+                    <xs:simpleType>
+                        <xs:restriction base="xs:int">
+                            <xs:minInclusive value="1" />
+                            <xs:maxInclusive value="100" />
+                        </xs:restriction>
+                    </xs:simpleType>
+                </xs:element>
+            </xs:sequence>
+            <xs:attribute name="id" type="xs:unsignedInt" use="required" />
+        </xs:complexType>
+        <xs:simpleType name="Sku">
+            <xs:restriction base="xs:string">
+                <xs:pattern value="[A-Z]{3}-\d+" />
+            </xs:restriction>
+        </xs:simpleType>
+    </xs:schema>
+"##;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Order {
+    id: u32,
+    sku: Vec<String>,
+    quantity: Option<i32>,
+}
+
+fn validate_order(s: &str) -> Result<Order, Error> {
+    let schema = ORDER_XSD.parse().unwrap();
+    let mut de = Deserializer::new_from_reader(s.as_bytes()).validate(schema);
+    Order::deserialize(&mut de)
+}
+
+#[test]
+fn validate_against_xsd() {
+    init_logger();
+
+    let order = validate_order(
+        r##"<order id="7"><sku>ABC-1</sku><sku>XYZ-22</sku><quantity>3</quantity></order>"##,
+    )
+    .unwrap();
+    assert_eq!(
+        order,
+        Order {
+            id: 7,
+            sku: vec!["ABC-1".to_string(), "XYZ-22".to_string()],
+            quantity: Some(3),
+        }
+    );
+
+    let violations = [
+        (r##"<order><sku>ABC-1</sku></order>"##, "/order/@id"),
+        (r##"<order id="x"><sku>ABC-1</sku></order>"##, "/order/@id"),
+        (
+            r##"<order id="7"><sku>ABC-1</sku><sku>abc</sku></order>"##,
+            "/order/sku[2]",
+        ),
+        (
+            r##"<order id="7"><sku>ABC-1</sku><quantity>101</quantity></order>"##,
+            "/order/quantity",
+        ),
+        (
+            r##"<order id="7"><quantity>1</quantity><sku>ABC-1</sku></order>"##,
+            "/order",
+        ),
+        (
+            r##"<order id="7"><sku>ABC-1</sku><note /></order>"##,
+            "/order/note",
+        ),
+        (r##"<purchase id="7" />"##, "/purchase"),
+    ];
+    for (s, expected_path) in violations.iter() {
+        match validate_order(s) {
+            Err(Error::Validation { path, .. }) => assert_eq!(path, *expected_path, "{}", s),
+            other => panic!("expected a validation error for {}, got {:?}", s, other),
+        }
+    }
+}
+
+#[test]
+fn validate_sample_against_generated_schema() {
+    init_logger();
+
+    let schema = serde_xml_rs::schema_for::<Palette>().unwrap();
+    let xsd = schema.to_xsd().unwrap().parse().unwrap();
+    let sample = schema.sample().unwrap();
+
+    let mut de = Deserializer::new_from_reader(sample.as_bytes()).validate(xsd);
+    assert!(Palette::deserialize(&mut de).is_ok());
+}
+
+#[test]
+fn generate_types_from_xsd() {
+    init_logger();
+
+    let code = serde_xml_rs::codegen::from_xsd(&ORDER_XSD.parse().unwrap()).unwrap();
+    assert!(code.contains("#[serde(rename = \"order\")]\npub struct Order {"));
+    assert!(code.contains("    #[serde(rename = \"@id\", alias = \"id\")]\n    pub id: u32,"));
+    assert!(code.contains("    pub sku: Vec<String>,"));
+    assert!(code.contains("    pub quantity: Option<i32>,"));
+}
+
+/// The types `generate_types_from_samples` expects, compiled here to check they build.
+#[path = "codegen/order.rs"]
+mod generated_order;
+
+#[test]
+fn generate_types_from_samples() {
+    use serde_xml_rs::NoneValues;
+
+    init_logger();
+
+    let samples = [
+        r##"<order id="7" rush="true"><line>apple</line><line>pear</line><quantity>3</quantity><note>urgent</note></order>"##,
+        r##"<order id="8"><quantity /><note /></order>"##,
+    ];
+    let code = serde_xml_rs::codegen::from_samples(samples.iter().map(|s| s.as_bytes())).unwrap();
+    assert_eq!(code, include_str!("codegen/order.rs"));
+
+    let read = |s: &str| {
+        let mut de =
+            Deserializer::new_from_reader(s.as_bytes()).none_values(NoneValues::EmptyElement);
+        generated_order::Order::deserialize(&mut de).unwrap()
+    };
+    assert_eq!(
+        read(samples[0]),
+        generated_order::Order {
+            id: 7,
+            rush: Some(true),
+            line: vec!["apple".to_string(), "pear".to_string()],
+            quantity: Some(3),
+            note: Some("urgent".to_string()),
+        }
+    );
+    assert_eq!(
+        read(samples[1]),
+        generated_order::Order {
+            id: 8,
+            rush: None,
+            line: vec![],
+            quantity: None,
+            note: None,
+        }
+    );
+}
+
+/// The types `generate_types_for_keywords` expects.
+#[path = "codegen/keywords.rs"]
+mod generated_keywords;
+
+#[test]
+fn generate_types_for_keywords() {
+    init_logger();
+
+    let sample = r##"<module><self><z>1</z></self><crate>c</crate><type>t</type></module>"##;
+    let code = serde_xml_rs::codegen::from_samples(vec![sample.as_bytes()]).unwrap();
+    assert_eq!(code, include_str!("codegen/keywords.rs"));
+
+    let module: generated_keywords::Module = from_str(sample).unwrap();
+    assert_eq!(
+        module,
+        generated_keywords::Module {
+            self_: generated_keywords::Self_ { z: 1 },
+            crate_: "c".to_string(),
+            r#type: "t".to_string(),
+        }
+    );
+}
+
+/// The types `generate_types_for_repeated_choice` expects.
+#[path = "codegen/cfg.rs"]
+mod generated_cfg;
+
+#[test]
+fn generate_types_for_repeated_choice() {
+    use generated_cfg::{Cfg, CfgContent};
+
+    init_logger();
+
+    let xsd = r##"
+        <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+            <xs:element name="cfg">
+                <xs:complexType>
+                    <xs:sequence>
+                        <xs:element name="mode" type="xs:string" />
+                        <xs:choice maxOccurs="unbounded">
+                            <xs:element name="a" type="xs:int" />
+                            <xs:element name="b" type="xs:string" />
+                        </xs:choice>
+                    </xs:sequence>
+                    <xs:attribute name="id" type="xs:unsignedInt" use="required" />
+                </xs:complexType>
+            </xs:element>
+        </xs:schema>
+    "##;
+    let code = serde_xml_rs::codegen::from_xsd(&xsd.parse().unwrap()).unwrap();
+    assert_eq!(code, include_str!("codegen/cfg.rs"));
+
+    let src = r##"<cfg id="1"><mode>on</mode><a>1</a><b>x</b><a>2</a></cfg>"##;
+    let cfg: Cfg = from_str(src).unwrap();
+    assert_eq!(
+        cfg,
+        Cfg {
+            id: 1,
+            value: vec![
+                CfgContent::Mode("on".to_string()),
+                CfgContent::A(1),
+                CfgContent::B("x".to_string()),
+                CfgContent::A(2),
+            ],
+        }
+    );
+}
+
+#[test]
+fn duplicate_element_policies() {
+    use serde_xml_rs::de::DuplicateElements;
+
+    init_logger();
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Channel {
+        id: u32,
+        name: String,
+        #[serde(default)]
+        tag: Vec<String>,
+    }
+
+    let s = r##"<Channel>
+        <id>1</id>
+        <tag>a</tag>
+        <name>Front</name>
+        <tag>b</tag>
+        <id>2</id>
+    </Channel>"##;
+    let read = |policy| {
+        let mut de = Deserializer::new_from_reader(s.as_bytes()).duplicate_elements(policy);
+        Channel::deserialize(&mut de)
+    };
+
+    let channel = read(DuplicateElements::FirstWins).unwrap();
+    assert_eq!((channel.id, channel.tag), (1, vec!["a".to_string()]));
+
+    let channel = read(DuplicateElements::LastWins).unwrap();
+    assert_eq!((channel.id, channel.tag), (2, vec!["b".to_string()]));
+
+    // Adjacent elements are all read into a sequence, and only the last one is kept otherwise
+    let adjacent = r##"<Channel>
+        <id>1</id>
+        <id>2</id>
+        <name>Front</name>
+        <name>Rear</name>
+        <tag>a</tag>
+        <tag>b</tag>
+    </Channel>"##;
+    let mut de = Deserializer::new_from_reader(adjacent.as_bytes())
+        .duplicate_elements(DuplicateElements::LastWins);
+    assert_eq!(
+        Channel::deserialize(&mut de).unwrap(),
+        Channel {
+            id: 2,
+            name: "Rear".to_string(),
+            tag: vec!["a".to_string(), "b".to_string()],
+        }
+    );
+
+    match read(DuplicateElements::Error) {
+        Err(Error::DuplicateElement {
+            name,
+            element,
+            position,
+        }) => {
+            assert_eq!((name.as_str(), element.as_str()), ("tag", "Channel"));
+            assert_eq!(position.row, 4);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // Without a policy, serde reports the repeated field
+    let mut de = Deserializer::new_from_reader(s.as_bytes());
+    assert!(Channel::deserialize(&mut de).is_err());
+}